@@ -0,0 +1,15 @@
+CREATE TABLE entry_reminders (
+    id VARCHAR(255) PRIMARY KEY,
+    entry_id VARCHAR(255) NOT NULL REFERENCES entries (id) ON DELETE CASCADE,
+    "offset" TEXT NOT NULL,
+    channel TEXT NOT NULL,
+    sent TEXT NOT NULL DEFAULT 'false'
+);
+
+CREATE INDEX entry_reminders_entry_id_idx ON entry_reminders (entry_id);
+
+-- carry over the existing single reminders
+INSERT INTO entry_reminders (id, entry_id, "offset", channel, sent)
+SELECT gen_random_uuid()::text, id, reminder, 'email', 'false'
+FROM entries
+WHERE reminder IS NOT NULL;
@@ -0,0 +1,3 @@
+ALTER TABLE entry_reminders ADD COLUMN sent TEXT NOT NULL DEFAULT 'false';
+
+DROP TABLE notifications;
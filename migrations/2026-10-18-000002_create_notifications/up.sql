@@ -0,0 +1,18 @@
+CREATE TABLE notifications (
+    id VARCHAR(255) PRIMARY KEY,
+    dedupe_key TEXT NOT NULL UNIQUE,
+    entry_id VARCHAR(255) NOT NULL REFERENCES entries (id) ON DELETE CASCADE,
+    user_id TEXT NOT NULL,
+    kind TEXT NOT NULL,
+    channel TEXT NOT NULL,
+    scheduled TIMESTAMP NOT NULL,
+    status TEXT NOT NULL,
+    created TIMESTAMP NOT NULL,
+    processed TIMESTAMP
+);
+
+CREATE INDEX notifications_status_scheduled_idx ON notifications (status, scheduled);
+CREATE INDEX notifications_entry_id_idx ON notifications (entry_id);
+
+-- delivery state now lives in the notification log
+ALTER TABLE entry_reminders DROP COLUMN sent;
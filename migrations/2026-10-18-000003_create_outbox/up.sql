@@ -0,0 +1,16 @@
+CREATE TABLE outbox (
+    id VARCHAR(255) PRIMARY KEY,
+    notification_id VARCHAR(255) REFERENCES notifications (id) ON DELETE SET NULL,
+    recipient_name TEXT NOT NULL,
+    recipient_email TEXT NOT NULL,
+    subject TEXT NOT NULL,
+    body TEXT NOT NULL,
+    status TEXT NOT NULL,
+    attempts INTEGER NOT NULL DEFAULT 0,
+    next_attempt TIMESTAMP NOT NULL,
+    last_error TEXT,
+    created TIMESTAMP NOT NULL,
+    updated TIMESTAMP NOT NULL
+);
+
+CREATE INDEX outbox_status_next_attempt_idx ON outbox (status, next_attempt);
@@ -0,0 +1,6 @@
+UPDATE entry_reminders SET channel = 'email' WHERE channel = 'default';
+
+ALTER TABLE outbox DROP COLUMN channel;
+ALTER TABLE outbox DROP COLUMN user_id;
+
+DROP TABLE user_channels;
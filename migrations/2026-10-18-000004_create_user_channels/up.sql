@@ -0,0 +1,15 @@
+CREATE TABLE user_channels (
+    id VARCHAR(255) PRIMARY KEY,
+    user_id TEXT NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    channel TEXT NOT NULL,
+    enabled TEXT NOT NULL DEFAULT 'true',
+    url TEXT,
+    headers TEXT,
+    UNIQUE (user_id, channel)
+);
+
+ALTER TABLE outbox ADD COLUMN user_id TEXT;
+ALTER TABLE outbox ADD COLUMN channel TEXT NOT NULL DEFAULT 'email';
+
+-- reminders made before there were channels meant every channel, which is now "default"
+UPDATE entry_reminders SET channel = 'default' WHERE channel = 'email';
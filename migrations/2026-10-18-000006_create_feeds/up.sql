@@ -0,0 +1,6 @@
+CREATE TABLE feeds (
+    id VARCHAR(255) PRIMARY KEY,
+    user_id TEXT NOT NULL UNIQUE REFERENCES users (id) ON DELETE CASCADE,
+    token TEXT NOT NULL UNIQUE,
+    created TIMESTAMP NOT NULL DEFAULT NOW()
+);
@@ -0,0 +1,13 @@
+CREATE TABLE idempotency_keys (
+    id VARCHAR(255) PRIMARY KEY,
+    scope TEXT NOT NULL,
+    idempotency_key TEXT NOT NULL,
+    request_hash TEXT NOT NULL,
+    response_status INTEGER,
+    response_headers TEXT,
+    response_body TEXT,
+    created TIMESTAMP NOT NULL DEFAULT NOW(),
+    UNIQUE (scope, idempotency_key)
+);
+
+CREATE INDEX idempotency_keys_created ON idempotency_keys (created);
@@ -0,0 +1,11 @@
+CREATE TABLE entry_events (
+    id BIGSERIAL PRIMARY KEY,
+    entry_id VARCHAR(255) NOT NULL,
+    user_id TEXT NOT NULL,
+    event TEXT NOT NULL,
+    payload TEXT NOT NULL,
+    created TIMESTAMP NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX entry_events_user_id ON entry_events (user_id, id);
+CREATE INDEX entry_events_created ON entry_events (created);
@@ -0,0 +1,2 @@
+DROP TABLE entry_changes;
+DROP SEQUENCE entry_change_seq;
@@ -0,0 +1,15 @@
+CREATE SEQUENCE entry_change_seq;
+
+CREATE TABLE entry_changes (
+    entry_id VARCHAR(255) PRIMARY KEY,
+    user_id TEXT NOT NULL,
+    seq BIGINT NOT NULL DEFAULT nextval('entry_change_seq'),
+    deleted BOOLEAN NOT NULL DEFAULT FALSE,
+    changed TIMESTAMP NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX entry_changes_seq ON entry_changes (seq);
+CREATE INDEX entry_changes_user_id ON entry_changes (user_id, seq);
+
+INSERT INTO entry_changes (entry_id, user_id, changed)
+SELECT id, user_id, updated FROM entries ORDER BY updated;
@@ -0,0 +1,2 @@
+DROP INDEX entries_deleted_at;
+ALTER TABLE entries DROP COLUMN deleted_at;
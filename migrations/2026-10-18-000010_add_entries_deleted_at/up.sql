@@ -0,0 +1,4 @@
+-- entries in the trash; hidden from everything but the trash endpoints until restored or purged
+ALTER TABLE entries ADD COLUMN deleted_at TIMESTAMP;
+
+CREATE INDEX entries_deleted_at ON entries (deleted_at);
@@ -0,0 +1,13 @@
+CREATE TABLE entry_revisions (
+    id BIGSERIAL PRIMARY KEY,
+    entry_id VARCHAR(255) NOT NULL REFERENCES entries (id) ON DELETE CASCADE,
+    user_id TEXT NOT NULL,
+    actor TEXT NOT NULL,
+    action TEXT NOT NULL,
+    fields TEXT NOT NULL,
+    old_values TEXT NOT NULL,
+    new_values TEXT NOT NULL,
+    created TIMESTAMP NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX entry_revisions_entry_id ON entry_revisions (entry_id, id);
@@ -0,0 +1,15 @@
+CREATE TABLE tags (
+    id VARCHAR(255) PRIMARY KEY,
+    user_id TEXT NOT NULL,
+    name TEXT NOT NULL,
+    created TIMESTAMP NOT NULL DEFAULT NOW(),
+    UNIQUE (user_id, name)
+);
+
+CREATE TABLE entry_tags (
+    entry_id VARCHAR(255) NOT NULL REFERENCES entries (id) ON DELETE CASCADE,
+    tag_id VARCHAR(255) NOT NULL REFERENCES tags (id) ON DELETE CASCADE,
+    PRIMARY KEY (entry_id, tag_id)
+);
+
+CREATE INDEX entry_tags_tag_id ON entry_tags (tag_id);
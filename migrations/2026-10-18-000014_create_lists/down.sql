@@ -0,0 +1,3 @@
+DROP INDEX entries_list_id;
+ALTER TABLE entries DROP COLUMN list_id;
+DROP TABLE lists;
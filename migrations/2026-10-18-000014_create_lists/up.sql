@@ -0,0 +1,16 @@
+CREATE TABLE lists (
+    id VARCHAR(255) PRIMARY KEY,
+    user_id TEXT NOT NULL,
+    name TEXT NOT NULL,
+    color TEXT NOT NULL,
+    default_reminder TEXT,
+    sort_order INTEGER NOT NULL,
+    created TIMESTAMP NOT NULL DEFAULT NOW(),
+    updated TIMESTAMP NOT NULL DEFAULT NOW(),
+    UNIQUE (user_id, name)
+);
+
+-- entries without a list are in the inbox; deleting a list moves whatever is left in it there
+ALTER TABLE entries ADD COLUMN list_id VARCHAR(255) REFERENCES lists (id) ON DELETE SET NULL;
+
+CREATE INDEX entries_list_id ON entries (list_id);
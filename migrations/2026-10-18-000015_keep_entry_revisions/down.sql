@@ -0,0 +1,2 @@
+DELETE FROM entry_revisions WHERE entry_id NOT IN (SELECT id FROM entries);
+ALTER TABLE entry_revisions ADD CONSTRAINT entry_revisions_entry_id_fkey FOREIGN KEY (entry_id) REFERENCES entries (id) ON DELETE CASCADE;
@@ -0,0 +1,2 @@
+-- an entry's history outlives the entry: revisions stay when it is purged from the trash, or replaced under the same id
+ALTER TABLE entry_revisions DROP CONSTRAINT entry_revisions_entry_id_fkey;
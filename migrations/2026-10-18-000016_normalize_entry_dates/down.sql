@@ -0,0 +1,2 @@
+-- the padding the dates were written with is not kept, so there is nothing to restore
+SELECT 1;
@@ -0,0 +1,4 @@
+-- "06" and "6" are the same month: date parts are stored as numbers without padding, so that they compare and filter as equal
+UPDATE entries SET year = CAST(CAST(year AS INTEGER) AS TEXT) WHERE year ~ '^0[0-9]+$';
+UPDATE entries SET month = CAST(CAST(month AS INTEGER) AS TEXT) WHERE month ~ '^0[0-9]+$';
+UPDATE entries SET day = CAST(CAST(day AS INTEGER) AS TEXT) WHERE day ~ '^0[0-9]+$';
@@ -0,0 +1 @@
+ALTER TABLE entry_events DROP COLUMN previous_list;
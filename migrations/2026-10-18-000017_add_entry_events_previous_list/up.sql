@@ -0,0 +1,2 @@
+-- the list an update moved the entry out of ("inbox" for none), so that a stream following that list is sent the move when it resumes
+ALTER TABLE entry_events ADD COLUMN previous_list TEXT;
@@ -4,9 +4,23 @@
     10/17/23
     © Matthew Bentivegna
 *************************************************************************/
+use std::{
+    collections::HashMap,
+    sync::Arc,
+};
 use actix_web::{
     HttpRequest,
     HttpResponse,
+    dev::Service,
+    http::{
+        StatusCode,
+        header::{
+            self,
+            HeaderMap,
+            HeaderName,
+            HeaderValue,
+        },
+    },
     web::{
         self,
         Data,
@@ -18,65 +32,318 @@ use base64::{
     engine::general_purpose
 };
 use crate::repository::database::Database;
+use crate::api::error::ApiError;
+use crate::api::socket;
+use crate::file::options::Options;
+use crate::timer::target::TargetResolver;
+use crate::events::events;
+use crate::import::import::import_items;
+use crate::openapi::openapi::openapi;
+use crate::patch::patch::{
+    PatchFormat,
+    patch_document,
+    ACCEPT_PATCH,
+};
+use crate::export::export::{
+    ExportCursor,
+    export_header,
+    export_row,
+    audit_export_header,
+    audit_export_row,
+};
+use crate::calendar::{
+    caldav,
+    ical::{
+        self,
+        Component,
+    },
+};
 use crate::models::{
+    entry_v2::entry_v2_successor,
+    revision::{
+        EntryRevision,
+        RevisionRead,
+        entry_revert_patch,
+    },
+    audit::{
+        AuditEventWrite,
+        AuditLogins,
+        AuditParams,
+        audit_filter,
+        AUDIT_ENTRIES_DELETED,
+        AUDIT_LOGIN,
+        AUDIT_LOGIN_FAILED,
+        AUDIT_PAGE_SIZE,
+        AUDIT_PASSWORD_CHANGED,
+        AUDIT_USER_CREATED,
+        AUDIT_USER_DELETED,
+    },
+    sync::{
+        SyncParams,
+        SyncResponse,
+        sync_changes,
+        sync_token_parse,
+        SYNC_PAGE_SIZE,
+    },
+    entry_event::{
+        entry_event_message,
+        entry_event_reset_message,
+        last_event_id_parse,
+        CONTENT_TYPE_EVENT_STREAM,
+        LAST_EVENT_ID_HEADER,
+    },
     user::{
         User,
         UserPatch,
+        UserDocument,
+        user_from_document,
     },
     entry::{
         Entry,
+        EntryRead,
         EntryWrite,
         EntryPatch,
         EntryParams,
-        entry_validate,
+        entry_validate_with_tags,
+        entry_due_date,
         entry_from_entry_write,
         entry_from_entry_write_edit,
+        entry_write_reminders,
+        entry_patch_apply,
+        entry_patch_from_document,
+        EntryDocument,
+        entry_etag,
+    },
+    channel::{
+        UserChannelRead,
+        UserChannelWrite,
+        user_channel_from_write,
+        user_channel_validate,
+    },
+    outbox::{
+        OutboxParams,
+        OUTBOX_SENT,
+        outbox_retryable,
+    },
+    reminder::{
+        Reminder,
+        ReminderWrite,
+        reminders_to_write,
+        reminders_validate,
+        reminder_legacy_from_list,
+    },
+    tag::tags_normalize,
+    list::{
+        EntryList,
+        EntryListWrite,
+        ListDeleteParams,
+        list_from_write,
+        list_validate,
+    },
+    webhook::{
+        WebhookSubscription,
+        WebhookRead,
+        WebhookWrite,
+        webhook_from_webhook_write,
+        webhook_validate,
+        EVENT_CREATED,
+        EVENT_DELETED,
+        EVENT_RESTORED,
+    },
+    import::{
+        ImportParams,
+        ImportRow,
+        ImportReport,
+        import_format,
+        ROW_CREATED,
+        ROW_VALID,
+        ROW_REJECTED,
+    },
+    feed::{
+        FeedRead,
+        FeedParams,
+        feed_token_generate,
+    },
+    export::{
+        ExportParams,
+        export_content_type,
+    },
+    idempotency::{
+        IdempotencyKey,
+        idempotency_key_validate,
+        idempotency_request_hash,
+        IDEMPOTENCY_KEY_HEADER,
+        IDEMPOTENT_REPLAYED_HEADER,
+        IDEMPOTENCY_PENDING_SECONDS,
+    },
+    batch::{
+        BatchParams,
+        BatchOperation,
+        BatchAction,
+        BatchChange,
+        BatchItem,
+        BatchReport,
+        batch_action,
+        ITEM_APPLIED,
+        ITEM_FAILED,
+        ITEM_ROLLED_BACK,
+        MODE_ATOMIC,
+        BATCH_MAX_OPERATIONS,
+    },
+    problem::{
+        FieldError,
+        field_errors_result,
+        field_errors_detail,
+        CODE_CHANNEL_NOT_FOUND,
+        CODE_EMAIL_IN_USE,
+        CODE_ENTRY_NOT_FOUND,
+        CODE_FEED_NOT_FOUND,
+        CODE_FORBIDDEN,
+        CODE_IDEMPOTENCY_KEY_INVALID,
+        CODE_IDEMPOTENCY_KEY_IN_PROGRESS,
+        CODE_IDEMPOTENCY_KEY_REUSED,
+        CODE_INTERNAL_ERROR,
+        CODE_INVALID_BODY,
+        CODE_INVALID_EMAIL,
+        CODE_INVALID_LIST,
+        CODE_INVALID_PARAMETER,
+        CODE_INVALID_UTC_OFFSET,
+        CODE_LIST_NOT_FOUND,
+        CODE_MESSAGE_ALREADY_SENT,
+        CODE_MESSAGE_NOT_FOUND,
+        CODE_MESSAGE_NOT_RETRYABLE,
+        CODE_NAME_IN_USE,
+        CODE_PRECONDITION_FAILED,
+        CODE_REVISION_NOT_FOUND,
+        CODE_TOO_MANY_OPERATIONS,
+        CODE_UNAUTHENTICATED,
+        CODE_UNSUPPORTED_MEDIA_TYPE,
+        CODE_URL_NOT_ALLOWED,
+        CODE_USERNAME_IN_USE,
+        CODE_USER_NOT_FOUND,
+        CODE_WEBHOOK_NOT_FOUND,
     },
 };
 
+/* entries per database page of a streamed export */
+const EXPORT_PAGE_SIZE: i64 = 500;
+
+/* an event stream sends a comment when it has been quiet for a while (so proxies keep it open) */
+const ENTRY_EVENT_KEEP_ALIVE_SECONDS: u64 = 15;
+
 pub fn config(cfg: &mut web::ServiceConfig) {
     cfg.service(
         web::scope("/api")
+            .app_data(json_config())
+            .app_data(query_config())
+            .wrap_fn(|request, service| {
+                let successor = entry_v2_successor(request.path());
+                let response = service.call(request);
+                
+                async move {
+                    let mut response = response.await?;
+                    
+                    if let Some(successor) = successor {
+                        deprecate(response.headers_mut(), &successor);
+                    }
+                    
+                    Ok(response)
+                }
+            })
+            
+            .route("/openapi.json", web::get().to(get_openapi))
+
             .route("/entries", web::post().to(create_entry))
             .route("/entries", web::get().to(get_entries))
+            .route("/entries/import", web::post().to(import_entries))
+            .route("/entries/export", web::get().to(export_entries))
+            .route("/entries/batch", web::post().to(batch_entries))
+            .route("/entries/events", web::get().to(get_entry_events))
             .route("/entries/{id}", web::get().to(get_entry_by_id))
             .route("/entries/{id}", web::put().to(update_entry_by_id))
             .route("/entries/{id}", web::patch().to(update_entry_partial_by_id))
             .route("/entries/{id}", web::delete().to(delete_entry_by_id))
+            .route("/entries/{id}/history", web::get().to(get_entry_history))
+            .route("/entries/{id}/history/{revision}/revert", web::post().to(revert_entry_by_id))
+
+            .route("/ws", web::get().to(socket::get_socket))
+            .route("/sync", web::get().to(get_sync))
+
+            .route("/trash", web::get().to(get_trash))
+            .route("/trash/{id}/restore", web::post().to(restore_trash_entry))
+
+            .route("/tags", web::get().to(get_tags))
+            
+            .route("/lists", web::post().to(create_list))
+            .route("/lists", web::get().to(get_lists))
+            .route("/lists/{id}", web::get().to(get_list_by_id))
+            .route("/lists/{id}", web::put().to(update_list_by_id))
+            .route("/lists/{id}", web::delete().to(delete_list_by_id))
 
             .route("/users", web::post().to(create_user))
             .route("/users", web::get().to(get_users))
             .route("/users/{id}", web::get().to(get_user_by_id))
             .route("/users/{id}", web::patch().to(update_user_partial_by_id))
             .route("/users/{id}", web::delete().to(delete_user_by_id))
+
+            .route("/channels", web::get().to(get_channels))
+            .route("/channels/{channel}", web::put().to(update_channel))
+            .route("/channels/{channel}", web::delete().to(delete_channel))
+
+            .route("/webhooks", web::post().to(create_webhook))
+            .route("/webhooks", web::get().to(get_webhooks))
+            .route("/webhooks/{id}", web::get().to(get_webhook_by_id))
+            .route("/webhooks/{id}", web::delete().to(delete_webhook_by_id))
+            .route("/webhooks/{id}/deliveries", web::get().to(get_webhook_deliveries))
+
+            .route("/feeds", web::get().to(get_feed))
+            .route("/feeds", web::post().to(regenerate_feed))
+            .route("/feeds", web::delete().to(delete_feed))
+            .route("/feeds/{token}.ics", web::get().to(get_feed_calendar))
+
+            .route("/admin/outbox", web::get().to(get_outbox))
+            .route("/admin/outbox/{id}/retry", web::post().to(retry_outbox_by_id))
+            .route("/admin/audit", web::get().to(get_audit))
+            .route("/admin/audit/export", web::get().to(export_audit))
     );
 }
 
 /**************************************************************************/
 /* ENTRY actions */
 /**************************************************************************/
-pub async fn create_entry(request: HttpRequest, db: Data<Database>, new_entry: Json<EntryWrite>) -> HttpResponse {
+/* a retry with the same Idempotency-Key gets the first response again, instead of a second entry */
+pub async fn create_entry(request: HttpRequest, db: Data<Database>, options: Data<Options>, body: web::Bytes) -> HttpResponse {
     let username = match get_username_from_base64(&db, &request) {
         Some(name) => { name },
-        None => { return HttpResponse::NotFound().body("Username not found"); },
+        None => { return ApiError::unauthorized(CODE_UNAUTHENTICATED, "Invalid credentials").into(); },
     };
     
     let id = match get_user_id_from_username(&db, &username) {
         Ok(id) => { id },
-        Err(()) => { return HttpResponse::NotFound().body("Matching username not found"); },
+        Err(()) => { return ApiError::not_found(CODE_USER_NOT_FOUND, "Matching username not found").into(); },
     };
     
-    let new_entry = entry_from_entry_write(&new_entry, &username);
+    let scope = format!["{} {}", id, request.path()];
+    
+    idempotent(&db, &options, &request, &scope, &body, || {
+        match serde_json::from_slice::<EntryWrite>(&body) {
+            Ok(new_entry) => create_entry_response(&db, &username, &id, &new_entry),
+            Err(err) => ApiError::bad_request(CODE_INVALID_BODY, &err.to_string()).into(),
+        }
+    }).await
+}
 
-    match validate_entry(&new_entry) {
-        Ok(()) => {
-            match db.create_entry(new_entry, &id) {
-                Ok(entry) => { return HttpResponse::Ok().json(entry); },
-                Err(err) => { return HttpResponse::BadRequest().body(err.to_string()); },
-            }
+#[allow(clippy::needless_return)]
+fn create_entry_response(db: &Database, username: &str, id: &str, new_entry: &EntryWrite) -> HttpResponse {
+    match entry_create(db, username, id, new_entry) {
+        Ok(read) => {
+            return HttpResponse::Created()
+                .insert_header(("Location", format!["/api/entries/{}", read.entry.id]))
+                .insert_header(("ETag", entry_etag(&read.entry)))
+                .json(read);
         },
-        Err(s) => {
-            return HttpResponse::BadRequest().body(s);
+        Err(error) => {
+            return error.into();
         },
     }
 }
@@ -84,118 +351,98 @@ pub async fn create_entry(request: HttpRequest, db: Data<Database>, new_entry: J
 pub async fn get_entries(request: HttpRequest, db: web::Data<Database>, info: web::Query<EntryParams>) -> HttpResponse {
     let username = match get_username_from_base64(&db, &request) {
         Some(name) => { name },
-        None => { return HttpResponse::NotFound().body("Username not found"); },
-    };
-    
-    let username = if username == "admin" { 
-        None
-    } else {
-        Some(username.clone())
-    };
-    
-    let f = EntryParams {
-        username: username,
-        year: info.year.to_owned(),
-        month: info.month.to_owned(),
-        day: info.day.to_owned(),
-        reminder: info.reminder.to_owned(),
-        expired: info.expired.to_owned(),
+        None => { return ApiError::unauthorized(CODE_UNAUTHENTICATED, "Invalid credentials").into(); },
     };
     
+    let f = get_entry_params(&username, &info);
     let entries = db.get_entries_by_filter(&f);
-    HttpResponse::Ok().json(entries)
+    HttpResponse::Ok().json(get_entries_read(&db, entries))
 }
 
 pub async fn get_entry_by_id(request: HttpRequest, db: web::Data<Database>, id: web::Path<String>) -> HttpResponse {
     let username = match get_username_from_base64(&db, &request) {
         Some(name) => { name },
-        None => { return HttpResponse::NotFound().body("Username not found"); },
+        None => { return ApiError::unauthorized(CODE_UNAUTHENTICATED, "Invalid credentials").into(); },
     };
     
     let entry = db.get_entry_by_id(&id);
     
     match entry {
-        Some(entry) => {
-            if username == "admin" || username == entry.username {
-                HttpResponse::Ok().json(entry)
-            } else {
-                HttpResponse::Unauthorized().body("Invalid access")
+        Some(entry) if username == "admin" || username == entry.username => {
+            let etag = entry_etag(&entry);
+            
+            if entry_not_modified(&request, &entry) {
+                return HttpResponse::NotModified().insert_header(("ETag", etag)).finish();
             }
+            
+            HttpResponse::Ok().insert_header(("ETag", etag)).json(get_entry_read(&db, entry))
         }
-       None => HttpResponse::NotFound().body("Entry not found"),
+       _ => ApiError::not_found(CODE_ENTRY_NOT_FOUND, "Entry not found").into(),
     }
 }
 
 pub async fn update_entry_by_id(request: HttpRequest, db: web::Data<Database>, id: web::Path<String>, updated_entry: web::Json<EntryWrite>) -> HttpResponse {
     let username = match get_username_from_base64(&db, &request) {
         Some(name) => { name },
-        None => { return HttpResponse::NotFound().body("Username not found"); },
+        None => { return ApiError::unauthorized(CODE_UNAUTHENTICATED, "Invalid credentials").into(); },
     };
     
     match db.get_entry_by_id(&id) {
-        Some(entry) => {
-            if username == "admin" || username == entry.username {
-                let updated_entry = entry_from_entry_write_edit(&updated_entry, &entry);
-
-                match entry_validate(&updated_entry) {
-                    Ok(()) => {
-                        let entry = db.update_entry_by_id(&id, updated_entry);
-                        return HttpResponse::Ok().json(entry);
-                    },
-                    Err(s) => {
-                        return HttpResponse::BadRequest().body(s);
-                    },
-                }
-            } else {
-                return HttpResponse::Unauthorized().body("Invalid access");
+        Some(entry) if username == "admin" || username == entry.username => {
+            if !entry_if_match(&request, &entry) {
+                return ApiError::new(StatusCode::PRECONDITION_FAILED, CODE_PRECONDITION_FAILED, "Entry has been changed").into();
+            }
+            
+            match entry_replace(&db, &username, &entry, &updated_entry) {
+                Ok(read) => {
+                    return entry_response(read);
+                },
+                Err(error) => {
+                    return error.into();
+                },
             }
         },
-        None => {
-            return HttpResponse::NotFound().body("Entry not found");
+        _ => {
+            return ApiError::not_found(CODE_ENTRY_NOT_FOUND, "Entry not found").into();
         },
     }
 }
 
-pub async fn update_entry_partial_by_id(request: HttpRequest, db: web::Data<Database>, id: web::Path<String>, partial_entry: web::Json<EntryPatch>) -> HttpResponse {
+/* the body is plain JSON (null is ignored), a merge-patch (null clears) or a json-patch, by Content-Type */
+#[allow(clippy::needless_return)]
+pub async fn update_entry_partial_by_id(request: HttpRequest, db: web::Data<Database>, id: web::Path<String>, body: String) -> HttpResponse {
     let username = match get_username_from_base64(&db, &request) {
         Some(name) => { name },
-        None => { return HttpResponse::NotFound().body("Username not found"); },
+        None => { return ApiError::unauthorized(CODE_UNAUTHENTICATED, "Invalid credentials").into(); },
+    };
+    
+    let format = match patch_format(&request) {
+        Some(format) => { format },
+        None => { return patch_unsupported(); },
     };
     
     match db.get_entry_by_id(&id) {
-        Some(mut entry) => {
-            if username == "admin" || username == entry.username {
-                if let Some(year) = &partial_entry.year {
-                    entry.year = year.clone();
-                }
-                if let Some(month) = &partial_entry.month {
-                    entry.month = month.clone();
-                }
-                if let Some(day) = &partial_entry.day {
-                    entry.day = day.clone();
-                }
-                if let Some(task) = &partial_entry.task {
-                    entry.task = task.clone();
-                }
-                if let Some(reminder) = &partial_entry.reminder {
-                    entry.reminder = Some(reminder.clone());
-                }
-                
-                match entry_validate(&entry) {
-                    Ok(()) => {
-                        let entry = db.update_entry_by_id(&id, entry.clone());
-                        return HttpResponse::Ok().json(entry);
-                    },
-                    Err(s) => {
-                        return HttpResponse::BadRequest().body(s);
-                    },
-                }
-            } else {
-                return HttpResponse::Unauthorized().body("Invalid access");
+        Some(entry) if username == "admin" || username == entry.username => {
+            if !entry_if_match(&request, &entry) {
+                return ApiError::new(StatusCode::PRECONDITION_FAILED, CODE_PRECONDITION_FAILED, "Entry has been changed").into();
+            }
+            
+            let partial_entry = match entry_patch_from_body(&db, format, &entry, &body) {
+                Ok(patch) => { patch },
+                Err(s) => { return ApiError::bad_request(CODE_INVALID_BODY, &s).into(); },
+            };
+            
+            match entry_update(&db, &username, &entry, &partial_entry) {
+                Ok(read) => {
+                    return entry_response(read);
+                },
+                Err(error) => {
+                    return error.into();
+                },
             }
         },
-        None => {
-            return HttpResponse::NotFound().body("Entry not found");
+        _ => {
+            return ApiError::not_found(CODE_ENTRY_NOT_FOUND, "Entry not found").into();
         },
     }
 }
@@ -203,244 +450,1583 @@ pub async fn update_entry_partial_by_id(request: HttpRequest, db: web::Data<Data
 pub async fn delete_entry_by_id(request: HttpRequest, db: web::Data<Database>, id: web::Path<String>) -> HttpResponse {
     let username = match get_username_from_base64(&db, &request) {
         Some(name) => { name },
-        None => { return HttpResponse::NotFound().body("Username not found"); },
+        None => { return ApiError::unauthorized(CODE_UNAUTHENTICATED, "Invalid credentials").into(); },
     };
     
     if id.clone() == "all" {
         if username == "admin" {
             let entries = db.get_entries();
             
-            for entry in entries {
-                db.delete_entry_by_id(&entry.id);
+            for entry in &entries {
+                db.delete_entry_by_id(entry, &username);
+                events::dispatch(&db, EVENT_DELETED, entry);
             }
             
+            audit(&db, &request, AUDIT_ENTRIES_DELETED, Some(&username), None, Some(&format!["all entries ({})", entries.len()]));
             return HttpResponse::Ok().body("Entry database cleared");
         } else {
-            return HttpResponse::Unauthorized().body("Invalid access");
+            return ApiError::forbidden(CODE_FORBIDDEN, "Invalid access").into();
         }
     }
     
     match db.get_entry_by_id(&id) {
-        Some(entry) => {
-            if username == "admin" || username == entry.username {
-                let entry = db.delete_entry_by_id(&id);
-                return HttpResponse::Ok().json(entry);
-            } else {
-                return HttpResponse::Unauthorized().body("Invalid access");
+        Some(entry) if username == "admin" || username == entry.username => {
+            if !entry_if_match(&request, &entry) {
+                return ApiError::new(StatusCode::PRECONDITION_FAILED, CODE_PRECONDITION_FAILED, "Entry has been changed").into();
+            }
+            
+            match entry_delete(&db, &username, &entry) {
+                Ok(()) => { return HttpResponse::Ok().json(1); },
+                Err(e) => { return e.into(); },
             }
         },
-        None => {
-            return HttpResponse::NotFound().body("Entry not found");
+        _ => {
+            return ApiError::not_found(CODE_ENTRY_NOT_FOUND, "Entry not found").into();
         },
     }
 }
 
-fn validate_entry(entry: &Entry) -> Result<(), String> {
-    let entry_check = Entry {
-        id: entry.id.clone(),
-        username: entry.username.clone(),
-        year: entry.year.clone(),
-        month: entry.month.clone(),
-        day: entry.day.clone(),
-        task: entry.task.clone(),
-        reminder: entry.reminder.clone(),
-        expired: entry.expired.clone(),
-        created: entry.created,
-        updated: entry.updated,
-        user_id: entry.user_id.clone(),
+/* every recorded change to the entry, oldest first */
+#[allow(clippy::needless_return)]
+pub async fn get_entry_history(request: HttpRequest, db: web::Data<Database>, id: web::Path<String>) -> HttpResponse {
+    let username = match get_username_from_base64(&db, &request) {
+        Some(name) => { name },
+        None => { return ApiError::unauthorized(CODE_UNAUTHENTICATED, "Invalid credentials").into(); },
     };
     
-    match entry_validate(&entry_check) {
-        Ok(()) => Ok(()),
-        Err(e) => {
-            Err(String::from(e))
-        }
-    }
-}
-
-/**************************************************************************/
-/* USER actions */
-/**************************************************************************/
-pub async fn create_user(db: Data<Database>, new_user: Json<User>) -> HttpResponse {
-    match validate_user(&db, &new_user) {
-        Ok(()) => {
-            match db.create_user(new_user.into_inner()) {
-                Ok(user) => { return HttpResponse::Ok().json(user); },
-                Err(err) => { return HttpResponse::BadRequest().body(err.to_string()); },
-            }
+    let user_id = get_user_id_from_username(&db, &username).unwrap_or_default();
+    
+    /* the history of an entry in the trash, or purged from it, is kept; it belongs to whoever owned the entry */
+    let revisions = match db.get_entry_by_id(&id) {
+        Some(entry) if username == "admin" || username == entry.username => Some(db.get_entry_revisions(&entry.id)),
+        Some(_) => None,
+        None => {
+            let revisions: Vec<EntryRevision> = db.get_entry_revisions(&id).into_iter().filter(|r| username == "admin" || r.user_id == user_id).collect();
+            (!revisions.is_empty()).then_some(revisions)
+        },
+    };
+    
+    match revisions {
+        Some(revisions) => {
+            let history: Vec<RevisionRead> = revisions.iter().map(RevisionRead::new).collect();
+            return HttpResponse::Ok().json(history);
         },
-        Err(s) => {
-            return HttpResponse::BadRequest().body(s);
+        None => {
+            return ApiError::not_found(CODE_ENTRY_NOT_FOUND, "Entry not found").into();
         },
     }
 }
 
-pub async fn get_users(request: HttpRequest, db: web::Data<Database>) -> HttpResponse {
+/* puts the entry back the way it was just after the given revision; the revert is itself recorded as a new revision */
+#[allow(clippy::needless_return)]
+pub async fn revert_entry_by_id(request: HttpRequest, db: web::Data<Database>, path: web::Path<(String, i64)>) -> HttpResponse {
     let username = match get_username_from_base64(&db, &request) {
         Some(name) => { name },
-        None => { return HttpResponse::NotFound().body("Username not found"); },
+        None => { return ApiError::unauthorized(CODE_UNAUTHENTICATED, "Invalid credentials").into(); },
     };
     
-    if username != "admin" {
-        return HttpResponse::Unauthorized().body("Invalid access");
-    }
+    let (entry_id, revision) = path.into_inner();
     
-    let users = db.get_users();
-    HttpResponse::Ok().json(users)
+    match db.get_entry_by_id(&entry_id) {
+        Some(entry) if username == "admin" || username == entry.username => {
+            if !entry_if_match(&request, &entry) {
+                return ApiError::new(StatusCode::PRECONDITION_FAILED, CODE_PRECONDITION_FAILED, "Entry has been changed").into();
+            }
+            
+            let revisions = db.get_entry_revisions(&entry.id);
+            
+            let later = match revisions.iter().position(|r| r.id == revision) {
+                Some(i) => { &revisions[i + 1..] },
+                None => { return ApiError::not_found(CODE_REVISION_NOT_FOUND, "Revision not found").into(); },
+            };
+            
+            let patch = entry_revert_patch(&get_entry_read(&db, entry.clone()), later);
+            
+            match entry_update(&db, &username, &entry, &patch) {
+                Ok(read) => {
+                    return entry_response(read);
+                },
+                Err(error) => {
+                    return error.into();
+                },
+            }
+        },
+        _ => {
+            return ApiError::not_found(CODE_ENTRY_NOT_FOUND, "Entry not found").into();
+        },
+    }
 }
 
-pub async fn get_user_by_id(request: HttpRequest, db: web::Data<Database>, id: web::Path<String>) -> HttpResponse {
+/* creates one entry per item of an uploaded file; every row is reported, created or not */
+pub async fn import_entries(request: HttpRequest, db: web::Data<Database>, info: web::Query<ImportParams>, body: String) -> HttpResponse {
     let username = match get_username_from_base64(&db, &request) {
         Some(name) => { name },
-        None => { return HttpResponse::NotFound().body("Username not found"); },
+        None => { return ApiError::unauthorized(CODE_UNAUTHENTICATED, "Invalid credentials").into(); },
     };
     
-    if username != "admin" {
-        return HttpResponse::Unauthorized().body("Invalid access");
-    }
-
-    let user = db.get_user_by_id(&id);
-    match user {
-        Some(user) => HttpResponse::Ok().json(user),
-        None => HttpResponse::NotFound().body("User not found"),
-    }
-}
-
-pub async fn update_user_partial_by_id(request: HttpRequest, db: web::Data<Database>, id: web::Path<String>, partial_user: web::Json<UserPatch>) -> HttpResponse {
-    let username = match get_username_from_base64(&db, &request) {
-        Some(name) => { name },
-        None => { return HttpResponse::NotFound().body("Username not found"); },
+    let id = match get_user_id_from_username(&db, &username) {
+        Ok(id) => { id },
+        Err(()) => { return ApiError::not_found(CODE_USER_NOT_FOUND, "Matching username not found").into(); },
     };
     
-    if username != "admin" {
-        return HttpResponse::Unauthorized().body("Invalid access");
-    }
-
-    let user = db.get_user_by_id(&id);
-    match user {
-        Some(mut user) => {
-            if let Some(password) = &partial_user.password {
-                user.password = password.clone();
+    let content_type = request.headers().get("content-type").and_then(|h| h.to_str().ok()).unwrap_or("");
+    
+    let format = match import_format(info.format.as_deref(), content_type) {
+        Some(format) => { format },
+        None => { return ApiError::bad_request(CODE_INVALID_PARAMETER, "Invalid format").into(); },
+    };
+    
+    let items = match import_items(format, &body, info.map.as_deref()) {
+        Ok(items) => { items },
+        Err(s) => { return ApiError::bad_request(CODE_INVALID_BODY, &s).into(); },
+    };
+    
+    let dry_run = info.dry_run.as_deref() == Some("true");
+    let rows: Vec<ImportRow> = items
+        .into_iter()
+        .map(|item| {
+            let mut row = ImportRow {
+                row: item.row,
+                status: String::from(ROW_REJECTED),
+                id: None,
+                task: None,
+                error: None,
+            };
+            
+            let write = match item.write {
+                Ok(write) => write,
+                Err(s) => {
+                    row.error = Some(s);
+                    return row;
+                },
+            };
+            
+            let tags = write.tags.clone().unwrap_or_default();
+            let mut new_entry = entry_from_entry_write(&write, &username);
+            let reminders = entry_write_list_reminders(&db, &id, &write, &mut new_entry);
+            row.task = Some(new_entry.task.clone());
+            
+            if let Err(errors) = entry_write_validate(&db, &id, &new_entry, &reminders, &tags, None) {
+                row.error = Some(field_errors_detail(&errors));
+                return row;
             }
-            if let Some(utc_offset) = &partial_user.utc_offset {
-                user.utc_offset = Some(utc_offset.clone());
+            
+            if dry_run {
+                row.status = String::from(ROW_VALID);
+                return row;
             }
-
-            match validate_user_partial(&user) {
-                Ok(()) => {
-                    match db.update_user_by_id(&id, user.clone()) {
-                        Some(user) => { return HttpResponse::Ok().json(user); },
-                        None => { return HttpResponse::NotFound().body("User not found"); },
-                    }
+            
+            match db.create_entry(new_entry, &reminders, &tags_normalize(&tags), &id, &username) {
+                Ok(read) => {
+                    events::dispatch(&db, EVENT_CREATED, &read.entry);
+                    row.status = String::from(ROW_CREATED);
+                    row.id = Some(read.entry.id);
                 },
-                Err(s) => {
-                    return HttpResponse::BadRequest().body(s);
+                Err(err) => {
+                    row.error = Some(err.to_string());
                 },
             }
-        }
-        
-        None => HttpResponse::NotFound().body("User not found"),
-    }
+            
+            row
+        })
+        .collect();
+    
+    HttpResponse::Ok().json(ImportReport::new(dry_run, rows))
 }
 
-pub async fn delete_user_by_id(request: HttpRequest, db: web::Data<Database>, id: web::Path<String>) -> HttpResponse {
+/* applies a list of create/patch/delete operations in one transaction; every operation is reported */
+pub async fn batch_entries(request: HttpRequest, db: web::Data<Database>, info: web::Query<BatchParams>, operations: Json<Vec<BatchOperation>>) -> HttpResponse {
     let username = match get_username_from_base64(&db, &request) {
         Some(name) => { name },
-        None => { return HttpResponse::NotFound().body("Username not found"); },
+        None => { return ApiError::unauthorized(CODE_UNAUTHENTICATED, "Invalid credentials").into(); },
     };
     
-    if username != "admin" {
-        return HttpResponse::Unauthorized().body("Invalid access");
-    }
-
-    let user = db.delete_user_by_id(&id);
-    match user {
-        Some(user) => HttpResponse::Ok().json(user),
-        None => HttpResponse::NotFound().body("User not found"),
+    let user_id = match get_user_id_from_username(&db, &username) {
+        Ok(id) => { id },
+        Err(()) => { return ApiError::not_found(CODE_USER_NOT_FOUND, "Matching username not found").into(); },
+    };
+    
+    let mode = match info.mode() {
+        Some(mode) => { mode },
+        None => { return ApiError::bad_request(CODE_INVALID_PARAMETER, "Invalid mode").into(); },
+    };
+    
+    if operations.len() > BATCH_MAX_OPERATIONS {
+        return ApiError::bad_request(CODE_TOO_MANY_OPERATIONS, &format!["Too many operations (at most {})", BATCH_MAX_OPERATIONS]).into();
     }
-}
-
-/**************************************************************************/
-/* supporting functions */
-/**************************************************************************/
-fn validate_user(db: &Database, test_user: &User) -> Result<(), String> {
-    let users = db.get_users();
     
-    for user in users {
-        if user.username == test_user.username {
-            return Err("Username in use".to_string());
-        } else if user.email == test_user.email {
-            return Err("Email in use".to_string());
+    let atomic = mode == MODE_ATOMIC;
+    let mut items: Vec<BatchItem> = operations
+        .iter()
+        .enumerate()
+        .map(|(i, operation)| BatchItem::new(i, &operation.op, operation.id.clone()))
+        .collect();
+    
+    /* every operation is checked before anything is written; each change keeps its item index and the entry as it was */
+    let mut changes: Vec<BatchChange> = vec![];
+    let mut prepared: Vec<(usize, Option<Entry>)> = vec![];
+    let mut touched: Vec<String> = vec![];
+    
+    for (i, operation) in operations.iter().enumerate() {
+        match prepare_batch_change(&db, &username, &user_id, operation, &touched) {
+            Ok((change, original)) => {
+                if let Some(original) = &original {
+                    touched.push(original.id.clone());
+                }
+                changes.push(change);
+                prepared.push((i, original));
+            },
+            Err(s) => {
+                items[i].status = String::from(ITEM_FAILED);
+                items[i].error = Some(s);
+            },
         }
     }
     
-    if validate_email(&test_user.email) == false {
-        return Err("Invalid email address format".to_string());
+    if atomic && prepared.len() < operations.len() {
+        return HttpResponse::BadRequest().json(BatchReport::new(mode, items));
     }
     
-    if test_user.utc_offset.is_some() && validate_utc_offset(&test_user.utc_offset.clone().unwrap()) == false {
-        return Err("Invalid utc-offset".to_string());
-    }
+    let (committed, results) = db.apply_entry_batch(&changes, atomic, &username);
+    let mut deleted = 0;
     
-    Ok(())
-}
-
-fn validate_user_partial(test_user: &User) -> Result<(), String> {
-    if validate_email(&test_user.email) == false {
-        return Err("Invalid email address format".to_string());
+    for ((i, original), result) in prepared.into_iter().zip(results) {
+        match result {
+            Ok(read) if committed => {
+                items[i].status = String::from(ITEM_APPLIED);
+                
+                match (&original, &read) {
+                    (None, Some(read)) => events::dispatch(&db, EVENT_CREATED, &read.entry),
+                    (Some(original), Some(read)) => events::dispatch_update(&db, original, &read.entry),
+                    (Some(original), None) => {
+                        events::dispatch(&db, EVENT_DELETED, original);
+                        deleted += 1;
+                    },
+                    (None, None) => {},
+                }
+                
+                items[i].id = read.as_ref().map(|r| r.entry.id.clone()).or(items[i].id.take());
+                items[i].entry = read;
+            },
+            /* applied, then undone when a later operation failed */
+            Ok(_) => {
+                items[i].status = String::from(ITEM_ROLLED_BACK);
+            },
+            Err(s) => {
+                items[i].status = String::from(ITEM_FAILED);
+                items[i].error = Some(s);
+            },
+        }
     }
     
-    if test_user.utc_offset.is_some() && validate_utc_offset(&test_user.utc_offset.clone().unwrap()) == false {
-        return Err("Invalid utc-offset".to_string());
+    if deleted > 0 {
+        audit(&db, &request, AUDIT_ENTRIES_DELETED, Some(&username), None, Some(&format!["batch ({})", deleted]));
     }
     
-    Ok(())
+    if committed {
+        HttpResponse::Ok().json(BatchReport::new(mode, items))
+    } else {
+        HttpResponse::BadRequest().json(BatchReport::new(mode, items))
+    }
 }
 
-fn get_username_from_base64(db: &Database, request: &HttpRequest) -> Option<String> {
-    let users = db.get_users();
+/* streams the filtered entries a page at a time, so a large export is never held in memory */
+pub async fn export_entries(request: HttpRequest, db: web::Data<Database>, info: web::Query<EntryParams>, export: web::Query<ExportParams>) -> HttpResponse {
+    let username = match get_username_from_base64(&db, &request) {
+        Some(name) => { name },
+        None => { return ApiError::unauthorized(CODE_UNAUTHENTICATED, "Invalid credentials").into(); },
+    };
+    
+    let format = match export.format() {
+        Some(format) => { format },
+        None => { return ApiError::bad_request(CODE_INVALID_PARAMETER, "Invalid format").into(); },
+    };
+    
+    let f = get_entry_params(&username, &info);
+    let db = db.into_inner();
     
-    if let Some(user64) = get_header_base64(request) {
-        for user in users {
-            if check_user(&user64, &user.username, &user.password) {
-                return Some(String::from(&user.username));
+    let stream = futures_util::stream::unfold(ExportCursor::Start, move |cursor| {
+        let db = Arc::clone(&db);
+        let f = f.clone();
+        
+        async move {
+            let after = match &cursor {
+                ExportCursor::Start => None,
+                ExportCursor::After(id) => Some(id.as_str()),
+                ExportCursor::Done => { return None; },
+            };
+            
+            let entries = db.get_entries_by_filter_page(&f, after, EXPORT_PAGE_SIZE);
+            let next = match entries.last() {
+                Some(last) if entries.len() as i64 == EXPORT_PAGE_SIZE => ExportCursor::After(last.id.clone()),
+                _ => ExportCursor::Done,
+            };
+            
+            let mut chunk = if cursor == ExportCursor::Start { export_header(format) } else { String::new() };
+            
+            for entry in get_entries_read(&db, entries) {
+                chunk.push_str(&export_row(format, &entry));
             }
+            
+            Some((Ok::<_, actix_web::Error>(web::Bytes::from(chunk)), next))
         }
-    }
+    });
     
-    None
+    HttpResponse::Ok()
+        .content_type(export_content_type(format))
+        .insert_header(("Content-Disposition", format!["attachment; filename=\"entries.{}\"", format]))
+        .streaming(stream)
 }
 
-fn get_header_base64<'a>(request: &'a HttpRequest) -> Option<String> {
-    let response = request.headers().get("authorization")?.to_str();
+/* a Server-Sent Events stream of changes to the caller's entries (every user's, for the admin), resumed after "Last-Event-ID" */
+pub async fn get_entry_events(request: HttpRequest, db: web::Data<Database>) -> HttpResponse {
+    let username = match get_username_from_base64(&db, &request) {
+        Some(name) => { name },
+        None => { return ApiError::unauthorized(CODE_UNAUTHENTICATED, "Invalid credentials").into(); },
+    };
     
-    if let Ok(r) = response {
-        let r = String::from(r);
-        let args: Vec<_> = r.split_whitespace().collect();
+    let user_id = if username == "admin" {
+        None
+    } else {
+        match get_user_id_from_username(&db, &username) {
+            Ok(id) => { Some(id) },
+            Err(()) => { return ApiError::not_found(CODE_USER_NOT_FOUND, "Matching username not found").into(); },
+        }
+    };
+    
+    let last_event_id = last_event_id_parse(request.headers().get(LAST_EVENT_ID_HEADER).and_then(|v| v.to_str().ok()));
+    
+    /* a new stream starts from now; a resumed one that has missed events no longer kept is told to start over */
+    let (after, opening) = match last_event_id {
+        Some(n) => match db.get_entry_event_id_first() {
+            Some(first) if n < first - 1 => {
+                let last = db.get_entry_event_id_last();
+                (last, entry_event_reset_message(last))
+            },
+            _ => (n, String::from(": resumed\n\n")),
+        },
+        None => (db.get_entry_event_id_last(), String::from(": connected\n\n")),
+    };
+    
+    let subscription = events::EventSubscription::new(&db, user_id, after);
+    let db = db.into_inner();
+    let opening = futures_util::stream::once(async move { Ok::<_, actix_web::Error>(web::Bytes::from(opening)) });
+    
+    /* events are pushed as they are dispatched */
+    let events = futures_util::stream::unfold(subscription, move |mut subscription| {
+        let db = Arc::clone(&db);
         
-        if args.len() == 2 && args[0].to_lowercase() == "basic" {
-            return Some(String::from(args[1]));
-        }        
-    }
+        async move {
+            let chunk = match subscription.next(&db, std::time::Duration::from_secs(ENTRY_EVENT_KEEP_ALIVE_SECONDS)).await {
+                Some(events) if events.is_empty() => String::from(": keep-alive\n\n"),
+                Some(events) => events.iter().map(entry_event_message).collect(),
+                None => { return None; },
+            };
+            
+            Some((Ok::<_, actix_web::Error>(web::Bytes::from(chunk)), subscription))
+        }
+    });
     
-    None
+    HttpResponse::Ok()
+        .content_type(CONTENT_TYPE_EVENT_STREAM)
+        .insert_header(("Cache-Control", "no-cache"))
+        .streaming(futures_util::StreamExt::chain(opening, events))
 }
 
-fn check_user(b64: &str, user_name: &str, user_password: &str) -> bool {
-    let user_b64 = get_b64(user_name, user_password);
+/* entries created or changed since the token, and tombstones for those deleted, with the token to send next time; no token is a full sync */
+pub async fn get_sync(request: HttpRequest, db: web::Data<Database>, info: web::Query<SyncParams>) -> HttpResponse {
+    let username = match get_username_from_base64(&db, &request) {
+        Some(name) => { name },
+        None => { return ApiError::unauthorized(CODE_UNAUTHENTICATED, "Invalid credentials").into(); },
+    };
     
-    b64 == user_b64
-}
-
-fn get_b64(user_name: &str, user_password: &str) -> String {
-    let combined = format!["{}:{}", user_name, user_password];
+    let id = match get_user_id_from_username(&db, &username) {
+        Ok(id) => { id },
+        Err(()) => { return ApiError::not_found(CODE_USER_NOT_FOUND, "Matching username not found").into(); },
+    };
     
-    String::from(
-        general_purpose::STANDARD.encode(combined.as_bytes())
-    )
+    let since = match sync_token_parse(info.since.as_deref()) {
+        Ok(since) => { since },
+        Err(s) => { return ApiError::bad_request(CODE_INVALID_PARAMETER, &s).into(); },
+    };
+    
+    /* a full sync has nothing to delete */
+    let user_id = if username == "admin" { None } else { Some(id.as_str()) };
+    let changes = db.get_entry_changes_after(since, user_id, since > 0, SYNC_PAGE_SIZE);
+    let (changed, deleted, token) = sync_changes(since, &changes);
+    
+    HttpResponse::Ok().json(SyncResponse {
+        entries: get_entries_read(&db, db.get_entries_by_ids(&changed)),
+        deleted,
+        token,
+        more: changes.len() as i64 == SYNC_PAGE_SIZE,
+    })
 }
 
-fn validate_email(email: &str) -> bool {
+/**************************************************************************/
+/* TRASH actions */
+/**************************************************************************/
+/* deleted entries, until they are restored or purged (every user's, for the admin) */
+pub async fn get_trash(request: HttpRequest, db: web::Data<Database>) -> HttpResponse {
+    let username = match get_username_from_base64(&db, &request) {
+        Some(name) => { name },
+        None => { return ApiError::unauthorized(CODE_UNAUTHENTICATED, "Invalid credentials").into(); },
+    };
+    
+    let entries = if username == "admin" {
+        db.get_trashed_entries(None)
+    } else {
+        db.get_trashed_entries(Some(&username))
+    };
+    
+    HttpResponse::Ok().json(get_entries_read(&db, entries))
+}
+
+#[allow(clippy::needless_return)]
+pub async fn restore_trash_entry(request: HttpRequest, db: web::Data<Database>, id: web::Path<String>) -> HttpResponse {
+    let username = match get_username_from_base64(&db, &request) {
+        Some(name) => { name },
+        None => { return ApiError::unauthorized(CODE_UNAUTHENTICATED, "Invalid credentials").into(); },
+    };
+    
+    match db.get_trashed_entry_by_id(&id) {
+        Some(entry) if username == "admin" || username == entry.username => {
+            match db.restore_entry_by_id(&entry, &username) {
+                Some(entry) => {
+                    events::dispatch(&db, EVENT_RESTORED, &entry);
+                    return HttpResponse::Ok().json(get_entry_read(&db, entry));
+                },
+                None => {
+                    return ApiError::not_found(CODE_ENTRY_NOT_FOUND, "Entry not found").into();
+                },
+            }
+        },
+        _ => {
+            return ApiError::not_found(CODE_ENTRY_NOT_FOUND, "Entry not found").into();
+        },
+    }
+}
+
+/**************************************************************************/
+/* TAG actions */
+/**************************************************************************/
+/* the requesting user's tags, with how many of their entries carry each */
+pub async fn get_tags(request: HttpRequest, db: web::Data<Database>) -> HttpResponse {
+    let username = match get_username_from_base64(&db, &request) {
+        Some(name) => { name },
+        None => { return ApiError::unauthorized(CODE_UNAUTHENTICATED, "Invalid credentials").into(); },
+    };
+    
+    let id = match get_user_id_from_username(&db, &username) {
+        Ok(id) => { id },
+        Err(()) => { return ApiError::not_found(CODE_USER_NOT_FOUND, "Matching username not found").into(); },
+    };
+    
+    HttpResponse::Ok().json(db.get_tag_counts(&id))
+}
+
+/**************************************************************************/
+/* LIST actions */
+/**************************************************************************/
+pub async fn create_list(request: HttpRequest, db: web::Data<Database>, new_list: web::Json<EntryListWrite>) -> HttpResponse {
+    let username = match get_username_from_base64(&db, &request) {
+        Some(name) => { name },
+        None => { return ApiError::unauthorized(CODE_UNAUTHENTICATED, "Invalid credentials").into(); },
+    };
+    
+    let id = match get_user_id_from_username(&db, &username) {
+        Ok(id) => { id },
+        Err(()) => { return ApiError::not_found(CODE_USER_NOT_FOUND, "Matching username not found").into(); },
+    };
+    
+    /* a new list goes after the user's others, unless it says otherwise */
+    let sort_order = db.get_lists_by_user_id(&id).iter().map(|l| l.sort_order + 1).max().unwrap_or(0);
+    let list = list_from_write(&new_list, &id, sort_order, None);
+    
+    match validate_list(&db, &list) {
+        Ok(()) => {
+            let list = db.create_list(list);
+            HttpResponse::Created()
+                .insert_header(("Location", format!["/api/lists/{}", list.id]))
+                .json(list)
+        },
+        Err(errors) => ApiError::validation(errors).into(),
+    }
+}
+
+/* in sort order (every user's, for the admin) */
+pub async fn get_lists(request: HttpRequest, db: web::Data<Database>) -> HttpResponse {
+    let username = match get_username_from_base64(&db, &request) {
+        Some(name) => { name },
+        None => { return ApiError::unauthorized(CODE_UNAUTHENTICATED, "Invalid credentials").into(); },
+    };
+    
+    let id = match get_user_id_from_username(&db, &username) {
+        Ok(id) => { id },
+        Err(()) => { return ApiError::not_found(CODE_USER_NOT_FOUND, "Matching username not found").into(); },
+    };
+    
+    let lists = if username == "admin" {
+        db.get_lists()
+    } else {
+        db.get_lists_by_user_id(&id)
+    };
+    
+    HttpResponse::Ok().json(lists)
+}
+
+pub async fn get_list_by_id(request: HttpRequest, db: web::Data<Database>, id: web::Path<String>) -> HttpResponse {
+    match get_list_for_request(&db, &request, &id) {
+        Ok((list, _)) => HttpResponse::Ok().json(list),
+        Err(response) => response,
+    }
+}
+
+pub async fn update_list_by_id(request: HttpRequest, db: web::Data<Database>, id: web::Path<String>, updated_list: web::Json<EntryListWrite>) -> HttpResponse {
+    let list = match get_list_for_request(&db, &request, &id) {
+        Ok((list, _)) => { list },
+        Err(response) => { return response; },
+    };
+    
+    let updated_list = list_from_write(&updated_list, &list.user_id, list.sort_order, Some(&list));
+    
+    match validate_list(&db, &updated_list) {
+        Ok(()) => HttpResponse::Ok().json(db.update_list_by_id(&list.id, updated_list)),
+        Err(errors) => ApiError::validation(errors).into(),
+    }
+}
+
+/* "?entries=inbox" (the default) moves the list's entries to the inbox; "?entries=delete" moves them to the trash with it */
+pub async fn delete_list_by_id(request: HttpRequest, db: web::Data<Database>, id: web::Path<String>, info: web::Query<ListDeleteParams>) -> HttpResponse {
+    let (list, username) = match get_list_for_request(&db, &request, &id) {
+        Ok(found) => { found },
+        Err(response) => { return response; },
+    };
+    
+    let cascade = match info.cascade() {
+        Some(cascade) => { cascade },
+        None => { return ApiError::bad_request(CODE_INVALID_PARAMETER, "Invalid entries").into(); },
+    };
+    
+    let changed = db.delete_list_by_id(&list, cascade, &username);
+    
+    for (entry, moved) in &changed {
+        match moved {
+            Some(moved) => events::dispatch_update(&db, entry, moved),
+            None => events::dispatch(&db, EVENT_DELETED, entry),
+        }
+    }
+    
+    let deleted = changed.iter().filter(|(_, moved)| moved.is_none()).count();
+    
+    if deleted > 0 {
+        audit(&db, &request, AUDIT_ENTRIES_DELETED, Some(&username), None, Some(&format!["list \"{}\" ({})", list.name, deleted]));
+    }
+    
+    HttpResponse::NoContent().finish()
+}
+
+/**************************************************************************/
+/* USER actions */
+/**************************************************************************/
+/* accounts are created without credentials, so the key is shared by all callers; clients should use random keys (e.g. UUIDs) */
+pub async fn create_user(request: HttpRequest, db: Data<Database>, options: Data<Options>, body: web::Bytes) -> HttpResponse {
+    idempotent(&db, &options, &request, request.path(), &body, || {
+        match serde_json::from_slice::<User>(&body) {
+            Ok(new_user) => create_user_response(&db, &request, new_user),
+            Err(err) => ApiError::bad_request(CODE_INVALID_BODY, &err.to_string()).into(),
+        }
+    }).await
+}
+
+/* the actor is whoever sent credentials with the request (e.g. the admin), or none for a sign-up */
+#[allow(clippy::needless_return)]
+fn create_user_response(db: &Database, request: &HttpRequest, new_user: User) -> HttpResponse {
+    match validate_user(db, &new_user) {
+        Ok(()) => {
+            match db.create_user(new_user) {
+                Ok(user) => {
+                    let actor = get_username_from_base64(db, request);
+                    audit(db, request, AUDIT_USER_CREATED, actor.as_deref(), Some(&user.username), None);
+                    
+                    return HttpResponse::Created()
+                        .insert_header(("Location", format!["/api/users/{}", user.id]))
+                        .json(user);
+                },
+                Err(err) => { return ApiError::new(StatusCode::INTERNAL_SERVER_ERROR, CODE_INTERNAL_ERROR, &err.to_string()).into(); },
+            }
+        },
+        Err(errors) => {
+            return ApiError::validation(errors).into();
+        },
+    }
+}
+
+pub async fn get_users(request: HttpRequest, db: web::Data<Database>) -> HttpResponse {
+    let username = match get_username_from_base64(&db, &request) {
+        Some(name) => { name },
+        None => { return ApiError::unauthorized(CODE_UNAUTHENTICATED, "Invalid credentials").into(); },
+    };
+    
+    if username != "admin" {
+        return ApiError::forbidden(CODE_FORBIDDEN, "Invalid access").into();
+    }
+    
+    let users = db.get_users();
+    HttpResponse::Ok().json(users)
+}
+
+pub async fn get_user_by_id(request: HttpRequest, db: web::Data<Database>, id: web::Path<String>) -> HttpResponse {
+    let username = match get_username_from_base64(&db, &request) {
+        Some(name) => { name },
+        None => { return ApiError::unauthorized(CODE_UNAUTHENTICATED, "Invalid credentials").into(); },
+    };
+    
+    if username != "admin" {
+        return ApiError::forbidden(CODE_FORBIDDEN, "Invalid access").into();
+    }
+
+    let user = db.get_user_by_id(&id);
+    match user {
+        Some(user) => HttpResponse::Ok().json(user),
+        None => ApiError::not_found(CODE_USER_NOT_FOUND, "User not found").into(),
+    }
+}
+
+/* as with entries, a merge-patch or json-patch can clear "utc_offset" */
+#[allow(clippy::needless_return)]
+pub async fn update_user_partial_by_id(request: HttpRequest, db: web::Data<Database>, id: web::Path<String>, body: String) -> HttpResponse {
+    let username = match get_username_from_base64(&db, &request) {
+        Some(name) => { name },
+        None => { return ApiError::unauthorized(CODE_UNAUTHENTICATED, "Invalid credentials").into(); },
+    };
+    
+    let format = match patch_format(&request) {
+        Some(format) => { format },
+        None => { return patch_unsupported(); },
+    };
+    
+    if username != "admin" {
+        return ApiError::forbidden(CODE_FORBIDDEN, "Invalid access").into();
+    }
+
+    let user = db.get_user_by_id(&id);
+    match user {
+        Some(orig) => {
+            let user = match user_patch_from_body(format, &orig, &body) {
+                Ok(user) => { user },
+                Err(s) => { return ApiError::bad_request(CODE_INVALID_BODY, &s).into(); },
+            };
+
+            match validate_user_partial(&user) {
+                Ok(()) => {
+                    match db.update_user_by_id(&id, user.clone()) {
+                        Some(user) => {
+                            if user.password != orig.password {
+                                audit(&db, &request, AUDIT_PASSWORD_CHANGED, Some(&username), Some(&user.username), None);
+                            }
+                            return HttpResponse::Ok().json(user);
+                        },
+                        None => { return ApiError::not_found(CODE_USER_NOT_FOUND, "User not found").into(); },
+                    }
+                },
+                Err(errors) => {
+                    return ApiError::validation(errors).into();
+                },
+            }
+        }
+        
+        None => ApiError::not_found(CODE_USER_NOT_FOUND, "User not found").into(),
+    }
+}
+
+pub async fn delete_user_by_id(request: HttpRequest, db: web::Data<Database>, id: web::Path<String>) -> HttpResponse {
+    let username = match get_username_from_base64(&db, &request) {
+        Some(name) => { name },
+        None => { return ApiError::unauthorized(CODE_UNAUTHENTICATED, "Invalid credentials").into(); },
+    };
+    
+    if username != "admin" {
+        return ApiError::forbidden(CODE_FORBIDDEN, "Invalid access").into();
+    }
+
+    let target = db.get_user_by_id(&id);
+    let user = db.delete_user_by_id(&id);
+    match user {
+        Some(user) => {
+            if let Some(target) = target {
+                audit(&db, &request, AUDIT_USER_DELETED, Some(&username), Some(&target.username), None);
+            }
+            HttpResponse::Ok().json(user)
+        },
+        None => ApiError::not_found(CODE_USER_NOT_FOUND, "User not found").into(),
+    }
+}
+
+/**************************************************************************/
+/* CHANNEL actions */
+/**************************************************************************/
+pub async fn get_channels(request: HttpRequest, db: web::Data<Database>) -> HttpResponse {
+    let username = match get_username_from_base64(&db, &request) {
+        Some(name) => { name },
+        None => { return ApiError::unauthorized(CODE_UNAUTHENTICATED, "Invalid credentials").into(); },
+    };
+    
+    let id = match get_user_id_from_username(&db, &username) {
+        Ok(id) => { id },
+        Err(()) => { return ApiError::not_found(CODE_USER_NOT_FOUND, "Matching username not found").into(); },
+    };
+    
+    let channels: Vec<UserChannelRead> = db.get_user_channels_by_user_id(&id)
+        .iter()
+        .map(UserChannelRead::new)
+        .collect();
+    HttpResponse::Ok().json(channels)
+}
+
+pub async fn update_channel(request: HttpRequest, db: web::Data<Database>, options: Data<Options>, channel: web::Path<String>, settings: web::Json<UserChannelWrite>) -> HttpResponse {
+    let username = match get_username_from_base64(&db, &request) {
+        Some(name) => { name },
+        None => { return ApiError::unauthorized(CODE_UNAUTHENTICATED, "Invalid credentials").into(); },
+    };
+    
+    let id = match get_user_id_from_username(&db, &username) {
+        Ok(id) => { id },
+        Err(()) => { return ApiError::not_found(CODE_USER_NOT_FOUND, "Matching username not found").into(); },
+    };
+    
+    let user_channel = user_channel_from_write(&settings, &channel, &id);
+    
+    match user_channel_validate(&user_channel).and_then(|()| validate_webhook_target(&options, user_channel.url.as_deref())) {
+        Ok(()) => {
+            let user_channel = db.set_user_channel(user_channel);
+            HttpResponse::Ok().json(UserChannelRead::new(&user_channel))
+        },
+        Err(errors) => ApiError::validation(errors).into(),
+    }
+}
+
+pub async fn delete_channel(request: HttpRequest, db: web::Data<Database>, channel: web::Path<String>) -> HttpResponse {
+    let username = match get_username_from_base64(&db, &request) {
+        Some(name) => { name },
+        None => { return ApiError::unauthorized(CODE_UNAUTHENTICATED, "Invalid credentials").into(); },
+    };
+    
+    let id = match get_user_id_from_username(&db, &username) {
+        Ok(id) => { id },
+        Err(()) => { return ApiError::not_found(CODE_USER_NOT_FOUND, "Matching username not found").into(); },
+    };
+    
+    match db.delete_user_channel_by_user_id(&id, &channel) {
+        Some(0) => ApiError::not_found(CODE_CHANNEL_NOT_FOUND, "Channel not found").into(),
+        count => HttpResponse::Ok().json(count),
+    }
+}
+
+/**************************************************************************/
+/* WEBHOOK actions */
+/**************************************************************************/
+pub async fn create_webhook(request: HttpRequest, db: web::Data<Database>, options: Data<Options>, new_webhook: web::Json<WebhookWrite>) -> HttpResponse {
+    let username = match get_username_from_base64(&db, &request) {
+        Some(name) => { name },
+        None => { return ApiError::unauthorized(CODE_UNAUTHENTICATED, "Invalid credentials").into(); },
+    };
+    
+    let id = match get_user_id_from_username(&db, &username) {
+        Ok(id) => { id },
+        Err(()) => { return ApiError::not_found(CODE_USER_NOT_FOUND, "Matching username not found").into(); },
+    };
+    
+    let webhook = webhook_from_webhook_write(&new_webhook, &id);
+    
+    match webhook_validate(&webhook).and_then(|()| validate_webhook_target(&options, Some(&webhook.url))) {
+        Ok(()) => {
+            let webhook = db.create_webhook(webhook);
+            HttpResponse::Created()
+                .insert_header(("Location", format!["/api/webhooks/{}", webhook.id]))
+                .json(WebhookRead::new(&webhook, true))
+        },
+        Err(errors) => ApiError::validation(errors).into(),
+    }
+}
+
+pub async fn get_webhooks(request: HttpRequest, db: web::Data<Database>) -> HttpResponse {
+    let username = match get_username_from_base64(&db, &request) {
+        Some(name) => { name },
+        None => { return ApiError::unauthorized(CODE_UNAUTHENTICATED, "Invalid credentials").into(); },
+    };
+    
+    let id = match get_user_id_from_username(&db, &username) {
+        Ok(id) => { id },
+        Err(()) => { return ApiError::not_found(CODE_USER_NOT_FOUND, "Matching username not found").into(); },
+    };
+    
+    let webhooks = if username == "admin" {
+        db.get_webhooks()
+    } else {
+        db.get_webhooks_by_user_id(&id)
+    };
+    
+    let webhooks: Vec<WebhookRead> = webhooks
+        .iter()
+        .map(|w| WebhookRead::new(w, false))
+        .collect();
+    HttpResponse::Ok().json(webhooks)
+}
+
+pub async fn get_webhook_by_id(request: HttpRequest, db: web::Data<Database>, id: web::Path<String>) -> HttpResponse {
+    match get_webhook_for_request(&db, &request, &id) {
+        Ok(webhook) => HttpResponse::Ok().json(WebhookRead::new(&webhook, false)),
+        Err(response) => response,
+    }
+}
+
+pub async fn delete_webhook_by_id(request: HttpRequest, db: web::Data<Database>, id: web::Path<String>) -> HttpResponse {
+    match get_webhook_for_request(&db, &request, &id) {
+        Ok(_) => HttpResponse::Ok().json(db.delete_webhook_by_id(&id)),
+        Err(response) => response,
+    }
+}
+
+pub async fn get_webhook_deliveries(request: HttpRequest, db: web::Data<Database>, id: web::Path<String>) -> HttpResponse {
+    match get_webhook_for_request(&db, &request, &id) {
+        Ok(_) => HttpResponse::Ok().json(db.get_webhook_deliveries_by_webhook_id(&id)),
+        Err(response) => response,
+    }
+}
+
+/**************************************************************************/
+/* FEED actions */
+/**************************************************************************/
+pub async fn get_feed(request: HttpRequest, db: web::Data<Database>) -> HttpResponse {
+    let username = match get_username_from_base64(&db, &request) {
+        Some(name) => { name },
+        None => { return ApiError::unauthorized(CODE_UNAUTHENTICATED, "Invalid credentials").into(); },
+    };
+    
+    let id = match get_user_id_from_username(&db, &username) {
+        Ok(id) => { id },
+        Err(()) => { return ApiError::not_found(CODE_USER_NOT_FOUND, "Matching username not found").into(); },
+    };
+    
+    match db.get_feed_by_user_id(&id) {
+        Some(feed) => HttpResponse::Ok().json(FeedRead::new(&feed)),
+        None => ApiError::not_found(CODE_FEED_NOT_FOUND, "Feed not found").into(),
+    }
+}
+
+/* creates the user's feed token, or replaces it (the old URL stops working) */
+pub async fn regenerate_feed(request: HttpRequest, db: web::Data<Database>) -> HttpResponse {
+    let username = match get_username_from_base64(&db, &request) {
+        Some(name) => { name },
+        None => { return ApiError::unauthorized(CODE_UNAUTHENTICATED, "Invalid credentials").into(); },
+    };
+    
+    let id = match get_user_id_from_username(&db, &username) {
+        Ok(id) => { id },
+        Err(()) => { return ApiError::not_found(CODE_USER_NOT_FOUND, "Matching username not found").into(); },
+    };
+    
+    let feed = db.set_feed(&id, &feed_token_generate());
+    HttpResponse::Ok().json(FeedRead::new(&feed))
+}
+
+pub async fn delete_feed(request: HttpRequest, db: web::Data<Database>) -> HttpResponse {
+    let username = match get_username_from_base64(&db, &request) {
+        Some(name) => { name },
+        None => { return ApiError::unauthorized(CODE_UNAUTHENTICATED, "Invalid credentials").into(); },
+    };
+    
+    let id = match get_user_id_from_username(&db, &username) {
+        Ok(id) => { id },
+        Err(()) => { return ApiError::not_found(CODE_USER_NOT_FOUND, "Matching username not found").into(); },
+    };
+    
+    match db.delete_feed_by_user_id(&id) {
+        Some(0) => ApiError::not_found(CODE_FEED_NOT_FOUND, "Feed not found").into(),
+        count => HttpResponse::Ok().json(count),
+    }
+}
+
+/* the token in the URL is the only authentication, since calendar apps cannot send credentials */
+pub async fn get_feed_calendar(db: web::Data<Database>, token: web::Path<String>, info: web::Query<FeedParams>) -> HttpResponse {
+    let component = match Component::from_param(info.component.as_deref()) {
+        Some(component) => component,
+        None => { return ApiError::bad_request(CODE_INVALID_PARAMETER, "Invalid component").into(); },
+    };
+    
+    let user = match db.get_feed_by_token(&token).and_then(|feed| db.get_user_by_id(&feed.user_id)) {
+        Some(user) => user,
+        None => { return ApiError::not_found(CODE_FEED_NOT_FOUND, "Feed not found").into(); },
+    };
+    
+    let f = EntryParams {
+        username: Some(user.username.clone()),
+        ..Default::default()
+    };
+    
+    let entries = get_entries_read(&db, db.get_entries_by_filter(&f));
+    
+    HttpResponse::Ok()
+        .content_type("text/calendar; charset=utf-8")
+        .body(ical::calendar(&user.username, &entries, component))
+}
+
+/**************************************************************************/
+/* ADMIN actions */
+/**************************************************************************/
+pub async fn get_outbox(request: HttpRequest, db: web::Data<Database>, info: web::Query<OutboxParams>) -> HttpResponse {
+    let username = match get_username_from_base64(&db, &request) {
+        Some(name) => { name },
+        None => { return ApiError::unauthorized(CODE_UNAUTHENTICATED, "Invalid credentials").into(); },
+    };
+    
+    if username != "admin" {
+        return ApiError::forbidden(CODE_FORBIDDEN, "Invalid access").into();
+    }
+    
+    let messages = db.get_outbox_by_filter(&info);
+    HttpResponse::Ok().json(messages)
+}
+
+pub async fn retry_outbox_by_id(request: HttpRequest, db: web::Data<Database>, id: web::Path<String>) -> HttpResponse {
+    let username = match get_username_from_base64(&db, &request) {
+        Some(name) => { name },
+        None => { return ApiError::unauthorized(CODE_UNAUTHENTICATED, "Invalid credentials").into(); },
+    };
+    
+    if username != "admin" {
+        return ApiError::forbidden(CODE_FORBIDDEN, "Invalid access").into();
+    }
+    
+    match db.get_outbox_by_id(&id) {
+        Some(message) => {
+            if message.status == OUTBOX_SENT {
+                return ApiError::new(StatusCode::CONFLICT, CODE_MESSAGE_ALREADY_SENT, "Message already sent").into();
+            }
+            
+            if !outbox_retryable(&message) {
+                return ApiError::new(StatusCode::CONFLICT, CODE_MESSAGE_NOT_RETRYABLE, "Message has not failed").into();
+            }
+            
+            match db.retry_outbox_by_id(&message) {
+                Some(message) => HttpResponse::Ok().json(message),
+                None => ApiError::new(StatusCode::CONFLICT, CODE_MESSAGE_NOT_RETRYABLE, "Message changed while retrying").into(),
+            }
+        },
+        None => ApiError::not_found(CODE_MESSAGE_NOT_FOUND, "Message not found").into(),
+    }
+}
+
+/**************************************************************************/
+/* AUDIT actions */
+/**************************************************************************/
+/* the most recent matching events, newest first */
+pub async fn get_audit(request: HttpRequest, db: web::Data<Database>, info: web::Query<AuditParams>) -> HttpResponse {
+    let username = match get_username_from_base64(&db, &request) {
+        Some(name) => { name },
+        None => { return ApiError::unauthorized(CODE_UNAUTHENTICATED, "Invalid credentials").into(); },
+    };
+    
+    if username != "admin" {
+        return ApiError::forbidden(CODE_FORBIDDEN, "Invalid access").into();
+    }
+    
+    let filter = match audit_filter(&info) {
+        Ok(filter) => { filter },
+        Err(s) => { return ApiError::bad_request(CODE_INVALID_PARAMETER, &s).into(); },
+    };
+    
+    HttpResponse::Ok().json(db.get_audit_events_by_filter(&filter, AUDIT_PAGE_SIZE))
+}
+
+/* every matching event, oldest first, streamed a page at a time */
+pub async fn export_audit(request: HttpRequest, db: web::Data<Database>, info: web::Query<AuditParams>, export: web::Query<ExportParams>) -> HttpResponse {
+    let username = match get_username_from_base64(&db, &request) {
+        Some(name) => { name },
+        None => { return ApiError::unauthorized(CODE_UNAUTHENTICATED, "Invalid credentials").into(); },
+    };
+    
+    if username != "admin" {
+        return ApiError::forbidden(CODE_FORBIDDEN, "Invalid access").into();
+    }
+    
+    let format = match export.format() {
+        Some(format) => { format },
+        None => { return ApiError::bad_request(CODE_INVALID_PARAMETER, "Invalid format").into(); },
+    };
+    
+    let filter = match audit_filter(&info) {
+        Ok(filter) => { filter },
+        Err(s) => { return ApiError::bad_request(CODE_INVALID_PARAMETER, &s).into(); },
+    };
+    
+    let db = db.into_inner();
+    
+    /* the cursor is the id of the last event written; None once the last page is done */
+    let stream = futures_util::stream::unfold(Some(0), move |cursor| {
+        let db = Arc::clone(&db);
+        let filter = filter.clone();
+        
+        async move {
+            let after = cursor?;
+            let events = db.get_audit_events_page(&filter, after, EXPORT_PAGE_SIZE);
+            let next = match events.last() {
+                Some(last) if events.len() as i64 == EXPORT_PAGE_SIZE => Some(last.id),
+                _ => None,
+            };
+            
+            let mut chunk = if after == 0 { audit_export_header(format) } else { String::new() };
+            
+            for event in &events {
+                chunk.push_str(&audit_export_row(format, event));
+            }
+            
+            Some((Ok::<_, actix_web::Error>(web::Bytes::from(chunk)), next))
+        }
+    });
+    
+    HttpResponse::Ok()
+        .content_type(export_content_type(format))
+        .insert_header(("Content-Disposition", format!["attachment; filename=\"audit.{}\"", format]))
+        .streaming(stream)
+}
+
+/**************************************************************************/
+/* OPENAPI actions */
+/**************************************************************************/
+pub async fn get_openapi() -> HttpResponse {
+    HttpResponse::Ok().json(openapi())
+}
+
+/**************************************************************************/
+/* supporting functions */
+/**************************************************************************/
+/* v1 entry responses point clients at their typed v2 replacement (RFC 9745, RFC 8288) */
+fn deprecate(headers: &mut HeaderMap, successor: &str) {
+    headers.insert(HeaderName::from_static("deprecation"), HeaderValue::from_static("true"));
+    
+    if let Ok(link) = HeaderValue::from_str(&format!["<{}>; rel=\"successor-version\"", successor]) {
+        headers.insert(header::LINK, link);
+    }
+}
+
+/* malformed bodies and query strings are answered with problem documents too */
+pub(crate) fn json_config() -> web::JsonConfig {
+    web::JsonConfig::default().error_handler(|err, _| ApiError::bad_request(CODE_INVALID_BODY, &err.to_string()).into())
+}
+
+pub(crate) fn query_config() -> web::QueryConfig {
+    web::QueryConfig::default().error_handler(|err, _| ApiError::bad_request(CODE_INVALID_PARAMETER, &err.to_string()).into())
+}
+
+/* the query's filters, limited to the user's own entries unless they are the admin */
+pub(crate) fn get_entry_params(username: &str, info: &EntryParams) -> EntryParams {
+    let username = if username == "admin" { 
+        None
+    } else {
+        Some(String::from(username))
+    };
+    
+    EntryParams {
+        username,
+        ..info.clone()
+    }
+}
+
+/* checks one batch operation the way the single-entry handlers would, without writing anything */
+fn prepare_batch_change(db: &Database, username: &str, user_id: &str, operation: &BatchOperation, touched: &[String]) -> Result<(BatchChange, Option<Entry>), String> {
+    let action = batch_action(operation)?;
+    
+    /* the entry a patch or delete applies to, as it is before the batch */
+    let get_entry = |id: &str| -> Result<Entry, String> {
+        if touched.iter().any(|t| t == id) {
+            return Err(String::from("Entry already changed in this batch"));
+        }
+        
+        match db.get_entry_by_id(id) {
+            Some(entry) if username == "admin" || username == entry.username => {
+                if !entry_etag_matches(operation.if_match.as_deref(), &entry) {
+                    return Err(String::from("Entry has been changed"));
+                }
+                Ok(entry)
+            },
+            _ => Err(String::from("Entry not found")),
+        }
+    };
+    
+    match action {
+        BatchAction::Create(write) => {
+            let mut entry = entry_from_entry_write(&write, username);
+            entry.user_id = String::from(user_id);
+            let reminders = entry_write_list_reminders(db, user_id, &write, &mut entry);
+            
+            entry_write_validate(db, user_id, &entry, &reminders, write.tags.as_deref().unwrap_or_default(), None).map_err(|errors| field_errors_detail(&errors))?;
+            
+            Ok((BatchChange::Create(entry, reminders, write.tags.as_deref().map(tags_normalize)), None))
+        },
+        BatchAction::Patch(id, patch) => {
+            let original = get_entry(&id)?;
+            let original_reminders = reminders_to_write(&db.get_reminders_by_entry_id(&id));
+            let mut entry = original.clone();
+            let reminders = match entry_patch_apply(&mut entry, &patch) {
+                Some(reminders) => reminders,
+                None => original_reminders.clone(),
+            };
+            
+            entry_write_validate(db, &entry.user_id, &entry, &reminders, patch.tags.as_deref().unwrap_or_default(), Some((&original, &original_reminders))).map_err(|errors| field_errors_detail(&errors))?;
+            
+            Ok((BatchChange::Update(entry, reminders, patch.tags.as_deref().map(tags_normalize)), Some(original)))
+        },
+        BatchAction::Delete(id) => {
+            let original = get_entry(&id)?;
+            
+            Ok((BatchChange::Delete(original.clone()), Some(original)))
+        },
+    }
+}
+
+/* the webhook, if the requesting user owns it (or is admin); other users' webhooks are not found, rather than forbidden */
+#[allow(clippy::result_large_err)]
+fn get_webhook_for_request(db: &Database, request: &HttpRequest, webhook_id: &str) -> Result<WebhookSubscription, HttpResponse> {
+    let username = match get_username_from_base64(db, request) {
+        Some(name) => { name },
+        None => { return Err(ApiError::unauthorized(CODE_UNAUTHENTICATED, "Invalid credentials").into()); },
+    };
+    
+    let id = match get_user_id_from_username(db, &username) {
+        Ok(id) => { id },
+        Err(()) => { return Err(ApiError::not_found(CODE_USER_NOT_FOUND, "Matching username not found").into()); },
+    };
+    
+    match db.get_webhook_by_id(webhook_id) {
+        Some(webhook) if username == "admin" || webhook.user_id == id => Ok(webhook),
+        _ => Err(ApiError::not_found(CODE_WEBHOOK_NOT_FOUND, "Webhook not found").into()),
+    }
+}
+
+/* the list, and the requesting username, if they own it (or are admin); other users' lists are not found, rather than forbidden */
+#[allow(clippy::result_large_err)]
+fn get_list_for_request(db: &Database, request: &HttpRequest, list_id: &str) -> Result<(EntryList, String), HttpResponse> {
+    let username = match get_username_from_base64(db, request) {
+        Some(name) => { name },
+        None => { return Err(ApiError::unauthorized(CODE_UNAUTHENTICATED, "Invalid credentials").into()); },
+    };
+    
+    let id = match get_user_id_from_username(db, &username) {
+        Ok(id) => { id },
+        Err(()) => { return Err(ApiError::not_found(CODE_USER_NOT_FOUND, "Matching username not found").into()); },
+    };
+    
+    match db.get_list_by_id(list_id) {
+        Some(list) if username == "admin" || list.user_id == id => Ok((list, username)),
+        _ => Err(ApiError::not_found(CODE_LIST_NOT_FOUND, "List not found").into()),
+    }
+}
+
+/* runs the handler once per Idempotency-Key (when one is sent), storing its response for the configured window to replay to retries */
+pub(crate) async fn idempotent(db: &Database, options: &Options, request: &HttpRequest, scope: &str, body: &[u8], handler: impl FnOnce() -> HttpResponse) -> HttpResponse {
+    let key = match request.headers().get(IDEMPOTENCY_KEY_HEADER) {
+        Some(key) => { key.to_str().unwrap_or("") },
+        None => { return handler(); },
+    };
+    
+    if let Err(s) = idempotency_key_validate(key) {
+        return ApiError::bad_request(CODE_IDEMPOTENCY_KEY_INVALID, &s).into();
+    }
+    
+    let now = chrono::Utc::now().naive_utc();
+    let hash = idempotency_request_hash(request.method().as_str(), request.path(), body);
+    let claim = IdempotencyKey::new(scope, key, &hash, now);
+    let expired_before = now - chrono::Duration::hours(options.idempotency_window_hours() as i64);
+    let abandoned_before = now - chrono::Duration::seconds(IDEMPOTENCY_PENDING_SECONDS);
+    
+    match db.claim_idempotency_key(&claim, expired_before, abandoned_before) {
+        None => {
+            let (response, body) = handler().into_parts();
+            
+            let body = match actix_web::body::to_bytes(body).await {
+                Ok(body) => { body },
+                Err(_) => {
+                    db.delete_idempotency_key_by_id(&claim.id);
+                    return ApiError::new(StatusCode::INTERNAL_SERVER_ERROR, CODE_INTERNAL_ERROR, "Error reading response").into();
+                },
+            };
+            
+            let headers: Vec<(String, String)> = response
+                .headers()
+                .iter()
+                .filter_map(|(name, value)| value.to_str().ok().map(|v| (name.to_string(), String::from(v))))
+                .collect();
+            
+            db.update_idempotency_key_by_id(&claim.id.clone(), IdempotencyKey {
+                response_status: Some(response.status().as_u16() as i32),
+                response_headers: serde_json::to_string(&headers).ok(),
+                response_body: Some(String::from_utf8_lossy(&body).into_owned()),
+                ..claim
+            });
+            
+            response.set_body(body).map_into_boxed_body()
+        },
+        Some(stored) if stored.request_hash != hash => {
+            ApiError::new(StatusCode::UNPROCESSABLE_ENTITY, CODE_IDEMPOTENCY_KEY_REUSED, "Idempotency-Key has already been used for a different request").into()
+        },
+        Some(stored) => {
+            let status = match stored.response_status.and_then(|s| StatusCode::from_u16(s as u16).ok()) {
+                Some(status) => { status },
+                None => { return ApiError::new(StatusCode::CONFLICT, CODE_IDEMPOTENCY_KEY_IN_PROGRESS, "A request with this Idempotency-Key is in progress").into(); },
+            };
+            let mut response = HttpResponse::build(status);
+            
+            for header in stored.headers() {
+                response.insert_header(header);
+            }
+            
+            response
+                .insert_header((IDEMPOTENT_REPLAYED_HEADER, "true"))
+                .body(stored.response_body.unwrap_or_default())
+        },
+    }
+}
+
+/* None for a Content-Type PATCH does not understand */
+pub(crate) fn patch_format(request: &HttpRequest) -> Option<PatchFormat> {
+    let content_type = request.headers().get("content-type").and_then(|h| h.to_str().ok());
+    
+    PatchFormat::from_content_type(content_type)
+}
+
+pub(crate) fn patch_unsupported() -> HttpResponse {
+    ApiError::new(StatusCode::UNSUPPORTED_MEDIA_TYPE, CODE_UNSUPPORTED_MEDIA_TYPE, "Unsupported patch format")
+        .with_header("Accept-Patch", ACCEPT_PATCH)
+        .into()
+}
+
+fn entry_patch_from_body(db: &Database, format: PatchFormat, entry: &Entry, body: &str) -> Result<EntryPatch, String> {
+    if format == PatchFormat::Json {
+        return serde_json::from_str(body).map_err(|e| e.to_string());
+    }
+    
+    let before = EntryDocument::new(entry, reminders_to_write(&db.get_reminders_by_entry_id(&entry.id)), db.get_tags_by_entry_id(&entry.id));
+    let mut document = serde_json::to_value(&before).map_err(|e| e.to_string())?;
+    
+    patch_document(format, &mut document, body)?;
+    entry_patch_from_document(&before, document)
+}
+
+fn user_patch_from_body(format: PatchFormat, user: &User, body: &str) -> Result<User, String> {
+    if format == PatchFormat::Json {
+        let patch: UserPatch = serde_json::from_str(body).map_err(|e| e.to_string())?;
+        
+        return Ok(User {
+            password: patch.password.unwrap_or(user.password.clone()),
+            utc_offset: patch.utc_offset.or(user.utc_offset.clone()),
+            ..user.clone()
+        });
+    }
+    
+    let mut document = serde_json::to_value(UserDocument::new(user)).map_err(|e| e.to_string())?;
+    
+    patch_document(format, &mut document, body)?;
+    user_from_document(user, document)
+}
+
+/* the write paths shared by every API version: validate, store, then send the webhook events */
+pub(crate) fn entry_create(db: &Database, username: &str, user_id: &str, new_entry: &EntryWrite) -> Result<EntryRead, ApiError> {
+    let tags = new_entry.tags.clone().unwrap_or_default();
+    let mut entry = entry_from_entry_write(new_entry, username);
+    let reminders = entry_write_list_reminders(db, user_id, new_entry, &mut entry);
+    
+    entry_write_validate(db, user_id, &entry, &reminders, &tags, None).map_err(ApiError::validation)?;
+    
+    match db.create_entry(entry, &reminders, &tags_normalize(&tags), user_id, username) {
+        Ok(read) => {
+            events::dispatch(db, EVENT_CREATED, &read.entry);
+            Ok(read)
+        },
+        Err(err) => Err(ApiError::new(StatusCode::INTERNAL_SERVER_ERROR, CODE_INTERNAL_ERROR, &err.to_string())),
+    }
+}
+
+pub(crate) fn entry_replace(db: &Database, username: &str, entry: &Entry, write: &EntryWrite) -> Result<EntryRead, ApiError> {
+    let reminders = entry_write_reminders(write);
+    let original_reminders = reminders_to_write(&db.get_reminders_by_entry_id(&entry.id));
+    let updated_entry = entry_from_entry_write_edit(write, entry);
+    
+    entry_write_validate(db, &entry.user_id, &updated_entry, &reminders, write.tags.as_deref().unwrap_or_default(), Some((entry, &original_reminders))).map_err(ApiError::validation)?;
+    
+    entry_update_write(db, username, entry, updated_entry, &reminders, write.tags.as_deref())
+}
+
+pub(crate) fn entry_update(db: &Database, username: &str, entry: &Entry, patch: &EntryPatch) -> Result<EntryRead, ApiError> {
+    let original_reminders = reminders_to_write(&db.get_reminders_by_entry_id(&entry.id));
+    let mut updated_entry = entry.clone();
+    let reminders = match entry_patch_apply(&mut updated_entry, patch) {
+        Some(reminders) => reminders,
+        None => original_reminders.clone(),
+    };
+    
+    entry_write_validate(db, &entry.user_id, &updated_entry, &reminders, patch.tags.as_deref().unwrap_or_default(), Some((entry, &original_reminders))).map_err(ApiError::validation)?;
+    
+    entry_update_write(db, username, entry, updated_entry, &reminders, patch.tags.as_deref())
+}
+
+/* the write is refused if the entry changed after it was read (If-Match only covers the client's copy, not the time since) */
+fn entry_update_write(db: &Database, username: &str, entry: &Entry, updated_entry: Entry, reminders: &[ReminderWrite], tags: Option<&[String]>) -> Result<EntryRead, ApiError> {
+    let tags = tags.map(tags_normalize);
+    
+    match db.update_entry_by_id(&entry.id, updated_entry, Some(reminders), tags.as_deref(), username) {
+        Some(read) => {
+            events::dispatch_update(db, entry, &read.entry);
+            Ok(read)
+        },
+        None => Err(ApiError::new(StatusCode::PRECONDITION_FAILED, CODE_PRECONDITION_FAILED, "Entry has been changed")),
+    }
+}
+
+/* moves the entry to the trash, unless it changed after it was read */
+pub(crate) fn entry_delete(db: &Database, username: &str, entry: &Entry) -> Result<(), ApiError> {
+    if db.delete_entry_unchanged(entry, username) == 0 {
+        return Err(ApiError::new(StatusCode::PRECONDITION_FAILED, CODE_PRECONDITION_FAILED, "Entry has been changed"));
+    }
+    
+    events::dispatch(db, EVENT_DELETED, entry);
+    Ok(())
+}
+
+/* entry_validate_with_tags, and that the entry's list is one of its owner's */
+fn entry_write_validate(db: &Database, user_id: &str, entry: &Entry, reminders: &[ReminderWrite], tags: &[String], orig: Option<(&Entry, &[ReminderWrite])>) -> Result<(), Vec<FieldError>> {
+    let mut errors = entry_validate_with_tags(entry, reminders, tags, orig).err().unwrap_or_default();
+    
+    if entry.list_id.is_some() && get_entry_list(db, user_id, entry).is_none() {
+        errors.push(FieldError::new("/list_id", CODE_INVALID_LIST, "List not found"));
+    }
+    
+    field_errors_result(errors)
+}
+
+fn get_entry_list(db: &Database, user_id: &str, entry: &Entry) -> Option<EntryList> {
+    entry.list_id
+        .as_deref()
+        .and_then(|list_id| db.get_list_by_id(list_id))
+        .filter(|list| list.user_id == user_id)
+}
+
+/* a new entry that sets no reminders gets its list's default reminder, unless that is too great for the due date */
+fn entry_write_list_reminders(db: &Database, user_id: &str, write: &EntryWrite, entry: &mut Entry) -> Vec<ReminderWrite> {
+    let reminders = entry_write_reminders(write);
+    
+    if write.reminder.is_some() || write.reminders.is_some() {
+        return reminders;
+    }
+    
+    let defaults = get_entry_list(db, user_id, entry).map(|list| list.default_reminders()).unwrap_or_default();
+    
+    match reminders_validate(&defaults, entry_due_date(entry).ok().as_ref()) {
+        Ok(()) if !defaults.is_empty() => {
+            entry.reminder = reminder_legacy_from_list(&defaults);
+            defaults
+        },
+        _ => reminders,
+    }
+}
+
+/* If-Match on a write: false when the client's copy of the entry is out of date */
+pub(crate) fn entry_if_match(request: &HttpRequest, entry: &Entry) -> bool {
+    let if_match = request.headers().get("If-Match").and_then(|h| h.to_str().ok());
+    
+    entry_etag_matches(if_match, entry)
+}
+
+/* the same check for the "if_match" of a batch operation or socket command (none given always matches) */
+pub(crate) fn entry_etag_matches(if_match: Option<&str>, entry: &Entry) -> bool {
+    caldav::preconditions_met(if_match, None, Some(&entry_etag(entry)))
+}
+
+/* If-None-Match on a read: true when the client's copy is still current */
+pub(crate) fn entry_not_modified(request: &HttpRequest, entry: &Entry) -> bool {
+    let if_none_match = request.headers().get("If-None-Match").and_then(|h| h.to_str().ok());
+    
+    !caldav::preconditions_met(None, if_none_match, Some(&entry_etag(entry)))
+}
+
+/* an updated entry, with the ETag to send back in If-Match next time */
+fn entry_response(read: EntryRead) -> HttpResponse {
+    HttpResponse::Ok().insert_header(("ETag", entry_etag(&read.entry))).json(read)
+}
+
+pub(crate) fn get_entry_read(db: &Database, entry: Entry) -> EntryRead {
+    let reminders = db.get_reminders_by_entry_id(&entry.id);
+    let tags = db.get_tags_by_entry_id(&entry.id);
+    
+    EntryRead::new(entry, reminders, tags)
+}
+
+pub(crate) fn get_entries_read(db: &Database, entries: Vec<Entry>) -> Vec<EntryRead> {
+    let ids: Vec<String> = entries.iter().map(|e| e.id.clone()).collect();
+    let mut reminders: HashMap<String, Vec<Reminder>> = HashMap::new();
+    
+    let mut tags: HashMap<String, Vec<String>> = HashMap::new();
+    
+    for reminder in db.get_reminders_by_entry_ids(&ids) {
+        reminders.entry(reminder.entry_id.clone()).or_default().push(reminder);
+    }
+    for (entry_id, name) in db.get_tags_by_entry_ids(&ids) {
+        tags.entry(entry_id).or_default().push(name);
+    }
+    
+    entries
+        .into_iter()
+        .map(|entry| {
+            let list = reminders.remove(&entry.id).unwrap_or_default();
+            let names = tags.remove(&entry.id).unwrap_or_default();
+            EntryRead::new(entry, list, names)
+        })
+        .collect()
+}
+
+/* list_validate, and that no other list of the user's has the name */
+fn validate_list(db: &Database, list: &EntryList) -> Result<(), Vec<FieldError>> {
+    let mut errors = list_validate(list).err().unwrap_or_default();
+    
+    if db.get_lists_by_user_id(&list.user_id).iter().any(|l| l.id != list.id && l.name.to_lowercase() == list.name.to_lowercase()) {
+        errors.push(FieldError::new("/name", CODE_NAME_IN_USE, "Name already in use"));
+    }
+    
+    field_errors_result(errors)
+}
+
+/* a webhook url must not point into the server's own network */
+fn validate_webhook_target(options: &Options, url: Option<&str>) -> Result<(), Vec<FieldError>> {
+    match url {
+        Some(url) => {
+            match TargetResolver::new(options.webhook_allowed_hosts()).check_url(url) {
+                Ok(()) => Ok(()),
+                Err(_) => Err(vec![FieldError::new("/url", CODE_URL_NOT_ALLOWED, "Webhook url not allowed")]),
+            }
+        },
+        None => Ok(()),
+    }
+}
+
+fn validate_user(db: &Database, test_user: &User) -> Result<(), Vec<FieldError>> {
+    let users = db.get_users();
+    let mut errors = Vec::new();
+    
+    if users.iter().any(|user| user.username == test_user.username) {
+        errors.push(FieldError::new("/username", CODE_USERNAME_IN_USE, "Username in use"));
+    }
+    
+    if users.iter().any(|user| user.email == test_user.email) {
+        errors.push(FieldError::new("/email", CODE_EMAIL_IN_USE, "Email in use"));
+    }
+    
+    if let Err(mut partial) = validate_user_partial(test_user) {
+        errors.append(&mut partial);
+    }
+    
+    field_errors_result(errors)
+}
+
+#[allow(clippy::bool_comparison)]
+fn validate_user_partial(test_user: &User) -> Result<(), Vec<FieldError>> {
+    let mut errors = Vec::new();
+    
+    if validate_email(&test_user.email) == false {
+        errors.push(FieldError::new("/email", CODE_INVALID_EMAIL, "Invalid email address format"));
+    }
+    
+    if test_user.utc_offset.is_some() && validate_utc_offset(&test_user.utc_offset.clone().unwrap()) == false {
+        errors.push(FieldError::new("/utc_offset", CODE_INVALID_UTC_OFFSET, "Invalid utc-offset"));
+    }
+    
+    field_errors_result(errors)
+}
+
+/* every check is a login: a success is recorded once per login window, and failures are counted per window (see AuditLogins) */
+pub(crate) fn get_username_from_base64(db: &Database, request: &HttpRequest) -> Option<String> {
+    let user64 = get_header_base64(request)?;
+    let attempted = get_username_attempted(&user64);
+    
+    if let Some(user) = attempted.as_deref().and_then(|name| db.get_user_by_username(name)) {
+        if check_user(&user64, &user.username, &user.password) {
+            let logins = request.app_data::<web::Data<AuditLogins>>();
+            let ip = request_ip(request);
+            
+            if logins.is_none_or(|l| l.record(&user.username, ip.as_deref(), chrono::Utc::now().naive_utc())) {
+                audit(db, request, AUDIT_LOGIN, Some(&user.username), None, None);
+            }
+            return Some(user.username);
+        }
+    }
+    
+    let logins = request.app_data::<web::Data<AuditLogins>>();
+    let ip = request_ip(request);
+    let now = chrono::Utc::now().naive_utc();
+    
+    match logins.map(|l| l.record_failed(attempted.as_deref(), ip.as_deref(), now)) {
+        None | Some(Some((1, _))) => {
+            audit(db, request, AUDIT_LOGIN_FAILED, attempted.as_deref(), None, None);
+        },
+        Some(Some((count, start))) => {
+            let detail = format!["{} failed attempts since {}", count, start.format("%Y-%m-%dT%H:%M:%S")];
+            audit(db, request, AUDIT_LOGIN_FAILED, attempted.as_deref(), None, Some(&detail));
+        },
+        Some(None) => {},
+    }
+    
+    None
+}
+
+/* the username part of the credentials, if they can be read */
+fn get_username_attempted(b64: &str) -> Option<String> {
+    let decoded = general_purpose::STANDARD.decode(b64).ok()?;
+    let decoded = String::from_utf8(decoded).ok()?;
+    
+    decoded.split_once(':').map(|(name, _)| String::from(name))
+}
+
+/* the address of the connection (not of any forwarding headers, which the client could set) */
+pub(crate) fn request_ip(request: &HttpRequest) -> Option<String> {
+    request.peer_addr().map(|addr| addr.ip().to_string())
+}
+
+pub(crate) fn audit(db: &Database, request: &HttpRequest, action: &str, actor: Option<&str>, target: Option<&str>, detail: Option<&str>) {
+    let ip = request_ip(request);
+    
+    db.create_audit_event(AuditEventWrite::new(action, actor, target, ip.as_deref(), detail, chrono::Utc::now().naive_utc()));
+}
+
+fn get_header_base64<'a>(request: &'a HttpRequest) -> Option<String> {
+    let response = request.headers().get("authorization")?.to_str();
+    
+    if let Ok(r) = response {
+        let r = String::from(r);
+        let args: Vec<_> = r.split_whitespace().collect();
+        
+        if args.len() == 2 && args[0].to_lowercase() == "basic" {
+            return Some(String::from(args[1]));
+        }        
+    }
+    
+    None
+}
+
+fn check_user(b64: &str, user_name: &str, user_password: &str) -> bool {
+    let user_b64 = get_b64(user_name, user_password);
+    
+    b64 == user_b64
+}
+
+fn get_b64(user_name: &str, user_password: &str) -> String {
+    let combined = format!["{}:{}", user_name, user_password];
+    
+    String::from(
+        general_purpose::STANDARD.encode(combined.as_bytes())
+    )
+}
+
+fn validate_email(email: &str) -> bool {
     if let Some(c_at) = email.find('@') {
         if let Some(c_dot) = email.find('.') {
             if c_at < c_dot {
@@ -467,14 +2053,10 @@ fn validate_utc_offset(utc_offset_str: &str) -> bool {
     }
 }
 
-fn get_user_id_from_username(db: &Database, username: &str) -> Result<String, ()> {
-    let users = db.get_users();
-    
-    for user in users {
-        if user.username == username {
-            return Ok(user.id);
-        }
+#[allow(clippy::needless_return)]
+pub(crate) fn get_user_id_from_username(db: &Database, username: &str) -> Result<String, ()> {
+    match db.get_user_by_username(username) {
+        Some(user) => return Ok(user.id),
+        None => return Err(()),
     }
-    
-    Err(())
 }
\ No newline at end of file
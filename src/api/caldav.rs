@@ -0,0 +1,409 @@
+/*************************************************************************
+    "task_server"
+    api/caldav.rs
+    10/18/26
+    © Matthew Bentivegna
+*************************************************************************/
+use actix_web::{
+    HttpRequest,
+    HttpResponse,
+    http::{
+        Method,
+        StatusCode,
+    },
+    web::{
+        self,
+        Data,
+    },
+};
+use diesel::result::{
+    DatabaseErrorKind,
+    Error::DatabaseError,
+};
+use crate::repository::database::Database;
+use crate::events::events;
+use crate::api::error::REALM;
+use crate::api::api::{
+    get_entry_read,
+    get_entries_read,
+    get_username_from_base64,
+};
+use crate::calendar::{
+    ical,
+    caldav::{
+        self,
+        Depth,
+        Report,
+        Resource,
+        PropResponse,
+        NS_DAV,
+        NS_CALDAV,
+        CONTENT_TYPE_TODO,
+    },
+};
+use crate::models::{
+    user::User,
+    entry::{
+        Entry,
+        EntryParams,
+        entry_etag,
+        entry_from_entry_write,
+        entry_from_entry_write_edit,
+        entry_write_reminders,
+    },
+    reminder::reminders_to_write,
+    webhook::{
+        EVENT_CREATED,
+        EVENT_DELETED,
+    },
+};
+
+const DAV_HEADER: &str = "1, 3, calendar-access";
+const ALLOW_HEADER: &str = "OPTIONS, GET, PUT, DELETE, PROPFIND, REPORT";
+
+pub fn config(cfg: &mut web::ServiceConfig) {
+    let propfind = Method::from_bytes(b"PROPFIND").unwrap();
+    let report = Method::from_bytes(b"REPORT").unwrap();
+
+    cfg
+        .route("/.well-known/caldav", web::route().to(well_known))
+        .service(
+            web::resource(["/dav", "/dav/"])
+                .route(web::method(propfind.clone()).to(propfind_root))
+                .route(web::method(Method::OPTIONS).to(options))
+        )
+        .service(
+            web::resource("/dav/{user}/")
+                .route(web::method(propfind.clone()).to(propfind_principal))
+                .route(web::method(Method::OPTIONS).to(options))
+        )
+        .service(
+            web::resource("/dav/{user}/tasks/")
+                .route(web::method(propfind).to(propfind_calendar))
+                .route(web::method(report).to(report_calendar))
+                .route(web::method(Method::OPTIONS).to(options))
+        )
+        .service(
+            web::resource("/dav/{user}/tasks/{name}.ics")
+                .route(web::get().to(get_todo))
+                .route(web::put().to(put_todo))
+                .route(web::delete().to(delete_todo))
+                .route(web::method(Method::OPTIONS).to(options))
+        );
+}
+
+/**************************************************************************/
+/* DISCOVERY actions */
+/**************************************************************************/
+pub async fn well_known() -> HttpResponse {
+    HttpResponse::MovedPermanently()
+        .insert_header(("Location", caldav::DAV_ROOT))
+        .finish()
+}
+
+pub async fn options() -> HttpResponse {
+    HttpResponse::Ok()
+        .insert_header(("DAV", DAV_HEADER))
+        .insert_header(("Allow", ALLOW_HEADER))
+        .finish()
+}
+
+pub async fn propfind_root(request: HttpRequest, db: Data<Database>, body: String) -> HttpResponse {
+    let username = match authenticate(&db, &request) {
+        Ok(name) => { name },
+        Err(response) => { return response; },
+    };
+
+    let props = match caldav::parse_propfind(&body) {
+        Ok(props) => { props },
+        Err(s) => { return HttpResponse::BadRequest().body(s); },
+    };
+
+    multistatus(&[Resource::Root { username: &username }.response(props.as_deref())])
+}
+
+pub async fn propfind_principal(request: HttpRequest, db: Data<Database>, user: web::Path<String>, body: String) -> HttpResponse {
+    let (owner, _) = match authorize(&db, &request, &user) {
+        Ok(authorized) => { authorized },
+        Err(response) => { return response; },
+    };
+
+    let props = match caldav::parse_propfind(&body) {
+        Ok(props) => { props },
+        Err(s) => { return HttpResponse::BadRequest().body(s); },
+    };
+
+    let mut responses = vec![Resource::Principal { username: &owner.username }.response(props.as_deref())];
+
+    if caldav::parse_depth(depth_header(&request)) == Depth::One {
+        let ctag = caldav::calendar_ctag(&get_calendar_entries(&db, &owner));
+        responses.push(Resource::Calendar { username: &owner.username, ctag }.response(props.as_deref()));
+    }
+
+    multistatus(&responses)
+}
+
+/**************************************************************************/
+/* CALENDAR actions */
+/**************************************************************************/
+pub async fn propfind_calendar(request: HttpRequest, db: Data<Database>, user: web::Path<String>, body: String) -> HttpResponse {
+    let (owner, _) = match authorize(&db, &request, &user) {
+        Ok(authorized) => { authorized },
+        Err(response) => { return response; },
+    };
+
+    let props = match caldav::parse_propfind(&body) {
+        Ok(props) => { props },
+        Err(s) => { return HttpResponse::BadRequest().body(s); },
+    };
+
+    let entries = get_calendar_entries(&db, &owner);
+    let ctag = caldav::calendar_ctag(&entries);
+    let mut responses = vec![Resource::Calendar { username: &owner.username, ctag }.response(props.as_deref())];
+
+    if caldav::parse_depth(depth_header(&request)) == Depth::One {
+        let entries = get_entries_read(&db, entries);
+
+        for entry in &entries {
+            responses.push(Resource::Todo { username: &owner.username, entry }.response(props.as_deref()));
+        }
+    }
+
+    multistatus(&responses)
+}
+
+pub async fn report_calendar(request: HttpRequest, db: Data<Database>, user: web::Path<String>, body: String) -> HttpResponse {
+    let (owner, _) = match authorize(&db, &request, &user) {
+        Ok(authorized) => { authorized },
+        Err(response) => { return response; },
+    };
+
+    match caldav::parse_report(&body) {
+        Ok(Report::Query { props }) => {
+            let entries = get_entries_read(&db, get_calendar_entries(&db, &owner));
+            let responses: Vec<PropResponse> = entries
+                .iter()
+                .map(|entry| Resource::Todo { username: &owner.username, entry }.response(Some(&props)))
+                .collect();
+
+            multistatus(&responses)
+        },
+        Ok(Report::Multiget { props, hrefs }) => {
+            let responses: Vec<PropResponse> = hrefs
+                .iter()
+                .map(|href| {
+                    let entry = caldav::todo_id_from_href(&owner.username, href)
+                        .and_then(|entry_id| get_calendar_entry(&db, &owner, &entry_id));
+
+                    match entry {
+                        Some(entry) => {
+                            let entry = get_entry_read(&db, entry);
+                            Resource::Todo { username: &owner.username, entry: &entry }.response(Some(&props))
+                        },
+                        None => caldav::not_found_response(href),
+                    }
+                })
+                .collect();
+
+            multistatus(&responses)
+        },
+        Ok(Report::Unsupported(_)) => {
+            precondition_failed(StatusCode::FORBIDDEN, NS_DAV, "supported-report")
+        },
+        Err(s) => HttpResponse::BadRequest().body(s),
+    }
+}
+
+/**************************************************************************/
+/* TODO actions */
+/**************************************************************************/
+pub async fn get_todo(request: HttpRequest, db: Data<Database>, path: web::Path<(String, String)>) -> HttpResponse {
+    let (user, name) = path.into_inner();
+
+    let (owner, _) = match authorize(&db, &request, &user) {
+        Ok(authorized) => { authorized },
+        Err(response) => { return response; },
+    };
+
+    match get_calendar_entry(&db, &owner, &name) {
+        Some(entry) => {
+            let etag = entry_etag(&entry);
+            let entry = get_entry_read(&db, entry);
+
+            HttpResponse::Ok()
+                .content_type(CONTENT_TYPE_TODO)
+                .insert_header(("ETag", etag))
+                .body(caldav::todo_calendar(&entry))
+        },
+        None => HttpResponse::NotFound().body("Entry not found"),
+    }
+}
+
+/* creates or replaces the entry named by the resource */
+pub async fn put_todo(request: HttpRequest, db: Data<Database>, path: web::Path<(String, String)>, body: String) -> HttpResponse {
+    let (user, name) = path.into_inner();
+
+    let (owner, username) = match authorize(&db, &request, &user) {
+        Ok(authorized) => { authorized },
+        Err(response) => { return response; },
+    };
+
+    if !caldav::resource_name_valid(&name) {
+        return HttpResponse::BadRequest().body("Invalid resource name");
+    }
+
+    /* ids are global, so a name in use by someone else's entry cannot be taken */
+    let existing = db.get_entry_by_id(&name);
+
+    if let Some(entry) = &existing {
+        if entry.username != owner.username {
+            return HttpResponse::Conflict().body("Resource name in use");
+        }
+    }
+
+    if !preconditions_met(&request, existing.as_ref()) {
+        return HttpResponse::PreconditionFailed().finish();
+    }
+
+    let write = match ical::parse_todo(&body).and_then(|todo| caldav::todo_entry_write(&todo)) {
+        Ok(write) => { write },
+        Err(_) => { return precondition_failed(StatusCode::FORBIDDEN, NS_CALDAV, "valid-calendar-data"); },
+    };
+    let reminders = entry_write_reminders(&write);
+
+    match existing {
+        Some(orig) => {
+            let orig_reminders = reminders_to_write(&db.get_reminders_by_entry_id(&orig.id));
+            let reminders = caldav::reminders_keep_channels(&orig_reminders, &reminders);
+            let updated_entry = entry_from_entry_write_edit(&write, &orig);
+
+            if caldav::todo_validate(&updated_entry, &reminders, Some((&orig, &orig_reminders))).is_err() {
+                return precondition_failed(StatusCode::FORBIDDEN, NS_CALDAV, "valid-calendar-object-resource");
+            }
+
+            /* tags are not part of the calendar data, so they are kept */
+            match db.update_entry_by_id(&orig.id, updated_entry, Some(&reminders), None, &username) {
+                Some(read) => {
+                    events::dispatch_update(&db, &orig, &read.entry);
+
+                    HttpResponse::NoContent()
+                        .insert_header(("ETag", entry_etag(&read.entry)))
+                        .finish()
+                },
+                None => HttpResponse::PreconditionFailed().finish(),
+            }
+        },
+        None => {
+            let new_entry = entry_from_entry_write(&write, &owner.username);
+
+            if caldav::todo_validate(&new_entry, &reminders, None).is_err() {
+                return precondition_failed(StatusCode::FORBIDDEN, NS_CALDAV, "valid-calendar-object-resource");
+            }
+
+            match db.create_entry_with_id(new_entry, &reminders, &[], &owner.id, &name, &username) {
+                Ok(read) => {
+                    events::dispatch(&db, EVENT_CREATED, &read.entry);
+
+                    HttpResponse::Created()
+                        .insert_header(("ETag", entry_etag(&read.entry)))
+                        .finish()
+                },
+                Err(DatabaseError(DatabaseErrorKind::UniqueViolation, _)) => HttpResponse::Conflict().body("Resource name in use"),
+                Err(err) => HttpResponse::InternalServerError().body(err.to_string()),
+            }
+        },
+    }
+}
+
+pub async fn delete_todo(request: HttpRequest, db: Data<Database>, path: web::Path<(String, String)>) -> HttpResponse {
+    let (user, name) = path.into_inner();
+
+    let (owner, username) = match authorize(&db, &request, &user) {
+        Ok(authorized) => { authorized },
+        Err(response) => { return response; },
+    };
+
+    match get_calendar_entry(&db, &owner, &name) {
+        Some(entry) => {
+            if !preconditions_met(&request, Some(&entry)) {
+                return HttpResponse::PreconditionFailed().finish();
+            }
+
+            if db.delete_entry_unchanged(&entry, &username) == 0 {
+                return HttpResponse::PreconditionFailed().finish();
+            }
+
+            events::dispatch(&db, EVENT_DELETED, &entry);
+
+            HttpResponse::NoContent().finish()
+        },
+        None => HttpResponse::NotFound().body("Entry not found"),
+    }
+}
+
+/**************************************************************************/
+/* supporting functions */
+/**************************************************************************/
+/* calendar clients only send credentials after being challenged */
+#[allow(clippy::result_large_err)]
+fn authenticate(db: &Database, request: &HttpRequest) -> Result<String, HttpResponse> {
+    match get_username_from_base64(db, request) {
+        Some(name) => Ok(name),
+        None => Err(
+            HttpResponse::Unauthorized()
+                .insert_header(("WWW-Authenticate", format!["Basic realm=\"{}\"", REALM]))
+                .body("Username not found")
+        ),
+    }
+}
+
+/* the owner of the calendar in the URL and the requesting user's name, if they may access it */
+#[allow(clippy::result_large_err)]
+fn authorize(db: &Database, request: &HttpRequest, user: &str) -> Result<(User, String), HttpResponse> {
+    let username = authenticate(db, request)?;
+
+    if username != "admin" && username != user {
+        return Err(HttpResponse::Forbidden().body("Invalid access"));
+    }
+
+    match db.get_users().into_iter().find(|u| u.username == user) {
+        Some(owner) => Ok((owner, username)),
+        None => Err(HttpResponse::NotFound().body("User not found")),
+    }
+}
+
+fn get_calendar_entries(db: &Database, owner: &User) -> Vec<Entry> {
+    let f = EntryParams {
+        username: Some(owner.username.clone()),
+        ..Default::default()
+    };
+
+    db.get_entries_by_filter(&f)
+}
+
+fn get_calendar_entry(db: &Database, owner: &User, entry_id: &str) -> Option<Entry> {
+    db.get_entry_by_id(entry_id).filter(|e| e.username == owner.username)
+}
+
+fn depth_header(request: &HttpRequest) -> Option<&str> {
+    request.headers().get("Depth").and_then(|h| h.to_str().ok())
+}
+
+fn preconditions_met(request: &HttpRequest, entry: Option<&Entry>) -> bool {
+    let header = |name: &str| request.headers().get(name).and_then(|h| h.to_str().ok());
+    let etag = entry.map(entry_etag);
+
+    caldav::preconditions_met(header("If-Match"), header("If-None-Match"), etag.as_deref())
+}
+
+fn multistatus(responses: &[PropResponse]) -> HttpResponse {
+    HttpResponse::build(StatusCode::MULTI_STATUS)
+        .content_type("application/xml; charset=utf-8")
+        .body(caldav::multistatus(responses))
+}
+
+fn precondition_failed(status: StatusCode, ns: &str, condition: &str) -> HttpResponse {
+    HttpResponse::build(status)
+        .content_type("application/xml; charset=utf-8")
+        .body(caldav::error_body(ns, condition))
+}
\ No newline at end of file
@@ -0,0 +1,186 @@
+/*************************************************************************
+    "task_server"
+    api/error.rs
+    10/18/26
+    © Matthew Bentivegna
+*************************************************************************/
+use std::fmt;
+use serde::Serialize;
+use actix_web::{
+    HttpResponse,
+    ResponseError,
+    http::StatusCode,
+};
+use crate::models::problem::{
+    FieldError,
+    CODE_VALIDATION_FAILED,
+};
+
+pub const CONTENT_TYPE_PROBLEM: &str = "application/problem+json";
+pub const REALM: &str = "task_server";
+
+/* an error response, rendered as an RFC 7807 problem document */
+#[derive(Debug, Clone)]
+pub struct ApiError {
+    pub status: StatusCode,
+    pub code: String,
+    pub detail: String,
+    pub errors: Vec<FieldError>,
+    pub headers: Vec<(String, String)>,
+}
+
+/* "type" is left as about:blank, so "title" is the status text; "code" and "errors" are extension members */
+#[derive(Debug, Serialize)]
+struct Problem<'a> {
+    #[serde(rename = "type")]
+    problem_type: &'a str,
+    title: &'a str,
+    status: u16,
+    code: &'a str,
+    detail: &'a str,
+    #[serde(skip_serializing_if = "<[_]>::is_empty")]
+    errors: &'a [FieldError],
+}
+
+impl ApiError {
+    pub fn new(status: StatusCode, code: &str, detail: &str) -> Self {
+        Self {
+            status,
+            code: String::from(code),
+            detail: String::from(detail),
+            errors: vec![],
+            headers: vec![],
+        }
+    }
+
+    pub fn bad_request(code: &str, detail: &str) -> Self {
+        Self::new(StatusCode::BAD_REQUEST, code, detail)
+    }
+
+    /* a 401 must carry the challenge (RFC 9110), so clients know to send basic credentials */
+    pub fn unauthorized(code: &str, detail: &str) -> Self {
+        Self::new(StatusCode::UNAUTHORIZED, code, detail)
+            .with_header("WWW-Authenticate", &format!["Basic realm=\"{}\"", REALM])
+    }
+
+    pub fn forbidden(code: &str, detail: &str) -> Self {
+        Self::new(StatusCode::FORBIDDEN, code, detail)
+    }
+
+    pub fn not_found(code: &str, detail: &str) -> Self {
+        Self::new(StatusCode::NOT_FOUND, code, detail)
+    }
+
+    /* every failed check of the request, each with its own code and pointer */
+    pub fn validation(errors: Vec<FieldError>) -> Self {
+        let detail = match errors.len() {
+            1 => errors[0].detail.clone(),
+            n => format!["{} fields are invalid", n],
+        };
+
+        Self {
+            errors,
+            ..Self::new(StatusCode::BAD_REQUEST, CODE_VALIDATION_FAILED, &detail)
+        }
+    }
+
+    pub fn with_header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((String::from(name), String::from(value)));
+        self
+    }
+
+    pub fn body(&self) -> String {
+        let problem = Problem {
+            problem_type: "about:blank",
+            title: self.status.canonical_reason().unwrap_or(""),
+            status: self.status.as_u16(),
+            code: &self.code,
+            detail: &self.detail,
+            errors: &self.errors,
+        };
+
+        serde_json::to_string(&problem).unwrap_or_default()
+    }
+}
+
+impl fmt::Display for ApiError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.code, self.detail)
+    }
+}
+
+impl ResponseError for ApiError {
+    fn status_code(&self) -> StatusCode {
+        self.status
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        let mut response = HttpResponse::build(self.status);
+
+        for header in &self.headers {
+            response.insert_header(header.clone());
+        }
+
+        response
+            .content_type(CONTENT_TYPE_PROBLEM)
+            .body(self.body())
+    }
+}
+
+impl From<ApiError> for HttpResponse {
+    fn from(error: ApiError) -> Self {
+        error.error_response()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::problem::{
+        CODE_ENTRY_NOT_FOUND,
+        CODE_DATE_PASSED,
+        CODE_TASK_MISSING,
+        CODE_UNAUTHENTICATED,
+    };
+
+    #[test]
+    fn problem_document() {
+        let body: serde_json::Value = serde_json::from_str(&ApiError::not_found(CODE_ENTRY_NOT_FOUND, "Entry not found").body()).unwrap();
+
+        assert_eq!(body, serde_json::json!({
+            "type": "about:blank",
+            "title": "Not Found",
+            "status": 404,
+            "code": "entry_not_found",
+            "detail": "Entry not found",
+        }));
+    }
+
+    #[test]
+    fn validation_errors() {
+        let error = ApiError::validation(vec![
+            FieldError::new("/day", CODE_DATE_PASSED, "Date has passed"),
+            FieldError::new("/task", CODE_TASK_MISSING, "No task present"),
+        ]);
+        let body: serde_json::Value = serde_json::from_str(&error.body()).unwrap();
+
+        assert!(
+            body["status"] == 400 &&
+            body["code"] == "validation_failed" &&
+            body["detail"] == "2 fields are invalid" &&
+            body["errors"][1]["pointer"] == "/task" &&
+            body["errors"][1]["code"] == "task_missing"
+        );
+    }
+
+    #[test]
+    fn response() {
+        let response = HttpResponse::from(ApiError::unauthorized(CODE_UNAUTHENTICATED, "Invalid credentials"));
+
+        assert!(
+            response.status() == StatusCode::UNAUTHORIZED &&
+            response.headers().get("content-type").unwrap() == CONTENT_TYPE_PROBLEM &&
+            response.headers().get("WWW-Authenticate").unwrap() == "Basic realm=\"task_server\""
+        );
+    }
+}
\ No newline at end of file
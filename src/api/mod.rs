@@ -4,4 +4,8 @@
 	10/17/23
 	© Matthew Bentivegna
 *************************************************************************/
-pub mod api;
\ No newline at end of file
+pub mod api;
+pub mod caldav;
+pub mod error;
+pub mod socket;
+pub mod v2;
\ No newline at end of file
@@ -0,0 +1,227 @@
+/*************************************************************************
+    "task_server"
+    api/socket.rs
+    10/18/26
+    © Matthew Bentivegna
+*************************************************************************/
+use std::{
+    sync::Arc,
+    time::{
+        Duration,
+        Instant,
+    },
+};
+use actix_web::{
+    HttpRequest,
+    HttpResponse,
+    http::StatusCode,
+    web::{
+        self,
+        Data,
+    },
+};
+use actix_ws::{
+    Message,
+    MessageStream,
+    Session,
+};
+use futures_util::future::{
+    Either,
+    select,
+};
+use crate::repository::database::Database;
+use crate::events::events::EventSubscription;
+use crate::api::error::ApiError;
+use crate::api::api::{
+    entry_create,
+    entry_delete,
+    entry_etag_matches,
+    entry_update,
+    get_username_from_base64,
+    get_user_id_from_username,
+};
+use crate::models::{
+    entry::Entry,
+    batch::{
+        BatchAction,
+        batch_action,
+    },
+    socket::{
+        SocketCommand,
+        SocketReply,
+        socket_event_message,
+        OP_SUBSCRIBE,
+    },
+    list::LIST_INBOX,
+    problem::{
+        CODE_ENTRY_NOT_FOUND,
+        CODE_INVALID_BODY,
+        CODE_LIST_NOT_FOUND,
+        CODE_PRECONDITION_FAILED,
+        CODE_UNAUTHENTICATED,
+        CODE_UPGRADE_REQUIRED,
+        CODE_USER_NOT_FOUND,
+    },
+};
+
+/* the socket pings the client when it has been quiet, and gives up on a client that has not answered */
+const SOCKET_HEARTBEAT_SECONDS: u64 = 15;
+const SOCKET_TIMEOUT_SECONDS: u64 = 45;
+
+/* entry commands in; their replies, and the changes to the caller's entries (every user's, for the admin) in the lists it subscribes to, out */
+pub async fn get_socket(request: HttpRequest, db: Data<Database>, body: web::Payload) -> HttpResponse {
+    let username = match get_username_from_base64(&db, &request) {
+        Some(name) => { name },
+        None => { return ApiError::unauthorized(CODE_UNAUTHENTICATED, "Invalid credentials").into(); },
+    };
+    
+    let user_id = match get_user_id_from_username(&db, &username) {
+        Ok(id) => { id },
+        Err(()) => { return ApiError::not_found(CODE_USER_NOT_FOUND, "Matching username not found").into(); },
+    };
+    
+    let (response, session, messages) = match actix_ws::handle(&request, body) {
+        Ok(parts) => { parts },
+        Err(_) => {
+            return ApiError::new(StatusCode::UPGRADE_REQUIRED, CODE_UPGRADE_REQUIRED, "WebSocket upgrade required")
+                .with_header("Upgrade", "websocket")
+                .into();
+        },
+    };
+    
+    actix_web::rt::spawn(socket_run(db.into_inner(), username, user_id, session, messages));
+    response
+}
+
+async fn socket_run(db: Arc<Database>, username: String, user_id: String, mut session: Session, mut messages: MessageStream) {
+    let events_user_id = if username == "admin" { None } else { Some(user_id.clone()) };
+    let mut subscription = EventSubscription::new(&db, events_user_id, db.get_entry_event_id_last());
+    let mut last_heard = Instant::now();
+    let mut last_ping = Instant::now();
+    
+    let reason = loop {
+        let wait = Duration::from_secs(SOCKET_HEARTBEAT_SECONDS);
+        
+        /* events are pushed as they are dispatched, so a command's own change follows its reply */
+        let next = match select(Box::pin(messages.recv()), Box::pin(subscription.next(&db, wait))).await {
+            Either::Left((message, _)) => Either::Left(message),
+            Either::Right((events, _)) => Either::Right(events),
+        };
+    
+        match next {
+            Either::Left(Some(Ok(message))) => {
+                last_heard = Instant::now();
+    
+                let sent = match message {
+                    Message::Text(text) => session.text(socket_command(&db, &username, &user_id, &mut subscription, &text)).await,
+                    Message::Binary(_) => session.text(socket_error(None, ApiError::bad_request(CODE_INVALID_BODY, "Commands must be text messages"))).await,
+                    Message::Ping(bytes) => session.pong(&bytes).await,
+                    Message::Close(reason) => { break reason; },
+                    _ => Ok(()),
+                };
+    
+                if sent.is_err() {
+                    return;
+                }
+            },
+            /* the connection was closed or broke */
+            Either::Left(_) => { break None; },
+            Either::Right(Some(events)) => {
+                for event in events {
+                    if session.text(socket_event_message(&event)).await.is_err() {
+                        return;
+                    }
+                }
+            },
+            Either::Right(None) => { break None; },
+        }
+    
+        if last_heard.elapsed() >= Duration::from_secs(SOCKET_TIMEOUT_SECONDS) {
+            break None;
+        }
+    
+        if last_ping.elapsed() >= Duration::from_secs(SOCKET_HEARTBEAT_SECONDS) {
+            last_ping = Instant::now();
+    
+            if session.ping(b"").await.is_err() {
+                return;
+            }
+        }
+    };
+    
+    let _ = session.close(reason).await;
+}
+
+/* runs one command with the same checks and validation as the REST API, and returns the reply */
+fn socket_command(db: &Database, username: &str, user_id: &str, subscription: &mut EventSubscription, text: &str) -> String {
+    let command = match serde_json::from_str::<SocketCommand>(text) {
+        Ok(command) => { command },
+        Err(err) => { return socket_error(None, ApiError::bad_request(CODE_INVALID_BODY, &err.to_string())); },
+    };
+    
+    let request_id = command.request_id;
+    
+    if command.operation.op == OP_SUBSCRIBE {
+        if let Err(error) = socket_lists_check(db, username, user_id, command.lists.as_deref().unwrap_or_default()) {
+            return socket_error(request_id, error);
+        }
+        
+        subscription.set_lists(command.lists);
+        return serde_json::to_string(&SocketReply::result(request_id, StatusCode::OK.as_u16(), None)).unwrap_or_default();
+    }
+    
+    let action = match batch_action(&command.operation) {
+        Ok(action) => { action },
+        Err(s) => { return socket_error(request_id, ApiError::bad_request(CODE_INVALID_BODY, &s)); },
+    };
+    
+    let reply = match action {
+        BatchAction::Create(write) => {
+            entry_create(db, username, user_id, &write).map(|read| SocketReply::result(request_id.clone(), StatusCode::CREATED.as_u16(), Some(read)))
+        },
+        BatchAction::Patch(entry_id, patch) => {
+            get_entry_for_user(db, username, &entry_id, command.operation.if_match.as_deref())
+                .and_then(|entry| entry_update(db, username, &entry, &patch))
+                .map(|read| SocketReply::result(request_id.clone(), StatusCode::OK.as_u16(), Some(read)))
+        },
+        BatchAction::Delete(entry_id) => {
+            get_entry_for_user(db, username, &entry_id, command.operation.if_match.as_deref())
+                .and_then(|entry| entry_delete(db, username, &entry))
+                .map(|()| SocketReply::result(request_id.clone(), StatusCode::NO_CONTENT.as_u16(), None))
+        },
+    };
+    
+    match reply {
+        Ok(reply) => serde_json::to_string(&reply).unwrap_or_default(),
+        Err(error) => socket_error(request_id, error),
+    }
+}
+
+fn socket_error(request_id: Option<String>, error: ApiError) -> String {
+    serde_json::to_string(&SocketReply::error(request_id, error.status.as_u16(), &error.body())).unwrap_or_default()
+}
+
+/* a socket can follow the inbox and the user's own lists (the admin: any list) */
+fn socket_lists_check(db: &Database, username: &str, user_id: &str, lists: &[String]) -> Result<(), ApiError> {
+    for list_id in lists.iter().filter(|l| l.as_str() != LIST_INBOX) {
+        match db.get_list_by_id(list_id) {
+            Some(list) if username == "admin" || list.user_id == user_id => {},
+            _ => { return Err(ApiError::not_found(CODE_LIST_NOT_FOUND, &format!["List {} not found", list_id])); },
+        }
+    }
+    
+    Ok(())
+}
+
+/* other users' entries are not found, as in the REST API; an "if_match" for another version fails as If-Match does */
+fn get_entry_for_user(db: &Database, username: &str, entry_id: &str, if_match: Option<&str>) -> Result<Entry, ApiError> {
+    match db.get_entry_by_id(entry_id) {
+        Some(entry) if username == "admin" || username == entry.username => {
+            if !entry_etag_matches(if_match, &entry) {
+                return Err(ApiError::new(StatusCode::PRECONDITION_FAILED, CODE_PRECONDITION_FAILED, "Entry has been changed"));
+            }
+            Ok(entry)
+        },
+        _ => Err(ApiError::not_found(CODE_ENTRY_NOT_FOUND, "Entry not found")),
+    }
+}
\ No newline at end of file
@@ -0,0 +1,277 @@
+/*************************************************************************
+    "task_server"
+    api/v2.rs
+    10/18/26
+    © Matthew Bentivegna
+*************************************************************************/
+use actix_web::{
+    HttpRequest,
+    HttpResponse,
+    http::StatusCode,
+    web::{
+        self,
+        Data,
+    },
+};
+use crate::repository::database::Database;
+use crate::file::options::Options;
+use crate::api::error::ApiError;
+use crate::api::api::{
+    entry_create,
+    entry_delete,
+    entry_replace,
+    entry_update,
+    entry_if_match,
+    entry_not_modified,
+    get_entry_params,
+    get_entry_read,
+    get_entries_read,
+    get_username_from_base64,
+    get_user_id_from_username,
+    idempotent,
+    json_config,
+    query_config,
+    patch_format,
+    patch_unsupported,
+};
+use crate::patch::patch::{
+    PatchFormat,
+    patch_document,
+};
+use crate::models::{
+    entry::{
+        Entry,
+        EntryRead,
+        entry_etag,
+    },
+    entry_v2::{
+        EntryV2Read,
+        EntryV2Write,
+        EntryV2Patch,
+        EntryV2Params,
+        entry_write_from_v2,
+        entry_patch_from_v2,
+        entry_v2_patch_from_document,
+        entry_params_from_v2,
+        field_errors_v2,
+    },
+    problem::{
+        CODE_ENTRY_NOT_FOUND,
+        CODE_INVALID_BODY,
+        CODE_INVALID_PARAMETER,
+        CODE_PRECONDITION_FAILED,
+        CODE_UNAUTHENTICATED,
+        CODE_USER_NOT_FOUND,
+    },
+};
+
+/* must be configured before api::api::config, whose "/api" scope would otherwise take these requests */
+pub fn config(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::scope("/api/v2")
+            .app_data(json_config())
+            .app_data(query_config())
+
+            .route("/entries", web::post().to(create_entry))
+            .route("/entries", web::get().to(get_entries))
+            .route("/entries/{id}", web::get().to(get_entry_by_id))
+            .route("/entries/{id}", web::put().to(update_entry_by_id))
+            .route("/entries/{id}", web::patch().to(update_entry_partial_by_id))
+            .route("/entries/{id}", web::delete().to(delete_entry_by_id))
+    );
+}
+
+/**************************************************************************/
+/* ENTRY actions */
+/**************************************************************************/
+pub async fn create_entry(request: HttpRequest, db: Data<Database>, options: Data<Options>, body: web::Bytes) -> HttpResponse {
+    let username = match get_username_from_base64(&db, &request) {
+        Some(name) => { name },
+        None => { return ApiError::unauthorized(CODE_UNAUTHENTICATED, "Invalid credentials").into(); },
+    };
+    
+    let id = match get_user_id_from_username(&db, &username) {
+        Ok(id) => { id },
+        Err(()) => { return ApiError::not_found(CODE_USER_NOT_FOUND, "Matching username not found").into(); },
+    };
+    
+    let scope = format!["{} {}", id, request.path()];
+    
+    idempotent(&db, &options, &request, &scope, &body, || {
+        match serde_json::from_slice::<EntryV2Write>(&body) {
+            Ok(new_entry) => create_entry_response(&db, &username, &id, &new_entry),
+            Err(err) => ApiError::bad_request(CODE_INVALID_BODY, &err.to_string()).into(),
+        }
+    }).await
+}
+
+#[allow(clippy::needless_return)]
+fn create_entry_response(db: &Database, username: &str, id: &str, new_entry: &EntryV2Write) -> HttpResponse {
+    let write = match entry_write_from_v2(new_entry) {
+        Ok(write) => { write },
+        Err(errors) => { return ApiError::validation(errors).into(); },
+    };
+    
+    match entry_create(db, username, id, &write) {
+        Ok(read) => {
+            return HttpResponse::Created()
+                .insert_header(("Location", format!["/api/v2/entries/{}", read.entry.id]))
+                .insert_header(("ETag", entry_etag(&read.entry)))
+                .json(EntryV2Read::new(&read));
+        },
+        Err(error) => {
+            return error_v2(error).into();
+        },
+    }
+}
+
+pub async fn get_entries(request: HttpRequest, db: web::Data<Database>, info: web::Query<EntryV2Params>) -> HttpResponse {
+    let username = match get_username_from_base64(&db, &request) {
+        Some(name) => { name },
+        None => { return ApiError::unauthorized(CODE_UNAUTHENTICATED, "Invalid credentials").into(); },
+    };
+    
+    let params = match entry_params_from_v2(&info) {
+        Ok(params) => { params },
+        Err(error) => { return ApiError::bad_request(CODE_INVALID_PARAMETER, &error.detail).into(); },
+    };
+    
+    let entries = db.get_entries_by_filter(&get_entry_params(&username, &params));
+    let entries: Vec<EntryV2Read> = get_entries_read(&db, entries).iter().map(EntryV2Read::new).collect();
+    
+    HttpResponse::Ok().json(entries)
+}
+
+pub async fn get_entry_by_id(request: HttpRequest, db: web::Data<Database>, id: web::Path<String>) -> HttpResponse {
+    let username = match get_username_from_base64(&db, &request) {
+        Some(name) => { name },
+        None => { return ApiError::unauthorized(CODE_UNAUTHENTICATED, "Invalid credentials").into(); },
+    };
+    
+    match get_entry_for_user(&db, &username, &id) {
+        Some(entry) => {
+            let etag = entry_etag(&entry);
+    
+            if entry_not_modified(&request, &entry) {
+                return HttpResponse::NotModified().insert_header(("ETag", etag)).finish();
+            }
+    
+            HttpResponse::Ok().insert_header(("ETag", etag)).json(EntryV2Read::new(&get_entry_read(&db, entry)))
+        },
+        None => ApiError::not_found(CODE_ENTRY_NOT_FOUND, "Entry not found").into(),
+    }
+}
+
+pub async fn update_entry_by_id(request: HttpRequest, db: web::Data<Database>, id: web::Path<String>, updated_entry: web::Json<EntryV2Write>) -> HttpResponse {
+    let username = match get_username_from_base64(&db, &request) {
+        Some(name) => { name },
+        None => { return ApiError::unauthorized(CODE_UNAUTHENTICATED, "Invalid credentials").into(); },
+    };
+    
+    let entry = match get_entry_for_user(&db, &username, &id) {
+        Some(entry) => { entry },
+        None => { return ApiError::not_found(CODE_ENTRY_NOT_FOUND, "Entry not found").into(); },
+    };
+    
+    if !entry_if_match(&request, &entry) {
+        return ApiError::new(StatusCode::PRECONDITION_FAILED, CODE_PRECONDITION_FAILED, "Entry has been changed").into();
+    }
+    
+    let write = match entry_write_from_v2(&updated_entry) {
+        Ok(write) => { write },
+        Err(errors) => { return ApiError::validation(errors).into(); },
+    };
+    
+    match entry_replace(&db, &username, &entry, &write) {
+        Ok(read) => entry_response(read),
+        Err(error) => error_v2(error).into(),
+    }
+}
+
+/* plain JSON (null is ignored), a merge-patch or a json-patch of the v2 document, by Content-Type */
+pub async fn update_entry_partial_by_id(request: HttpRequest, db: web::Data<Database>, id: web::Path<String>, body: String) -> HttpResponse {
+    let username = match get_username_from_base64(&db, &request) {
+        Some(name) => { name },
+        None => { return ApiError::unauthorized(CODE_UNAUTHENTICATED, "Invalid credentials").into(); },
+    };
+    
+    let format = match patch_format(&request) {
+        Some(format) => { format },
+        None => { return patch_unsupported(); },
+    };
+    
+    let entry = match get_entry_for_user(&db, &username, &id) {
+        Some(entry) => { entry },
+        None => { return ApiError::not_found(CODE_ENTRY_NOT_FOUND, "Entry not found").into(); },
+    };
+    
+    if !entry_if_match(&request, &entry) {
+        return ApiError::new(StatusCode::PRECONDITION_FAILED, CODE_PRECONDITION_FAILED, "Entry has been changed").into();
+    }
+    
+    let patch = match entry_v2_patch_from_body(&db, format, &entry, &body) {
+        Ok(patch) => { patch },
+        Err(s) => { return ApiError::bad_request(CODE_INVALID_BODY, &s).into(); },
+    };
+    
+    let patch = match entry_patch_from_v2(&patch) {
+        Ok(patch) => { patch },
+        Err(errors) => { return ApiError::validation(errors).into(); },
+    };
+    
+    match entry_update(&db, &username, &entry, &patch) {
+        Ok(read) => entry_response(read),
+        Err(error) => error_v2(error).into(),
+    }
+}
+
+pub async fn delete_entry_by_id(request: HttpRequest, db: web::Data<Database>, id: web::Path<String>) -> HttpResponse {
+    let username = match get_username_from_base64(&db, &request) {
+        Some(name) => { name },
+        None => { return ApiError::unauthorized(CODE_UNAUTHENTICATED, "Invalid credentials").into(); },
+    };
+    
+    let entry = match get_entry_for_user(&db, &username, &id) {
+        Some(entry) => { entry },
+        None => { return ApiError::not_found(CODE_ENTRY_NOT_FOUND, "Entry not found").into(); },
+    };
+    
+    if !entry_if_match(&request, &entry) {
+        return ApiError::new(StatusCode::PRECONDITION_FAILED, CODE_PRECONDITION_FAILED, "Entry has been changed").into();
+    }
+    
+    match entry_delete(&db, &username, &entry) {
+        Ok(()) => HttpResponse::NoContent().finish(),
+        Err(e) => e.into(),
+    }
+}
+
+/**************************************************************************/
+/* supporting functions */
+/**************************************************************************/
+/* other users' entries are not found, as in v1 */
+fn get_entry_for_user(db: &Database, username: &str, id: &str) -> Option<Entry> {
+    db.get_entry_by_id(id).filter(|entry| username == "admin" || username == entry.username)
+}
+
+fn entry_v2_patch_from_body(db: &Database, format: PatchFormat, entry: &Entry, body: &str) -> Result<EntryV2Patch, String> {
+    if format == PatchFormat::Json {
+        return serde_json::from_str(body).map_err(|e| e.to_string());
+    }
+    
+    let before = EntryV2Write::new(&get_entry_read(db, entry.clone()));
+    let mut document = serde_json::to_value(&before).map_err(|e| e.to_string())?;
+    
+    patch_document(format, &mut document, body)?;
+    entry_v2_patch_from_document(document)
+}
+
+fn entry_response(read: EntryRead) -> HttpResponse {
+    HttpResponse::Ok().insert_header(("ETag", entry_etag(&read.entry))).json(EntryV2Read::new(&read))
+}
+
+/* validation is shared with v1, so its field pointers are translated to the v2 document */
+fn error_v2(mut error: ApiError) -> ApiError {
+    error.errors = field_errors_v2(error.errors);
+    error
+}
\ No newline at end of file
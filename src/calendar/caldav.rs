@@ -0,0 +1,741 @@
+/*************************************************************************
+    "task_server"
+    calendar/caldav.rs
+    10/18/26
+    © Matthew Bentivegna
+*************************************************************************/
+use quick_xml::{
+    events::Event,
+    name::ResolveResult,
+    reader::NsReader,
+};
+use crate::calendar::ical::{
+    self,
+    Component,
+    Todo,
+};
+use crate::models::{
+    entry::{
+        Entry,
+        EntryRead,
+        EntryWrite,
+        entry_etag,
+        entry_validate_edit,
+    },
+    reminder::ReminderWrite,
+    problem::FieldError,
+};
+
+pub const NS_DAV: &str = "DAV:";
+pub const NS_CALDAV: &str = "urn:ietf:params:xml:ns:caldav";
+pub const NS_CALENDARSERVER: &str = "http://calendarserver.org/ns/";
+
+pub const DAV_ROOT: &str = "/dav/";
+pub const CALENDAR_NAME: &str = "tasks";
+pub const CONTENT_TYPE_TODO: &str = "text/calendar; charset=utf-8; component=vtodo";
+
+/* (namespace, local name) */
+pub type PropName = (String, String);
+
+/* a parsed XML element, namespaces resolved */
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Element {
+    pub ns: String,
+    pub name: String,
+    pub children: Vec<Element>,
+    pub text: String,
+}
+
+impl Element {
+    pub fn is(&self, ns: &str, name: &str) -> bool {
+        self.ns == ns && self.name == name
+    }
+
+    pub fn child(&self, ns: &str, name: &str) -> Option<&Element> {
+        self.children.iter().find(|c| c.is(ns, name))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Depth {
+    Zero,
+    One,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Report {
+    Query { props: Vec<PropName> },
+    Multiget { props: Vec<PropName>, hrefs: Vec<String> },
+    Unsupported(String),
+}
+
+/* the things a client can PROPFIND */
+#[derive(Debug, Clone)]
+pub enum Resource<'a> {
+    Root { username: &'a str },
+    Principal { username: &'a str },
+    Calendar { username: &'a str, ctag: String },
+    Todo { username: &'a str, entry: &'a EntryRead },
+}
+
+/* one <response> of a multistatus; "not_found" marks an href that does not exist at all */
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PropResponse {
+    pub href: String,
+    pub found: Vec<(PropName, String)>,
+    pub missing: Vec<PropName>,
+    pub not_found: bool,
+}
+
+/*----------------------------------------------------------------------*/
+/* requests */
+/*----------------------------------------------------------------------*/
+pub fn parse_xml(body: &str) -> Result<Element, String> {
+    let mut reader = NsReader::from_str(body);
+    reader.trim_text(true);
+    let mut stack: Vec<Element> = vec![];
+
+    loop {
+        match reader.read_resolved_event() {
+            Ok((ns, Event::Start(e))) => {
+                stack.push(element(ns, e.local_name().as_ref()));
+            },
+            Ok((ns, Event::Empty(e))) => {
+                let el = element(ns, e.local_name().as_ref());
+
+                match stack.last_mut() {
+                    Some(parent) => parent.children.push(el),
+                    None => { return Ok(el); },
+                }
+            },
+            Ok((_, Event::End(_))) => {
+                let el = stack.pop().ok_or("Unbalanced XML")?;
+
+                match stack.last_mut() {
+                    Some(parent) => parent.children.push(el),
+                    None => { return Ok(el); },
+                }
+            },
+            Ok((_, Event::Text(t))) => {
+                if let Some(el) = stack.last_mut() {
+                    el.text.push_str(&t.unescape().map_err(|e| e.to_string())?);
+                }
+            },
+            Ok((_, Event::CData(t))) => {
+                if let Some(el) = stack.last_mut() {
+                    el.text.push_str(&String::from_utf8_lossy(&t));
+                }
+            },
+            Ok((_, Event::Eof)) => { return Err(String::from("Incomplete XML")); },
+            Err(e) => { return Err(e.to_string()); },
+            _ => {},
+        }
+    }
+}
+
+fn element(ns: ResolveResult, name: &[u8]) -> Element {
+    let ns = match ns {
+        ResolveResult::Bound(ns) => String::from_utf8_lossy(ns.as_ref()).to_string(),
+        _ => String::new(),
+    };
+
+    Element {
+        ns,
+        name: String::from_utf8_lossy(name).to_string(),
+        ..Default::default()
+    }
+}
+
+/* "Depth: infinity" is answered as "1"; nothing here nests deeper */
+pub fn parse_depth(header: Option<&str>) -> Depth {
+    match header.map(|h| h.trim()) {
+        Some("0") => Depth::Zero,
+        _ => Depth::One,
+    }
+}
+
+/* the requested properties, or None for allprop (also used for an empty body, RFC 4918 9.1) */
+pub fn parse_propfind(body: &str) -> Result<Option<Vec<PropName>>, String> {
+    if body.trim().is_empty() {
+        return Ok(None);
+    }
+
+    let root = parse_xml(body)?;
+
+    if !root.is(NS_DAV, "propfind") {
+        return Err(String::from("Expected propfind"));
+    }
+
+    match root.child(NS_DAV, "prop") {
+        Some(prop) => Ok(Some(prop_names(prop))),
+        None => Ok(None),
+    }
+}
+
+pub fn parse_report(body: &str) -> Result<Report, String> {
+    let root = parse_xml(body)?;
+    let props = root.child(NS_DAV, "prop").map(prop_names).unwrap_or_default();
+
+    if root.is(NS_CALDAV, "calendar-query") {
+        return Ok(Report::Query { props });
+    }
+
+    if root.is(NS_CALDAV, "calendar-multiget") {
+        let hrefs = root.children
+            .iter()
+            .filter(|c| c.is(NS_DAV, "href"))
+            .map(|c| String::from(c.text.trim()))
+            .collect();
+
+        return Ok(Report::Multiget { props, hrefs });
+    }
+
+    Ok(Report::Unsupported(root.name))
+}
+
+fn prop_names(prop: &Element) -> Vec<PropName> {
+    prop.children
+        .iter()
+        .map(|c| (c.ns.clone(), c.name.clone()))
+        .collect()
+}
+
+/*----------------------------------------------------------------------*/
+/* resources */
+/*----------------------------------------------------------------------*/
+pub fn principal_href(username: &str) -> String {
+    format!["{}{}/", DAV_ROOT, href_encode(username)]
+}
+
+pub fn calendar_href(username: &str) -> String {
+    format!["{}{}/", principal_href(username), CALENDAR_NAME]
+}
+
+pub fn todo_href(username: &str, entry_id: &str) -> String {
+    format!["{}{}.ics", calendar_href(username), href_encode(entry_id)]
+}
+
+/* the entry id in a multiget href, if it points into the calendar */
+pub fn todo_id_from_href(username: &str, href: &str) -> Option<String> {
+    /* clients may send absolute URLs */
+    let path = match href.find("://") {
+        Some(i) => &href[href[i + 3..].find('/').map(|j| i + 3 + j)?..],
+        None => href,
+    };
+    let name = href_decode(path).strip_prefix(&href_decode(&calendar_href(username)))?.to_string();
+
+    name.strip_suffix(".ics").filter(|n| !n.is_empty() && !n.contains('/')).map(String::from)
+}
+
+/* resource names become entry ids, so they are kept to a safe character set */
+pub fn resource_name_valid(name: &str) -> bool {
+    !name.is_empty() &&
+    name.len() <= 200 &&
+    name.chars().all(|c| c.is_ascii_alphanumeric() || "-_.@".contains(c))
+}
+
+/* changes whenever an entry in the calendar is created, changed or deleted */
+pub fn calendar_ctag(entries: &[Entry]) -> String {
+    let latest = entries
+        .iter()
+        .map(|e| e.updated.format("%Y%m%d%H%M%S%6f").to_string())
+        .max()
+        .unwrap_or_default();
+
+    format!["{}-{}", entries.len(), latest]
+}
+
+impl<'a> Resource<'a> {
+    pub fn href(&self) -> String {
+        match self {
+            Resource::Root { .. } => String::from(DAV_ROOT),
+            Resource::Principal { username } => principal_href(username),
+            Resource::Calendar { username, .. } => calendar_href(username),
+            Resource::Todo { username, entry } => todo_href(username, &entry.entry.id),
+        }
+    }
+
+    /* the properties returned for allprop */
+    pub fn allprops(&self) -> Vec<PropName> {
+        let mut names = vec![
+            (NS_DAV, "resourcetype"),
+            (NS_DAV, "displayname"),
+            (NS_DAV, "current-user-principal"),
+        ];
+
+        match self {
+            Resource::Calendar { .. } => {
+                names.push((NS_CALDAV, "supported-calendar-component-set"));
+                names.push((NS_CALENDARSERVER, "getctag"));
+            },
+            Resource::Todo { .. } => {
+                names.push((NS_DAV, "getetag"));
+                names.push((NS_DAV, "getcontenttype"));
+                names.push((NS_DAV, "getlastmodified"));
+            },
+            _ => {},
+        }
+
+        names
+            .into_iter()
+            .map(|(ns, name)| (String::from(ns), String::from(name)))
+            .collect()
+    }
+
+    /* the inner XML of a property, None if this resource does not have it */
+    pub fn prop(&self, prop: &PropName) -> Option<String> {
+        let username = match self {
+            Resource::Root { username } | Resource::Principal { username } | Resource::Calendar { username, .. } | Resource::Todo { username, .. } => *username,
+        };
+        let href = |h: String| format!["<d:href>{}</d:href>", escape_xml(&h)];
+
+        match (prop.0.as_str(), prop.1.as_str(), self) {
+            (NS_DAV, "resourcetype", Resource::Root { .. }) => Some(String::from("<d:collection/>")),
+            (NS_DAV, "resourcetype", Resource::Principal { .. }) => Some(String::from("<d:collection/><d:principal/>")),
+            (NS_DAV, "resourcetype", Resource::Calendar { .. }) => Some(String::from("<d:collection/><c:calendar/>")),
+            (NS_DAV, "resourcetype", Resource::Todo { .. }) => Some(String::new()),
+
+            (NS_DAV, "displayname", Resource::Principal { .. }) => Some(escape_xml(username)),
+            (NS_DAV, "displayname", Resource::Calendar { .. }) => Some(String::from("Tasks")),
+            (NS_DAV, "displayname", Resource::Todo { entry, .. }) => Some(escape_xml(&entry.entry.task)),
+
+            (NS_DAV, "current-user-principal", _) => Some(href(principal_href(username))),
+            (NS_DAV, "principal-URL", Resource::Principal { .. }) => Some(href(principal_href(username))),
+            (NS_DAV, "owner", Resource::Calendar { .. } | Resource::Todo { .. }) => Some(href(principal_href(username))),
+            (NS_CALDAV, "calendar-home-set", Resource::Root { .. } | Resource::Principal { .. }) => Some(href(principal_href(username))),
+            (NS_CALDAV, "calendar-user-address-set", Resource::Principal { .. }) => Some(href(principal_href(username))),
+
+            (NS_DAV, "current-user-privilege-set", _) => Some(String::from(
+                "<d:privilege><d:read/></d:privilege><d:privilege><d:write/></d:privilege><d:privilege><d:write-content/></d:privilege><d:privilege><d:bind/></d:privilege><d:privilege><d:unbind/></d:privilege>"
+            )),
+            (NS_DAV, "supported-report-set", Resource::Calendar { .. }) => Some(String::from(
+                "<d:supported-report><d:report><c:calendar-query/></d:report></d:supported-report><d:supported-report><d:report><c:calendar-multiget/></d:report></d:supported-report>"
+            )),
+            (NS_CALDAV, "supported-calendar-component-set", Resource::Calendar { .. }) => Some(String::from("<c:comp name=\"VTODO\"/>")),
+            (NS_CALENDARSERVER, "getctag", Resource::Calendar { ctag, .. }) => Some(escape_xml(ctag)),
+
+            (NS_DAV, "getetag", Resource::Todo { entry, .. }) => Some(escape_xml(&entry_etag(&entry.entry))),
+            (NS_DAV, "getcontenttype", Resource::Todo { .. }) => Some(String::from(CONTENT_TYPE_TODO)),
+            (NS_DAV, "getlastmodified", Resource::Todo { entry, .. }) => Some(entry.entry.updated.format("%a, %d %b %Y %H:%M:%S GMT").to_string()),
+            (NS_CALDAV, "calendar-data", Resource::Todo { entry, .. }) => Some(escape_xml(&todo_calendar(entry))),
+
+            _ => None,
+        }
+    }
+
+    /* "props" None means allprop */
+    pub fn response(&self, props: Option<&[PropName]>) -> PropResponse {
+        let props = match props {
+            Some(props) => props.to_vec(),
+            None => self.allprops(),
+        };
+        let mut response = PropResponse {
+            href: self.href(),
+            ..Default::default()
+        };
+
+        for prop in props {
+            match self.prop(&prop) {
+                Some(value) => response.found.push((prop, value)),
+                None => response.missing.push(prop),
+            }
+        }
+
+        response
+    }
+}
+
+/* a single entry as a calendar object resource */
+pub fn todo_calendar(entry: &EntryRead) -> String {
+    ical::calendar(CALENDAR_NAME, std::slice::from_ref(entry), Component::Todo)
+}
+
+/*----------------------------------------------------------------------*/
+/* responses */
+/*----------------------------------------------------------------------*/
+pub fn multistatus(responses: &[PropResponse]) -> String {
+    let mut xml = format![
+        "<?xml version=\"1.0\" encoding=\"utf-8\"?>\n<d:multistatus xmlns:d=\"{}\" xmlns:c=\"{}\" xmlns:cs=\"{}\">",
+        NS_DAV, NS_CALDAV, NS_CALENDARSERVER
+    ];
+
+    for response in responses {
+        xml.push_str(&format!["<d:response><d:href>{}</d:href>", escape_xml(&response.href)]);
+
+        if response.not_found {
+            xml.push_str("<d:status>HTTP/1.1 404 Not Found</d:status></d:response>");
+            continue;
+        }
+
+        if !response.found.is_empty() {
+            xml.push_str("<d:propstat><d:prop>");
+            for (name, value) in &response.found {
+                xml.push_str(&prop_element(name, value));
+            }
+            xml.push_str("</d:prop><d:status>HTTP/1.1 200 OK</d:status></d:propstat>");
+        }
+
+        if !response.missing.is_empty() {
+            xml.push_str("<d:propstat><d:prop>");
+            for name in &response.missing {
+                xml.push_str(&prop_element(name, ""));
+            }
+            xml.push_str("</d:prop><d:status>HTTP/1.1 404 Not Found</d:status></d:propstat>");
+        }
+
+        xml.push_str("</d:response>");
+    }
+
+    xml.push_str("</d:multistatus>\n");
+    xml
+}
+
+/* a multiget href that is not in the calendar */
+pub fn not_found_response(href: &str) -> PropResponse {
+    PropResponse {
+        href: String::from(href),
+        not_found: true,
+        ..Default::default()
+    }
+}
+
+/* the body of a failed precondition, RFC 4918 16 */
+pub fn error_body(ns: &str, condition: &str) -> String {
+    let (prefix, ns_decl) = match ns {
+        NS_CALDAV => ("c", format![" xmlns:c=\"{}\"", NS_CALDAV]),
+        _ => ("d", String::new()),
+    };
+
+    format![
+        "<?xml version=\"1.0\" encoding=\"utf-8\"?>\n<d:error xmlns:d=\"{}\"{}><{}:{}/></d:error>\n",
+        NS_DAV, ns_decl, prefix, condition
+    ]
+}
+
+fn prop_element(name: &PropName, value: &str) -> String {
+    let (prefix, ns_decl) = match name.0.as_str() {
+        NS_DAV => ("d", String::new()),
+        NS_CALDAV => ("c", String::new()),
+        NS_CALENDARSERVER => ("cs", String::new()),
+        ns => ("x", format![" xmlns:x=\"{}\"", escape_xml(ns)]),
+    };
+
+    if value.is_empty() {
+        format!["<{}:{}{}/>", prefix, name.1, ns_decl]
+    } else {
+        format!["<{0}:{1}{2}>{3}</{0}:{1}>", prefix, name.1, ns_decl, value]
+    }
+}
+
+pub fn escape_xml(text: &str) -> String {
+    text
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+pub fn href_encode(text: &str) -> String {
+    text
+        .bytes()
+        .map(|b| {
+            if b.is_ascii_alphanumeric() || b"-_.~@".contains(&b) {
+                (b as char).to_string()
+            } else {
+                format!["%{:02X}", b]
+            }
+        })
+        .collect()
+}
+
+pub fn href_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut decoded = vec![];
+    let mut i = 0;
+
+    while i < bytes.len() {
+        if bytes[i] == b'%' && i + 2 < bytes.len() {
+            if let Ok(b) = u8::from_str_radix(&text[i + 1..i + 3], 16) {
+                decoded.push(b);
+                i += 3;
+                continue;
+            }
+        }
+
+        decoded.push(bytes[i]);
+        i += 1;
+    }
+
+    String::from_utf8_lossy(&decoded).to_string()
+}
+
+/*----------------------------------------------------------------------*/
+/* VTODO <-> entry */
+/*----------------------------------------------------------------------*/
+/* the entry fields a VTODO sets; everything else is kept (or defaulted) by entry_from_entry_write(_edit) */
+pub fn todo_entry_write(todo: &Todo) -> Result<EntryWrite, String> {
+    let due = todo.due.ok_or("No due date present")?;
+    let mut alarms = todo.alarms.clone();
+    alarms.sort_unstable_by(|a, b| b.cmp(a));
+    alarms.dedup();
+
+    Ok(EntryWrite {
+        year: due.year().to_string(),
+        month: due.month().to_string(),
+        day: due.day().to_string(),
+        task: todo.summary.clone().unwrap_or_default(),
+        reminder: None,
+        reminders: Some(
+            alarms
+                .iter()
+                .map(|a| ReminderWrite {
+                    offset: a.to_string(),
+                    channel: None,
+                })
+                .collect()
+        ),
+        completed: Some(todo.completed.to_string()),
+        tags: None,
+        list_id: None,
+    })
+}
+
+/* an unchanged date and reminder list is not re-checked against today, so overdue tasks can still be ticked off */
+pub fn todo_validate(entry: &Entry, reminders: &[ReminderWrite], orig: Option<(&Entry, &[ReminderWrite])>) -> Result<(), Vec<FieldError>> {
+    entry_validate_edit(entry, reminders, orig)
+}
+
+/* reminders the client kept (same offset) keep the channel they had, which iCalendar cannot carry */
+pub fn reminders_keep_channels(orig: &[ReminderWrite], reminders: &[ReminderWrite]) -> Vec<ReminderWrite> {
+    reminders
+        .iter()
+        .map(|r| match orig.iter().find(|o| o.offset == r.offset) {
+            Some(o) => o.clone(),
+            None => r.clone(),
+        })
+        .collect()
+}
+
+/* If-Match / If-None-Match against the current ETag (None when the resource does not exist) */
+pub fn preconditions_met(if_match: Option<&str>, if_none_match: Option<&str>, etag: Option<&str>) -> bool {
+    let matches = |header: &str| {
+        header.split(',').any(|t| {
+            let t = t.trim();
+            t == "*" || Some(t) == etag
+        })
+    };
+
+    if let Some(h) = if_match {
+        if etag.is_none() || !matches(h) {
+            return false;
+        }
+    }
+
+    if let Some(h) = if_none_match {
+        if etag.is_some() && matches(h) {
+            return false;
+        }
+    }
+
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::date::date::Date;
+    use crate::models::entry::{
+        entry_fixture,
+        entry_from_entry_write,
+    };
+
+    fn entry_read() -> EntryRead {
+        let entry = Entry {
+            id: String::from("2f4c1c1e-6b0e-4b1e-9d1a-3c9f8a7b6d5e"),
+            task: String::from("Pay rent & bills"),
+            ..entry_fixture()
+        };
+
+        EntryRead::new(entry, vec![], vec![])
+    }
+
+    #[test]
+    fn propfind_principal_ios() {
+        let props = parse_propfind(include_str!("fixtures/propfind_principal_ios.xml")).unwrap().unwrap();
+        let response = Resource::Root { username: "user" }.response(Some(&props));
+
+        assert!(response.found.contains(&((String::from(NS_DAV), String::from("current-user-principal")), String::from("<d:href>/dav/user/</d:href>"))));
+        assert!(response.missing.contains(&(String::from(NS_DAV), String::from("principal-collection-set"))));
+    }
+
+    #[test]
+    fn propfind_home_davx5() {
+        let props = parse_propfind(include_str!("fixtures/propfind_home_davx5.xml")).unwrap().unwrap();
+        let response = Resource::Calendar { username: "user", ctag: String::from("1-x") }.response(Some(&props));
+        let xml = multistatus(std::slice::from_ref(&response));
+
+        assert!(response.missing.contains(&(String::from("http://apple.com/ns/ical/"), String::from("calendar-color"))));
+        assert!(xml.contains("<d:resourcetype><d:collection/><c:calendar/></d:resourcetype>"));
+        assert!(xml.contains("<c:supported-calendar-component-set><c:comp name=\"VTODO\"/></c:supported-calendar-component-set>"));
+        assert!(xml.contains("<cs:getctag>1-x</cs:getctag>"));
+        assert!(xml.contains("<x:calendar-color xmlns:x=\"http://apple.com/ns/ical/\"/>"));
+        assert!(parse_xml(&xml).is_ok());
+    }
+
+    #[test]
+    fn propfind_collection_thunderbird() {
+        let props = parse_propfind(include_str!("fixtures/propfind_collection_thunderbird.xml")).unwrap().unwrap();
+        let entry = entry_read();
+        let response = Resource::Todo { username: "user", entry: &entry }.response(Some(&props));
+        let xml = multistatus(&[response]);
+
+        assert!(xml.contains("<d:href>/dav/user/tasks/2f4c1c1e-6b0e-4b1e-9d1a-3c9f8a7b6d5e.ics</d:href>"));
+        assert!(xml.contains("<d:getetag>&quot;20300601123000000000&quot;</d:getetag>"));
+        assert!(xml.contains("<d:getcontenttype>text/calendar; charset=utf-8; component=vtodo</d:getcontenttype>"));
+        assert!(xml.contains("<d:resourcetype/>"));
+    }
+
+    #[test]
+    fn propfind_allprop() {
+        assert!(
+            parse_propfind("").unwrap().is_none() &&
+            parse_propfind("<?xml version=\"1.0\"?><propfind xmlns=\"DAV:\"><allprop/></propfind>").unwrap().is_none() &&
+            parse_propfind("<calendar-query xmlns=\"urn:ietf:params:xml:ns:caldav\"/>").is_err()
+        );
+    }
+
+    #[test]
+    fn report_query_davx5() {
+        let report = parse_report(include_str!("fixtures/report_query_davx5.xml")).unwrap();
+
+        assert_eq!(report, Report::Query {
+            props: vec![
+                (String::from(NS_DAV), String::from("getcontenttype")),
+                (String::from(NS_DAV), String::from("getetag")),
+            ],
+        });
+    }
+
+    #[test]
+    fn report_multiget_thunderbird() {
+        let report = parse_report(include_str!("fixtures/report_multiget_thunderbird.xml")).unwrap();
+        let Report::Multiget { props, hrefs } = report else { panic!("expected multiget") };
+        let entry = entry_read();
+        let xml = multistatus(&[Resource::Todo { username: "user", entry: &entry }.response(Some(&props))]);
+        let data = parse_xml(&xml).unwrap();
+        let calendar_data = &data.children[0]
+            .child(NS_DAV, "propstat").unwrap()
+            .child(NS_DAV, "prop").unwrap()
+            .child(NS_CALDAV, "calendar-data").unwrap()
+            .text;
+
+        assert_eq!(hrefs.len(), 2);
+        assert_eq!(todo_id_from_href("user", &hrefs[0]), Some(String::from("2f4c1c1e-6b0e-4b1e-9d1a-3c9f8a7b6d5e")));
+        assert_eq!(todo_id_from_href("user", &hrefs[1]), Some(String::from("missing item")));
+        assert_eq!(ical::parse_todo(calendar_data).unwrap().summary, Some(String::from("Pay rent & bills")));
+    }
+
+    #[test]
+    fn multiget_missing_href() {
+        let xml = multistatus(&[not_found_response("/dav/user/tasks/missing.ics")]);
+
+        assert!(xml.contains("<d:href>/dav/user/tasks/missing.ics</d:href><d:status>HTTP/1.1 404 Not Found</d:status></d:response>"));
+    }
+
+    #[test]
+    fn report_unsupported() {
+        assert_eq!(
+            parse_report(include_str!("fixtures/report_sync_collection.xml")).unwrap(),
+            Report::Unsupported(String::from("sync-collection"))
+        );
+    }
+
+    #[test]
+    fn put_todo_ios() {
+        let todo = ical::parse_todo(include_str!("fixtures/put_todo_ios.ics")).unwrap();
+        let write = todo_entry_write(&todo).unwrap();
+        let entry = entry_from_entry_write(&write, "user");
+
+        assert!(
+            entry.year == "2030" && entry.month == "7" && entry.day == "4" &&
+            entry.task == "Buy fireworks, sparklers" &&
+            entry.completed == "false" &&
+            entry.reminder == Some(String::from("2"))
+        );
+        assert_eq!(write.reminders.unwrap().iter().map(|r| r.offset.clone()).collect::<Vec<_>>(), vec!["2", "0"]);
+    }
+
+    #[test]
+    fn put_todo_completed_tasks() {
+        let todo = ical::parse_todo(include_str!("fixtures/put_todo_completed_tasks.ics")).unwrap();
+        let write = todo_entry_write(&todo).unwrap();
+
+        assert!(
+            todo.due == Some(Date::new(2030, 6, 15).unwrap()) &&
+            write.completed == Some(String::from("true")) &&
+            write.reminders.unwrap().len() == 1
+        );
+    }
+
+    #[test]
+    fn put_todo_without_due() {
+        let todo = ical::parse_todo("BEGIN:VCALENDAR\r\nBEGIN:VTODO\r\nSUMMARY:Someday\r\nEND:VTODO\r\nEND:VCALENDAR\r\n").unwrap();
+
+        assert!(todo_entry_write(&todo).is_err());
+    }
+
+    #[test]
+    fn validate_unchanged_overdue() {
+        let mut orig = entry_read().entry;
+        orig.year = String::from("2001");
+        let mut ticked = orig.clone();
+        ticked.completed = String::from("true");
+        let mut moved = ticked.clone();
+        moved.day = String::from("16");
+
+        assert!(
+            todo_validate(&ticked, &[], Some((&orig, &[]))).is_ok() &&
+            todo_validate(&moved, &[], Some((&orig, &[]))).is_err() &&
+            todo_validate(&ticked, &[], None).is_err()
+        );
+    }
+
+    #[test]
+    fn keep_channels() {
+        let reminder = |offset: &str, channel: Option<&str>| ReminderWrite {
+            offset: String::from(offset),
+            channel: channel.map(String::from),
+        };
+        let kept = reminders_keep_channels(
+            &[reminder("7", Some("webhook")), reminder("1", None)],
+            &[reminder("7", None), reminder("0", None)]
+        );
+
+        assert!(kept[0].channel == Some(String::from("webhook")) && kept[1].offset == "0");
+    }
+
+    #[test]
+    fn preconditions() {
+        let etag = Some("\"1\"");
+
+        assert!(
+            preconditions_met(None, None, etag) &&
+            preconditions_met(Some("\"1\""), None, etag) &&
+            !preconditions_met(Some("\"2\""), None, etag) &&
+            !preconditions_met(Some("*"), None, None) &&
+            preconditions_met(None, Some("*"), None) &&
+            !preconditions_met(None, Some("*"), etag)
+        );
+    }
+
+    #[test]
+    fn hrefs() {
+        assert!(
+            todo_href("first last", "abc") == "/dav/first%20last/tasks/abc.ics" &&
+            todo_id_from_href("first last", "https://tasks.example.com/dav/first%20last/tasks/abc.ics") == Some(String::from("abc")) &&
+            todo_id_from_href("user", "/dav/other/tasks/abc.ics").is_none() &&
+            resource_name_valid("2F4C1C1E-6B0E-4B1E@example.com") &&
+            !resource_name_valid("../x")
+        );
+    }
+}
\ No newline at end of file
@@ -0,0 +1,554 @@
+/*************************************************************************
+    "task_server"
+    calendar/ical.rs
+    10/18/26
+    © Matthew Bentivegna
+*************************************************************************/
+use chrono::NaiveDateTime;
+use crate::date::date::Date;
+use crate::models::entry::{
+    EntryRead,
+    entry_due_date,
+};
+
+pub const PRODID: &str = "-//task_server//Task Server//EN";
+
+/* RFC 5545 3.1: content lines are folded at 75 octets */
+const LINE_OCTETS: usize = 75;
+
+/* how entries are shown in a calendar: as to-dos, or as all-day events */
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Component {
+    Todo,
+    Event,
+}
+
+impl Component {
+    pub fn from_param(param: Option<&str>) -> Option<Self> {
+        match param {
+            None | Some("vtodo") => Some(Component::Todo),
+            Some("vevent") => Some(Component::Event),
+            _ => None,
+        }
+    }
+}
+
+/* a complete VCALENDAR object holding one component per entry */
+pub fn calendar(name: &str, entries: &[EntryRead], component: Component) -> String {
+    let mut lines = vec![
+        String::from("BEGIN:VCALENDAR"),
+        String::from("VERSION:2.0"),
+        format!["PRODID:{}", PRODID],
+        String::from("CALSCALE:GREGORIAN"),
+        format!["X-WR-CALNAME:{}", escape_text(name)],
+    ];
+
+    for entry in entries {
+        lines.extend(entry_lines(entry, component));
+    }
+
+    lines.push(String::from("END:VCALENDAR"));
+
+    lines
+        .iter()
+        .map(|l| fold_line(l))
+        .collect::<Vec<String>>()
+        .join("\r\n") + "\r\n"
+}
+
+/* the content lines of a single entry (unfolded) */
+pub fn entry_lines(entry: &EntryRead, component: Component) -> Vec<String> {
+    let due_date = match entry_due_date(&entry.entry) {
+        Ok(due_date) => due_date,
+        Err(_) => { return vec![]; },
+    };
+    let completed = entry.entry.completed == "true";
+    let expired = entry.entry.expired == "true";
+
+    let (name, status) = match component {
+        Component::Todo => ("VTODO", if completed { "COMPLETED" } else if expired { "CANCELLED" } else { "NEEDS-ACTION" }),
+        Component::Event => ("VEVENT", if completed { "CONFIRMED" } else if expired { "CANCELLED" } else { "TENTATIVE" }),
+    };
+
+    let mut lines = vec![
+        format!["BEGIN:{}", name],
+        format!["UID:{}", entry.entry.id],
+        format!["DTSTAMP:{}", format_timestamp(&entry.entry.updated)],
+        format!["CREATED:{}", format_timestamp(&entry.entry.created)],
+        format!["LAST-MODIFIED:{}", format_timestamp(&entry.entry.updated)],
+        format!["SUMMARY:{}", escape_text(&entry.entry.task)],
+    ];
+
+    match component {
+        Component::Todo => {
+            lines.push(format!["DUE;VALUE=DATE:{}", format_date(&due_date)]);
+        },
+        Component::Event => {
+            lines.push(format!["DTSTART;VALUE=DATE:{}", format_date(&due_date)]);
+            lines.push(format!["DTEND;VALUE=DATE:{}", format_date(&due_date.add_days(1))]);
+            lines.push(String::from("TRANSP:TRANSPARENT"));
+        },
+    }
+
+    lines.push(format!["STATUS:{}", status]);
+
+    if completed && component == Component::Todo {
+        lines.push(format!["COMPLETED:{}", format_timestamp(&entry.entry.updated)]);
+    }
+
+    /* finished entries have nothing left to remind about */
+    if !completed && !expired {
+        let mut offsets: Vec<i64> = entry.reminders
+            .iter()
+            .filter_map(|r| r.offset.parse::<i64>().ok())
+            .collect();
+        offsets.sort_unstable_by(|a, b| b.cmp(a));
+        offsets.dedup();
+
+        for offset in offsets {
+            lines.extend(alarm_lines(&entry.entry.task, offset, component));
+        }
+    }
+
+    lines.push(format!["END:{}", name]);
+
+    lines
+}
+
+/* a display alarm "offset" days before the due date */
+fn alarm_lines(task: &str, offset: i64, component: Component) -> Vec<String> {
+    /* a VTODO's date is its DUE (the end), a VEVENT's is its DTSTART */
+    let related = match component {
+        Component::Todo => ";RELATED=END",
+        Component::Event => "",
+    };
+    let trigger = if offset == 0 { String::from("PT0S") } else { format!["-P{}D", offset] };
+
+    vec![
+        String::from("BEGIN:VALARM"),
+        String::from("ACTION:DISPLAY"),
+        format!["DESCRIPTION:{}", escape_text(task)],
+        format!["TRIGGER{}:{}", related, trigger],
+        String::from("END:VALARM"),
+    ]
+}
+
+pub fn format_date(date: &Date) -> String {
+    format!["{:04}{:02}{:02}", date.year(), date.month(), date.day()]
+}
+
+pub fn format_timestamp(timestamp: &NaiveDateTime) -> String {
+    timestamp.format("%Y%m%dT%H%M%SZ").to_string()
+}
+
+/* RFC 5545 3.3.11 */
+pub fn escape_text(text: &str) -> String {
+    text
+        .replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace("\r\n", "\\n")
+        .replace('\n', "\\n")
+}
+
+/* splits a line into 75-octet pieces, continuations starting with a space (never inside a UTF-8 character) */
+pub fn fold_line(line: &str) -> String {
+    let mut folded = String::new();
+    let mut octets = 0;
+
+    for c in line.chars() {
+        if octets + c.len_utf8() > LINE_OCTETS {
+            folded.push_str("\r\n ");
+            octets = 1;
+        }
+
+        folded.push(c);
+        octets += c.len_utf8();
+    }
+
+    folded
+}
+
+/* the parts of a VTODO that map onto an entry */
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Todo {
+    pub uid: Option<String>,
+    pub summary: Option<String>,
+    pub due: Option<Date>,
+    pub completed: bool,
+    pub alarms: Vec<i64>,
+}
+
+/* a content line split into name, parameters and value */
+#[derive(Debug, Clone, PartialEq)]
+pub struct Property {
+    pub name: String,
+    pub params: Vec<(String, String)>,
+    pub value: String,
+}
+
+impl Property {
+    pub fn param(&self, name: &str) -> Option<&str> {
+        self.params
+            .iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+}
+
+/* undoes line folding, RFC 5545 3.1 */
+pub fn unfold_lines(text: &str) -> Vec<String> {
+    let mut lines: Vec<String> = vec![];
+
+    for line in text.split('\n') {
+        let line = line.strip_suffix('\r').unwrap_or(line);
+
+        if let Some(rest) = line.strip_prefix(' ').or_else(|| line.strip_prefix('\t')) {
+            if let Some(last) = lines.last_mut() {
+                last.push_str(rest);
+                continue;
+            }
+        }
+
+        if !line.is_empty() {
+            lines.push(String::from(line));
+        }
+    }
+
+    lines
+}
+
+pub fn parse_property(line: &str) -> Option<Property> {
+    /* the value starts at the first colon outside a quoted parameter value */
+    let mut quoted = false;
+    let mut split = None;
+
+    for (i, c) in line.char_indices() {
+        match c {
+            '"' => quoted = !quoted,
+            ':' if !quoted => {
+                split = Some(i);
+                break;
+            },
+            _ => {},
+        }
+    }
+
+    let split = split?;
+    let mut parts = line[..split].split(';');
+    let name = parts.next()?.to_uppercase();
+    let params = parts
+        .filter_map(|p| p.split_once('='))
+        .map(|(n, v)| (n.to_uppercase(), String::from(v.trim_matches('"'))))
+        .collect();
+
+    Some(Property {
+        name,
+        params,
+        value: String::from(&line[split + 1..]),
+    })
+}
+
+pub fn unescape_text(text: &str) -> String {
+    let mut result = String::new();
+    let mut chars = text.chars();
+
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            match chars.next() {
+                Some('n') | Some('N') => result.push('\n'),
+                Some(other) => result.push(other),
+                None => {},
+            }
+        } else {
+            result.push(c);
+        }
+    }
+
+    result
+}
+
+/* the first VTODO in an iCalendar object */
+pub fn parse_todo(text: &str) -> Result<Todo, String> {
+    let todos = parse_todos(text)?;
+
+    todos.into_iter().next().ok_or_else(|| String::from("No VTODO present"))
+}
+
+/* every VTODO in an iCalendar object (other components, e.g. VTIMEZONE, are skipped) */
+pub fn parse_todos(text: &str) -> Result<Vec<Todo>, String> {
+    let mut todos = vec![];
+    let mut todo: Option<Todo> = None;
+    let mut in_alarm = false;
+    let mut trigger: Option<Property> = None;
+    let mut seen_calendar = false;
+
+    for line in unfold_lines(text) {
+        let property = parse_property(&line).ok_or_else(|| format!["Invalid line: {}", line])?;
+
+        match (property.name.as_str(), property.value.to_uppercase().as_str()) {
+            ("BEGIN", "VCALENDAR") => { seen_calendar = true; },
+            ("BEGIN", "VTODO") => { todo = Some(Todo::default()); },
+            ("END", "VTODO") => {
+                if let Some(t) = todo.take() {
+                    todos.push(t);
+                }
+            },
+            ("BEGIN", "VALARM") => { in_alarm = true; },
+            ("END", "VALARM") => {
+                in_alarm = false;
+
+                if let (Some(t), Some(p)) = (todo.as_mut(), trigger.take()) {
+                    t.alarms.push(trigger_offset(&p, t.due.as_ref())?);
+                }
+            },
+            _ => {
+                let Some(t) = todo.as_mut() else { continue; };
+
+                if in_alarm {
+                    if property.name == "TRIGGER" {
+                        trigger = Some(property);
+                    }
+                    continue;
+                }
+
+                match property.name.as_str() {
+                    "UID" => t.uid = Some(property.value.clone()),
+                    "SUMMARY" => t.summary = Some(unescape_text(&property.value)),
+                    "DUE" => t.due = Some(parse_date(&property.value)?),
+                    "STATUS" => t.completed = t.completed || property.value.eq_ignore_ascii_case("COMPLETED"),
+                    "COMPLETED" => t.completed = true,
+                    "PERCENT-COMPLETE" => t.completed = t.completed || property.value.trim() == "100",
+                    _ => {},
+                }
+            },
+        }
+    }
+
+    if !seen_calendar {
+        return Err(String::from("No VCALENDAR present"));
+    }
+
+    Ok(todos)
+}
+
+/* DATE or DATE-TIME values: only the date part is kept, entries have no time of day */
+pub fn parse_date(value: &str) -> Result<Date, String> {
+    let digits = value.get(..8).ok_or_else(|| format!["Invalid date: {}", value])?;
+    let field = |range: std::ops::Range<usize>| digits[range].parse::<u32>().map_err(|_| format!["Invalid date: {}", value]);
+
+    Date::new(field(0..4)? as i32, field(4..6)?, field(6..8)?).map_err(|_| format!["Invalid date: {}", value])
+}
+
+/* days before the due date: durations are rounded down to whole days, alarms after the due date become 0 */
+fn trigger_offset(trigger: &Property, due: Option<&Date>) -> Result<i64, String> {
+    if trigger.param("VALUE").map(|v| v.eq_ignore_ascii_case("DATE-TIME")) == Some(true) {
+        let date = parse_date(&trigger.value)?;
+        let due = due.ok_or("Alarm without due date")?;
+
+        return Ok(Date::difference(due, &date).max(0));
+    }
+
+    let seconds = parse_duration(&trigger.value).ok_or_else(|| format!["Invalid trigger: {}", trigger.value])?;
+
+    Ok((-seconds).max(0) / 86400)
+}
+
+/* RFC 5545 3.3.6, in seconds */
+pub fn parse_duration(value: &str) -> Option<i64> {
+    let (sign, rest) = match value.chars().next()? {
+        '-' => (-1, &value[1..]),
+        '+' => (1, &value[1..]),
+        _ => (1, value),
+    };
+    let rest = rest.strip_prefix('P')?;
+    let mut seconds = 0;
+    let mut number = String::new();
+    let mut in_time = false;
+
+    for c in rest.chars() {
+        match c {
+            '0'..='9' => number.push(c),
+            'T' => in_time = true,
+            _ => {
+                let n: i64 = number.parse().ok()?;
+                number.clear();
+
+                seconds += n * match (c, in_time) {
+                    ('W', false) => 7 * 86400,
+                    ('D', false) => 86400,
+                    ('H', true) => 3600,
+                    ('M', true) => 60,
+                    ('S', true) => 1,
+                    _ => { return None; },
+                };
+            },
+        }
+    }
+
+    if !number.is_empty() {
+        return None;
+    }
+
+    Some(sign * seconds)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{
+        entry::{
+            Entry,
+            entry_fixture,
+        },
+        reminder::Reminder,
+    };
+
+    fn entry(completed: &str, expired: &str, offsets: &[&str]) -> EntryRead {
+        let entry = Entry {
+            task: String::from("Pay rent, water; gas"),
+            expired: String::from(expired),
+            completed: String::from(completed),
+            ..entry_fixture()
+        };
+        let reminders = offsets
+            .iter()
+            .map(|o| Reminder {
+                id: String::new(),
+                entry_id: String::from("entry-1"),
+                offset: String::from(*o),
+                channel: String::from("default"),
+            })
+            .collect();
+
+        EntryRead::new(entry, reminders, vec![])
+    }
+
+    #[test]
+    fn todo_lines() {
+        let lines = entry_lines(&entry("false", "false", &["0", "7"]), Component::Todo);
+
+        assert_eq!(lines, vec![
+            "BEGIN:VTODO",
+            "UID:entry-1",
+            "DTSTAMP:20300601T123000Z",
+            "CREATED:20300601T123000Z",
+            "LAST-MODIFIED:20300601T123000Z",
+            "SUMMARY:Pay rent\\, water\\; gas",
+            "DUE;VALUE=DATE:20300615",
+            "STATUS:NEEDS-ACTION",
+            "BEGIN:VALARM",
+            "ACTION:DISPLAY",
+            "DESCRIPTION:Pay rent\\, water\\; gas",
+            "TRIGGER;RELATED=END:-P7D",
+            "END:VALARM",
+            "BEGIN:VALARM",
+            "ACTION:DISPLAY",
+            "DESCRIPTION:Pay rent\\, water\\; gas",
+            "TRIGGER;RELATED=END:PT0S",
+            "END:VALARM",
+            "END:VTODO",
+        ]);
+    }
+
+    #[test]
+    fn event_all_day() {
+        let lines = entry_lines(&entry("false", "false", &["1"]), Component::Event);
+
+        assert!(
+            lines.contains(&String::from("DTSTART;VALUE=DATE:20300615")) &&
+            lines.contains(&String::from("DTEND;VALUE=DATE:20300616")) &&
+            lines.contains(&String::from("TRIGGER:-P1D")) &&
+            lines.contains(&String::from("STATUS:TENTATIVE"))
+        );
+    }
+
+    #[test]
+    fn status_mapping() {
+        let completed = entry_lines(&entry("true", "false", &["1"]), Component::Todo);
+        let expired = entry_lines(&entry("false", "true", &["1"]), Component::Todo);
+
+        assert!(
+            completed.contains(&String::from("STATUS:COMPLETED")) &&
+            completed.contains(&String::from("COMPLETED:20300601T123000Z")) &&
+            expired.contains(&String::from("STATUS:CANCELLED")) &&
+            !completed.contains(&String::from("BEGIN:VALARM")) &&
+            !expired.contains(&String::from("BEGIN:VALARM"))
+        );
+    }
+
+    #[test]
+    fn calendar_wrapper() {
+        let ics = calendar("user", &[entry("false", "false", &[])], Component::Todo);
+
+        assert!(
+            ics.starts_with("BEGIN:VCALENDAR\r\nVERSION:2.0\r\n") &&
+            ics.ends_with("END:VTODO\r\nEND:VCALENDAR\r\n")
+        );
+    }
+
+    #[test]
+    fn fold_long_line() {
+        let line = format!["SUMMARY:{}", "é".repeat(60)];
+        let folded = fold_line(&line);
+
+        assert!(folded.split("\r\n").all(|l| l.len() <= LINE_OCTETS));
+        assert_eq!(folded.replace("\r\n ", ""), line);
+    }
+
+    #[test]
+    fn component_param() {
+        assert!(
+            Component::from_param(None) == Some(Component::Todo) &&
+            Component::from_param(Some("vevent")) == Some(Component::Event) &&
+            Component::from_param(Some("vjournal")).is_none()
+        );
+    }
+
+    #[test]
+    fn parse_round_trip() {
+        let ics = calendar("user", &[entry("false", "false", &["0", "7"])], Component::Todo);
+        let todo = parse_todo(&ics).unwrap();
+
+        assert_eq!(todo, Todo {
+            uid: Some(String::from("entry-1")),
+            summary: Some(String::from("Pay rent, water; gas")),
+            due: Some(Date::new(2030, 6, 15).unwrap()),
+            completed: false,
+            alarms: vec![7, 0],
+        });
+    }
+
+    #[test]
+    fn parse_folded_and_completed() {
+        let ics = "BEGIN:VCALENDAR\r\nBEGIN:VTODO\r\nUID:abc\r\nSUMMARY:Long\r\n  task\r\nDUE;TZID=Europe/Berlin:20300615T090000\r\nSTATUS:COMPLETED\r\nEND:VTODO\r\nEND:VCALENDAR\r\n";
+        let todo = parse_todo(ics).unwrap();
+
+        assert!(
+            todo.summary == Some(String::from("Long task")) &&
+            todo.due == Some(Date::new(2030, 6, 15).unwrap()) &&
+            todo.completed
+        );
+    }
+
+    #[test]
+    fn parse_invalid() {
+        assert!(
+            parse_todo("BEGIN:VTODO\r\nEND:VTODO\r\n").is_err() &&
+            parse_todo("BEGIN:VCALENDAR\r\nEND:VCALENDAR\r\n").is_err() &&
+            parse_todo("BEGIN:VCALENDAR\r\nBEGIN:VTODO\r\nDUE:2030\r\nEND:VTODO\r\nEND:VCALENDAR\r\n").is_err()
+        );
+    }
+
+    #[test]
+    fn durations() {
+        assert!(
+            parse_duration("-P7D") == Some(-7 * 86400) &&
+            parse_duration("-PT15M") == Some(-900) &&
+            parse_duration("P1W") == Some(7 * 86400) &&
+            parse_duration("-P1DT12H") == Some(-129600) &&
+            parse_duration("PT0S") == Some(0) &&
+            parse_duration("-P1X").is_none()
+        );
+    }
+}
\ No newline at end of file
@@ -0,0 +1,8 @@
+/*************************************************************************
+	"task_server"
+	calendar/mod.rs
+	10/18/26
+	© Matthew Bentivegna
+*************************************************************************/
+pub mod ical;
+pub mod caldav;
\ No newline at end of file
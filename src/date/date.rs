@@ -177,6 +177,24 @@ impl Date {
         }
     }
     
+    pub fn add_days(&self, days: i64) -> Date {
+        let d = NaiveDate::from_ymd_opt(self.year, self.month, self.day).unwrap() + chrono::Duration::days(days);
+        
+        Self {
+            year: d.year(),
+            month: d.month(),
+            day: d.day(),
+        }
+    }
+    
+    /* the UTC time of the midnight that ends this date, for a user "utc_offset" hours from UTC */
+    pub fn end_of_day_utc(&self, utc_offset: i32) -> NaiveDateTime {
+        let d = NaiveDate::from_ymd_opt(self.year, self.month, self.day).unwrap();
+        let t = NaiveTime::from_hms_milli_opt(0, 0, 0, 0).unwrap();
+        
+        NaiveDateTime::new(d, t) + chrono::Duration::days(1) - chrono::Duration::hours(utc_offset as i64)
+    }
+    
     fn validate_year(year: i32) -> Result<i32, DateInvalidYear> {
         match year {
             Self::VALID_YEAR_MIN..=Self::VALID_YEAR_MAX => Ok(year),
@@ -481,6 +499,32 @@ mod tests {
         )
     }
 
+    #[test]
+    fn add_days_across_month() {
+        let date = Date::new(2024, 2, 28).unwrap();
+        
+        assert_eq!(date.add_days(2), Date::new(2024, 3, 1).unwrap());
+    }
+    
+    #[test]
+    fn add_days_negative() {
+        let date = Date::new(2024, 1, 1).unwrap();
+        
+        assert_eq!(date.add_days(-1), Date::new(2023, 12, 31).unwrap());
+    }
+    
+    #[test]
+    fn end_of_day_utc_offsets() {
+        let date = Date::new(2023, 10, 27).unwrap();
+        let midnight = NaiveDate::from_ymd_opt(2023, 10, 28).unwrap().and_hms_opt(0, 0, 0).unwrap();
+        
+        assert!(
+            date.end_of_day_utc(0) == midnight &&
+            date.end_of_day_utc(5) == midnight - chrono::Duration::hours(5) &&
+            date.end_of_day_utc(-8) == midnight + chrono::Duration::hours(8)
+        );
+    }
+
     #[test]
     fn today_or_later_before() {
         let date = Date::new(2020, 4, 1).unwrap();
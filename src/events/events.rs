@@ -0,0 +1,274 @@
+/*************************************************************************
+    "task_server"
+    events/events.rs
+    10/18/26
+    © Matthew Bentivegna
+*************************************************************************/
+use std::time::{
+    Duration,
+    Instant,
+};
+use chrono::Utc;
+use tokio::sync::broadcast::{
+    self,
+    error::RecvError,
+};
+use hmac::{
+    Hmac,
+    Mac,
+};
+use sha2::Sha256;
+use crate::models::{
+    entry::Entry,
+    entry_event::{
+        EntryEvent,
+        EntryEventNotice,
+        EntryEventWrite,
+        entry_event_lists,
+    },
+    list::LIST_INBOX,
+    webhook::{
+        WebhookDelivery,
+        EVENT_UPDATED,
+        EVENT_COMPLETED,
+    },
+};
+use crate::repository::database::Database;
+
+/* entry events a stream or socket reads from the database at a time, when it resumes or has fallen behind the broadcast */
+const EVENT_PAGE_SIZE: i64 = 100;
+
+/* records "event" for the entry event streams and broadcasts it to the open ones, and queues a delivery of it for every webhook subscribed
+   to it (the entry owner's, and the admin's) */
+pub fn dispatch(db: &Database, event: &str, entry: &Entry) {
+    dispatch_lists(db, event, entry, None);
+}
+
+fn dispatch_lists(db: &Database, event: &str, entry: &Entry, before: Option<&Entry>) {
+    let now = Utc::now();
+    let payload = event_payload(event, entry, now);
+
+    let previous_list = before.map(|b| b.list_id.clone().unwrap_or(String::from(LIST_INBOX)));
+
+    if let Some(recorded) = db.create_entry_event(EntryEventWrite::new(&entry.id, &entry.user_id, event, &payload, previous_list.as_deref(), now.naive_utc())) {
+        let lists = entry_event_lists(&recorded);
+        db.publish_entry_event(EntryEventNotice { event: recorded, lists });
+    }
+
+    let webhooks: Vec<_> = db.get_webhooks_for_user_id(&entry.user_id)
+        .into_iter()
+        .filter(|w| w.subscribes_to(event))
+        .collect();
+
+    for webhook in webhooks {
+        db.create_webhook_delivery(WebhookDelivery::new(&webhook.id, event, &payload, now.naive_utc()));
+    }
+}
+
+/* an update may also complete the entry; it concerns the list the entry left as well as the one it is in */
+pub fn dispatch_update(db: &Database, before: &Entry, after: &Entry) {
+    dispatch_lists(db, EVENT_UPDATED, after, Some(before));
+
+    if before.completed != "true" && after.completed == "true" {
+        dispatch_lists(db, EVENT_COMPLETED, after, Some(before));
+    }
+}
+
+/* an event stream's or socket's place in the entry events: it is sent those for one user's entries (None: every user's) and, once it
+   subscribes to lists, only those in them; what it missed (before it subscribed, or by falling behind the broadcast) comes from the database */
+pub struct EventSubscription {
+    receiver: broadcast::Receiver<EntryEventNotice>,
+    user_id: Option<String>,
+    lists: Option<Vec<String>>,
+    /* the last event sent, and the last one read from the database (broadcast ones up to it were sent with it) */
+    after: i64,
+    read_to: i64,
+    missed: bool,
+}
+
+impl EventSubscription {
+    /* subscribes to the broadcast before reading the database, so no event can fall between the two */
+    pub fn new(db: &Database, user_id: Option<String>, after: i64) -> Self {
+        Self {
+            receiver: db.subscribe_entry_events(),
+            user_id,
+            lists: None,
+            after,
+            read_to: after,
+            missed: true,
+        }
+    }
+
+    /* None is every list again */
+    pub fn set_lists(&mut self, lists: Option<Vec<String>>) {
+        self.lists = lists;
+    }
+
+    /* the next events for the subscriber; empty when "wait" passes without any, None when the broadcast has ended */
+    pub async fn next(&mut self, db: &Database, wait: Duration) -> Option<Vec<EntryEvent>> {
+        let deadline = Instant::now() + wait;
+
+        loop {
+            if self.missed {
+                let events = db.get_entry_events_after(self.after, self.user_id.as_deref(), EVENT_PAGE_SIZE);
+
+                self.missed = events.len() as i64 == EVENT_PAGE_SIZE;
+
+                if let Some(last) = events.last() {
+                    self.after = last.id;
+                    self.read_to = last.id;
+                }
+
+                let events: Vec<EntryEvent> = events
+                    .into_iter()
+                    .filter(|e| self.concerns(&e.user_id, &entry_event_lists(e)))
+                    .collect();
+
+                if !events.is_empty() {
+                    return Some(events);
+                }
+
+                continue;
+            }
+
+            match actix_web::rt::time::timeout(deadline.saturating_duration_since(Instant::now()), self.receiver.recv()).await {
+                Ok(Ok(notice)) => {
+                    if let Some(event) = self.accept(notice) {
+                        return Some(vec![event]);
+                    }
+                },
+                Ok(Err(RecvError::Lagged(_))) => { self.missed = true; },
+                Ok(Err(RecvError::Closed)) => { return None; },
+                Err(_) => { return Some(vec![]); },
+            }
+        }
+    }
+
+    /* a broadcast event, unless it was already read from the database or is not one the subscriber follows */
+    fn accept(&mut self, notice: EntryEventNotice) -> Option<EntryEvent> {
+        if notice.event.id <= self.read_to || !self.concerns(&notice.event.user_id, &notice.lists) {
+            return None;
+        }
+
+        self.after = self.after.max(notice.event.id);
+        Some(notice.event)
+    }
+
+    fn concerns(&self, user_id: &str, lists: &[String]) -> bool {
+        self.user_id.as_deref().is_none_or(|u| u == user_id) &&
+        self.lists.as_ref().is_none_or(|subscribed| lists.iter().any(|l| subscribed.contains(l)))
+    }
+}
+
+pub fn event_payload(event: &str, entry: &Entry, now: chrono::DateTime<Utc>) -> String {
+    serde_json::json!({
+        "event": event,
+        "timestamp": now.to_rfc3339(),
+        "data": entry,
+    })
+    .to_string()
+}
+
+/* value of the "X-Signature" header: HMAC-SHA256 of the raw body, keyed with the subscription secret */
+pub fn signature(secret: &str, body: &str) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(body.as_bytes());
+
+    let digest: String = mac
+        .finalize()
+        .into_bytes()
+        .iter()
+        .map(|b| format!["{:02x}", b])
+        .collect();
+
+    format!["sha256={}", digest]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{
+        NaiveDateTime,
+        TimeZone,
+    };
+    use crate::models::entry::entry_fixture;
+    use crate::models::webhook::EVENT_CREATED;
+
+    fn notice(id: i64, user_id: &str, lists: &[&str]) -> EntryEventNotice {
+        EntryEventNotice {
+            event: EntryEvent {
+                id,
+                entry_id: String::from("entry-1"),
+                user_id: String::from(user_id),
+                event: String::from(EVENT_UPDATED),
+                payload: String::from("{}"),
+                created: NaiveDateTime::MIN,
+                previous_list: None,
+            },
+            lists: lists.iter().map(|l| String::from(*l)).collect(),
+        }
+    }
+
+    fn subscription(user_id: Option<&str>, lists: Option<&[&str]>, read_to: i64) -> EventSubscription {
+        EventSubscription {
+            receiver: broadcast::channel(1).1,
+            user_id: user_id.map(String::from),
+            lists: lists.map(|l| l.iter().map(|l| String::from(*l)).collect()),
+            after: read_to,
+            read_to,
+            missed: false,
+        }
+    }
+
+    #[test]
+    fn subscription_user_and_lists() {
+        let mut own = subscription(Some("user-1"), None, 0);
+        let mut board = subscription(Some("user-1"), Some(&["list-1"]), 0);
+        let mut admin = subscription(None, Some(&["inbox"]), 0);
+
+        assert!(
+            own.accept(notice(1, "user-1", &["inbox"])).is_some() &&
+            own.accept(notice(2, "user-2", &["inbox"])).is_none() &&
+            board.accept(notice(3, "user-1", &["inbox"])).is_none() &&
+            /* moved out of the list */
+            board.accept(notice(4, "user-1", &["list-1", "inbox"])).is_some() &&
+            admin.accept(notice(5, "user-2", &["inbox"])).is_some() &&
+            admin.accept(notice(6, "user-2", &["list-2"])).is_none()
+        );
+    }
+
+    #[test]
+    fn subscription_skips_read() {
+        let mut resumed = subscription(None, None, 10);
+
+        assert!(resumed.accept(notice(9, "user-1", &["inbox"])).is_none() && resumed.accept(notice(11, "user-1", &["inbox"])).is_some());
+        assert_eq!(resumed.after, 11);
+    }
+
+    #[test]
+    fn signature_known_vector() {
+        /* RFC 4231, test case 2 */
+        assert_eq!(
+            signature("Jefe", "what do ya want for nothing?"),
+            "sha256=5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+    }
+
+    #[test]
+    fn signature_depends_on_secret() {
+        assert_ne!(signature("secret-one", "{}"), signature("secret-two", "{}"));
+    }
+
+    #[test]
+    fn payload_fields() {
+        let entry = entry_fixture();
+        let now = Utc.with_ymd_and_hms(2030, 6, 1, 12, 0, 0).unwrap();
+        let payload: serde_json::Value = serde_json::from_str(&event_payload(EVENT_CREATED, &entry, now)).unwrap();
+
+        assert!(
+            payload["event"] == EVENT_CREATED &&
+            payload["timestamp"] == "2030-06-01T12:00:00+00:00" &&
+            payload["data"]["id"] == "entry-1"
+        );
+    }
+}
\ No newline at end of file
@@ -0,0 +1,245 @@
+/*************************************************************************
+    "task_server"
+    export/export.rs
+    10/18/26
+    © Matthew Bentivegna
+*************************************************************************/
+use crate::models::{
+    entry::{
+        EntryRead,
+        entry_due_date,
+    },
+    reminder::Reminder,
+    audit::AuditEvent,
+    channel::CHANNEL_DEFAULT,
+    export::{
+        FORMAT_CSV,
+        FORMAT_JSONL,
+        FORMAT_MD,
+    },
+};
+
+/* the CSV columns line up with the import, so an export can be imported again (the import ignores "id", "username", "expired",
+   "created" and "updated") */
+const CSV_HEADER: [&str; 11] = ["id", "username", "task", "due", "reminders", "completed", "expired", "tags", "list_id", "created", "updated"];
+
+const AUDIT_CSV_HEADER: [&str; 7] = ["id", "created", "action", "actor", "target", "ip", "detail"];
+
+/* where a streamed export has got to */
+#[derive(Debug, Clone, PartialEq)]
+pub enum ExportCursor {
+    Start,
+    After(String),
+    Done,
+}
+
+/* written once, before the first entry */
+pub fn export_header(format: &str) -> String {
+    match format {
+        FORMAT_CSV => csv_line(&CSV_HEADER.map(String::from)),
+        FORMAT_MD => String::from("| Due | Task | Reminders | Status | User |\n| --- | --- | --- | --- | --- |\n"),
+        _ => String::new(),
+    }
+}
+
+pub fn export_row(format: &str, entry: &EntryRead) -> String {
+    match format {
+        FORMAT_JSONL => serde_json::to_string(entry).unwrap_or_default() + "\n",
+        FORMAT_MD => format![
+            "| {} | {} | {} | {} | {} |\n",
+            due(entry),
+            markdown_cell(&entry.entry.task),
+            offsets(entry).join(", "),
+            status(entry),
+            markdown_cell(&entry.entry.username),
+        ],
+        _ => csv_line(&[
+            entry.entry.id.clone(),
+            entry.entry.username.clone(),
+            entry.entry.task.clone(),
+            due(entry),
+            reminders(entry).join(";"),
+            entry.entry.completed.clone(),
+            entry.entry.expired.clone(),
+            entry.tags.join(","),
+            entry.entry.list_id.clone().unwrap_or_default(),
+            entry.entry.created.format("%Y-%m-%dT%H:%M:%S").to_string(),
+            entry.entry.updated.format("%Y-%m-%dT%H:%M:%S").to_string(),
+        ]),
+    }
+}
+
+pub fn audit_export_header(format: &str) -> String {
+    match format {
+        FORMAT_CSV => csv_line(&AUDIT_CSV_HEADER.map(String::from)),
+        FORMAT_MD => String::from("| Time | Action | Actor | Target | IP | Detail |\n| --- | --- | --- | --- | --- | --- |\n"),
+        _ => String::new(),
+    }
+}
+
+pub fn audit_export_row(format: &str, event: &AuditEvent) -> String {
+    let field = |value: &Option<String>| value.clone().unwrap_or_default();
+    let created = event.created.format("%Y-%m-%dT%H:%M:%S").to_string();
+
+    match format {
+        FORMAT_JSONL => serde_json::to_string(event).unwrap_or_default() + "\n",
+        FORMAT_MD => format![
+            "| {} | {} | {} | {} | {} | {} |\n",
+            created,
+            event.action,
+            markdown_cell(&field(&event.actor)),
+            markdown_cell(&field(&event.target)),
+            field(&event.ip),
+            markdown_cell(&field(&event.detail)),
+        ],
+        _ => csv_line(&[
+            event.id.to_string(),
+            created,
+            event.action.clone(),
+            field(&event.actor),
+            field(&event.target),
+            field(&event.ip),
+            field(&event.detail),
+        ]),
+    }
+}
+
+/* YYYY-MM-DD, or the stored fields as they are if they do not form a date */
+fn due(entry: &EntryRead) -> String {
+    match entry_due_date(&entry.entry) {
+        Ok(date) => format!["{:04}-{:02}-{:02}", date.year(), date.month(), date.day()],
+        Err(_) => format!["{}-{}-{}", entry.entry.year, entry.entry.month, entry.entry.day],
+    }
+}
+
+/* every reminder, largest offset first, as "7" (the default channel) or "7:email" */
+fn reminders(entry: &EntryRead) -> Vec<String> {
+    let mut list: Vec<&Reminder> = entry.reminders.iter().collect();
+    list.sort_by_key(|r| std::cmp::Reverse(r.offset.parse::<i64>().unwrap_or(0)));
+
+    list
+        .into_iter()
+        .map(|r| match r.channel.as_str() {
+            CHANNEL_DEFAULT => r.offset.clone(),
+            channel => format!["{}:{}", r.offset, channel],
+        })
+        .collect()
+}
+
+/* largest first, as they would fire (for reading: one offset on several channels is shown once) */
+fn offsets(entry: &EntryRead) -> Vec<String> {
+    let mut offsets: Vec<&str> = entry.reminders.iter().map(|r| r.offset.as_str()).collect();
+    offsets.sort_by_key(|o| std::cmp::Reverse(o.parse::<i64>().unwrap_or(0)));
+    offsets.dedup();
+
+    offsets.into_iter().map(String::from).collect()
+}
+
+fn status(entry: &EntryRead) -> &'static str {
+    if entry.entry.completed == "true" {
+        "completed"
+    } else if entry.entry.expired == "true" {
+        "expired"
+    } else {
+        "open"
+    }
+}
+
+fn csv_line(fields: &[String]) -> String {
+    let mut writer = csv::Writer::from_writer(vec![]);
+    writer.write_record(fields).ok();
+
+    String::from_utf8(writer.into_inner().unwrap_or_default()).unwrap_or_default()
+}
+
+fn markdown_cell(text: &str) -> String {
+    text
+        .replace('\\', "\\\\")
+        .replace('|', "\\|")
+        .replace(['\r', '\n'], " ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDateTime;
+    use crate::models::{
+        entry::{
+            Entry,
+            entry_fixture,
+        },
+    };
+    use crate::import::import::import_items;
+
+    fn entry(task: &str, completed: &str) -> EntryRead {
+        let entry = Entry {
+            day: String::from("5"),
+            task: String::from(task),
+            reminder: Some(String::from("7")),
+            completed: String::from(completed),
+            ..entry_fixture()
+        };
+        let reminder = |offset: &str, channel: &str| Reminder {
+            id: String::new(),
+            entry_id: String::from("entry-1"),
+            offset: String::from(offset),
+            channel: String::from(channel),
+        };
+
+        EntryRead::new(
+            Entry { list_id: Some(String::from("list-1")), ..entry },
+            vec![reminder("1", "default"), reminder("7", "default"), reminder("1", "webhook")],
+            vec![String::from("home"), String::from("bills")],
+        )
+    }
+
+    #[test]
+    fn csv_round_trip() {
+        let csv = export_header(FORMAT_CSV) + &export_row(FORMAT_CSV, &entry("Pay rent, \"water\"", "true"));
+        let items = import_items("csv", &csv, None).unwrap();
+        let write = items[0].write.as_ref().unwrap();
+
+        assert!(csv.contains("entry-1,user,\"Pay rent, \"\"water\"\"\",2030-06-05,7;1;1:webhook,true,false,\"home,bills\",list-1,2030-06-01T12:30:00,2030-06-01T12:30:00\n"));
+        assert!(
+            write.task == "Pay rent, \"water\"" &&
+            write.day == "5" &&
+            write.completed == Some(String::from("true")) &&
+            write.reminders.as_ref().unwrap().len() == 3 &&
+            write.reminders.as_ref().unwrap()[2].channel.as_deref() == Some("webhook") &&
+            write.tags == Some(vec![String::from("home"), String::from("bills")]) &&
+            write.list_id.as_deref() == Some("list-1")
+        );
+    }
+
+    #[test]
+    fn jsonl_line() {
+        let line = export_row(FORMAT_JSONL, &entry("Pay rent", "false"));
+        let value: serde_json::Value = serde_json::from_str(line.trim_end()).unwrap();
+
+        assert!(line.ends_with("}\n") && value["id"] == "entry-1" && value["reminders"].as_array().unwrap().len() == 3);
+    }
+
+    #[test]
+    fn markdown_row() {
+        assert_eq!(
+            export_row(FORMAT_MD, &entry("Pay rent | water\nand gas", "false")),
+            "| 2030-06-05 | Pay rent \\| water and gas | 7, 1 | open | user |\n"
+        );
+    }
+
+    #[test]
+    fn audit_csv_row() {
+        let event = AuditEvent {
+            id: 3,
+            action: String::from("login.failed"),
+            actor: Some(String::from("bob")),
+            target: None,
+            ip: Some(String::from("127.0.0.1")),
+            detail: None,
+            created: NaiveDateTime::parse_from_str("2026-10-18 09:30:00", "%Y-%m-%d %H:%M:%S").unwrap(),
+        };
+
+        assert_eq!(audit_export_header(FORMAT_CSV), "id,created,action,actor,target,ip,detail\n");
+        assert_eq!(audit_export_row(FORMAT_CSV, &event), "3,2026-10-18T09:30:00,login.failed,bob,,127.0.0.1,\n");
+    }
+}
\ No newline at end of file
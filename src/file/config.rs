@@ -13,16 +13,21 @@ use std::{
 };
 use crate::file::options::Options;
 
-pub fn config_load(filename: &str) -> Options {
+/* where the loaded options came from, for the audit log */
+pub const CONFIG_SOURCE_FILE: &str = "file";
+pub const CONFIG_SOURCE_DEFAULTS: &str = "defaults (invalid file)";
+pub const CONFIG_SOURCE_CREATED: &str = "defaults (new file)";
+
+pub fn config_load(filename: &str) -> (Options, &'static str) {
     match fs::read_to_string(filename) {
         Ok(config_data) => {
             match Options::from_file_data(&config_data) {
                 Ok(options) => {
-                    return options;
+                    return (options, CONFIG_SOURCE_FILE);
                 },
                 Err(()) => {
                     eprintln!("Error parsing configuration file, using default configuration values");
-                    return Options::default();
+                    return (Options::default(), CONFIG_SOURCE_DEFAULTS);
                 },
             }
         },
@@ -30,7 +35,7 @@ pub fn config_load(filename: &str) -> Options {
             eprintln!("{e} - Creating new default configuration file");
             let options = Options::default();
             config_write(filename, &options.to_string());
-            return options;
+            return (options, CONFIG_SOURCE_CREATED);
         },
     }
 }
@@ -14,11 +14,20 @@ pub struct Options {
     enable_expired_emails: bool,
     server_ip: String,
     server_port: u16,
+    outbox_max_attempts: u32,
+    outbox_retry_seconds: u32,
+    idempotency_window_hours: u32,
+    trash_retention_days: u32,
+    webhook_allowed_hosts: Vec<String>,
 }
 
 impl Options {
     const OCTET_MAX: u32 = 255;
     
+    /* lines after the required ones are optional, so older configuration files remain valid */
+    const LINES_REQUIRED: usize = 7;
+    const LINES_TOTAL: usize = 12;
+    
     const PREFIX_SENDER_EMAIL_ADDRESS: &str = "sender_email_address";
     const PREFIX_SENDER_EMAIL_PASSWORD: &str = "sender_email_password";
     const PREFIX_SENDER_EMAIL_SMTP: &str = "sender_email_smtp";
@@ -26,6 +35,11 @@ impl Options {
     const PREFIX_ENABLE_EXPIRED_EMAILS: &str = "enable_expired_emails";
     const PREFIX_SERVER_IP: &str = "server_ip";
     const PREFIX_SERVER_PORT: &str = "server_port";
+    const PREFIX_OUTBOX_MAX_ATTEMPTS: &str = "outbox_max_attempts";
+    const PREFIX_OUTBOX_RETRY_SECONDS: &str = "outbox_retry_seconds";
+    const PREFIX_IDEMPOTENCY_WINDOW_HOURS: &str = "idempotency_window_hours";
+    const PREFIX_TRASH_RETENTION_DAYS: &str = "trash_retention_days";
+    const PREFIX_WEBHOOK_ALLOWED_HOSTS: &str = "webhook_allowed_hosts";
     
     const DEFAULT_SENDER_EMAIL_ADDRESS: &str = "username@domain.com";
     const DEFAULT_SENDER_EMAIL_PASSWORD: &str = "password123";
@@ -34,11 +48,15 @@ impl Options {
     const DEFAULT_ENABLE_EXPIRED_EMAILS: bool = false;
     const DEFAULT_SERVER_IP: &str = "127.0.0.1";
     const DEFAULT_SERVER_PORT: u16 = 8085;
+    const DEFAULT_OUTBOX_MAX_ATTEMPTS: u32 = 5;
+    const DEFAULT_OUTBOX_RETRY_SECONDS: u32 = 60;
+    const DEFAULT_IDEMPOTENCY_WINDOW_HOURS: u32 = 24;
+    const DEFAULT_TRASH_RETENTION_DAYS: u32 = 30;
     
     pub fn from_file_data(file_data: &str) -> Result<Options, ()> {
         let lines: Vec<_> = file_data.trim().lines().collect();
         
-        if lines.len() != 7 {
+        if lines.len() < Self::LINES_REQUIRED || lines.len() > Self::LINES_TOTAL {
             return Err(());
         }
         
@@ -49,6 +67,26 @@ impl Options {
         let enable_expired_emails = Self::parse_bool_argument(lines[4], Self::PREFIX_ENABLE_EXPIRED_EMAILS)?;
         let server_ip = Self::parse_ip_argument(lines[5], Self::PREFIX_SERVER_IP)?;
         let server_port = Self::parse_u16_argument(lines[6], Self::PREFIX_SERVER_PORT)?;
+        let outbox_max_attempts = match lines.get(7) {
+            Some(line) => Self::parse_u32_argument(line, Self::PREFIX_OUTBOX_MAX_ATTEMPTS)?,
+            None => Self::DEFAULT_OUTBOX_MAX_ATTEMPTS,
+        };
+        let outbox_retry_seconds = match lines.get(8) {
+            Some(line) => Self::parse_u32_argument(line, Self::PREFIX_OUTBOX_RETRY_SECONDS)?,
+            None => Self::DEFAULT_OUTBOX_RETRY_SECONDS,
+        };
+        let idempotency_window_hours = match lines.get(9) {
+            Some(line) => Self::parse_u32_argument(line, Self::PREFIX_IDEMPOTENCY_WINDOW_HOURS)?,
+            None => Self::DEFAULT_IDEMPOTENCY_WINDOW_HOURS,
+        };
+        let trash_retention_days = match lines.get(10) {
+            Some(line) => Self::parse_u32_argument(line, Self::PREFIX_TRASH_RETENTION_DAYS)?,
+            None => Self::DEFAULT_TRASH_RETENTION_DAYS,
+        };
+        let webhook_allowed_hosts = match lines.get(11) {
+            Some(line) => Self::parse_list_argument(line, Self::PREFIX_WEBHOOK_ALLOWED_HOSTS)?,
+            None => vec![],
+        };
         
         Ok(
             Options {
@@ -59,6 +97,11 @@ impl Options {
                 enable_expired_emails,
                 server_ip: server_ip.to_owned(),
                 server_port: server_port,
+                outbox_max_attempts,
+                outbox_retry_seconds,
+                idempotency_window_hours,
+                trash_retention_days,
+                webhook_allowed_hosts,
             }
         )
     }
@@ -91,6 +134,27 @@ impl Options {
         self.server_port
     }
     
+    pub fn outbox_max_attempts(&self) -> u32 {
+        self.outbox_max_attempts
+    }
+    
+    pub fn outbox_retry_seconds(&self) -> u32 {
+        self.outbox_retry_seconds
+    }
+    
+    pub fn idempotency_window_hours(&self) -> u32 {
+        self.idempotency_window_hours
+    }
+    
+    pub fn trash_retention_days(&self) -> u32 {
+        self.trash_retention_days
+    }
+    
+    /* hosts that webhooks may reach even though they resolve to a loopback, link-local or private address */
+    pub fn webhook_allowed_hosts(&self) -> &[String] {
+        &self.webhook_allowed_hosts
+    }
+    
     fn parse_string_argument(line: &str, prefix: &str) -> Result<String, ()> {
         let fields: Vec<_> = line.split('=').collect();
         
@@ -145,6 +209,37 @@ impl Options {
         Ok(port.unwrap())
     }
     
+    fn parse_u32_argument(line: &str, prefix: &str) -> Result<u32, ()> {
+        let fields: Vec<_> = line.split('=').collect();
+        
+        if !Self::field_check_prelim(&fields, prefix, false) {
+            return Err(());
+        }
+        
+        match fields[1].parse::<u32>() {
+            Ok(value) => Ok(value),
+            Err(_) => Err(()),
+        }
+    }
+    
+    /* comma separated, may be empty */
+    fn parse_list_argument(line: &str, prefix: &str) -> Result<Vec<String>, ()> {
+        let fields: Vec<_> = line.split('=').collect();
+        
+        if !Self::field_check_prelim(&fields, prefix, false) {
+            return Err(());
+        }
+        
+        Ok(
+            fields[1]
+                .split(',')
+                .map(|s| s.trim())
+                .filter(|s| !s.is_empty())
+                .map(String::from)
+                .collect()
+        )
+    }
+    
     fn validate_ip(ip: &str) -> bool {
         let fields: Vec<_> = ip.split(".").collect();
         
@@ -199,7 +294,7 @@ impl Options {
 
 impl fmt::Display for Options {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{}={}\n{}={}\n{}={}\n{}={}\n{}={}\n{}={}\n{}={}\n",
+        write!(f, "{}={}\n{}={}\n{}={}\n{}={}\n{}={}\n{}={}\n{}={}\n{}={}\n{}={}\n{}={}\n{}={}\n{}={}\n",
             Options::PREFIX_SENDER_EMAIL_ADDRESS, self.sender_email_address,
             Options::PREFIX_SENDER_EMAIL_PASSWORD, self.sender_email_password,
             Options::PREFIX_SENDER_EMAIL_SMTP, self.sender_email_smtp,
@@ -207,6 +302,11 @@ impl fmt::Display for Options {
             Options::PREFIX_ENABLE_EXPIRED_EMAILS, self.enable_expired_emails,
             Options::PREFIX_SERVER_IP, self.server_ip,
             Options::PREFIX_SERVER_PORT, self.server_port,
+            Options::PREFIX_OUTBOX_MAX_ATTEMPTS, self.outbox_max_attempts,
+            Options::PREFIX_OUTBOX_RETRY_SECONDS, self.outbox_retry_seconds,
+            Options::PREFIX_IDEMPOTENCY_WINDOW_HOURS, self.idempotency_window_hours,
+            Options::PREFIX_TRASH_RETENTION_DAYS, self.trash_retention_days,
+            Options::PREFIX_WEBHOOK_ALLOWED_HOSTS, self.webhook_allowed_hosts.join(","),
         )
     }
 }
@@ -221,6 +321,11 @@ impl Default for Options {
             enable_expired_emails: Self::DEFAULT_ENABLE_EXPIRED_EMAILS,
             server_ip: String::from(Self::DEFAULT_SERVER_IP),
             server_port: Self::DEFAULT_SERVER_PORT,
+            outbox_max_attempts: Self::DEFAULT_OUTBOX_MAX_ATTEMPTS,
+            outbox_retry_seconds: Self::DEFAULT_OUTBOX_RETRY_SECONDS,
+            idempotency_window_hours: Self::DEFAULT_IDEMPOTENCY_WINDOW_HOURS,
+            trash_retention_days: Self::DEFAULT_TRASH_RETENTION_DAYS,
+            webhook_allowed_hosts: vec![],
         }
     }
 }
@@ -238,6 +343,72 @@ mod tests {
         }
     }
     
+    #[test]
+    #[allow(clippy::assertions_on_constants)]
+    fn valid_outbox() {
+        match Options::from_file_data("sender_email_address=username@domain.com\nsender_email_password=password123\nsender_email_smtp=smtp.domain.com\n\
+                                       enable_reminder_emails=false\nenable_expired_emails=true\nserver_ip=127.0.0.1\nserver_port=8085\n\
+                                       outbox_max_attempts=3\noutbox_retry_seconds=30\n") {
+            Ok(options) => assert!(options.outbox_max_attempts() == 3 && options.outbox_retry_seconds() == 30),
+            Err(()) => assert!(false),
+        }
+    }
+    
+    #[test]
+    #[allow(clippy::assertions_on_constants)]
+    fn valid_outbox_defaults() {
+        match Options::from_file_data("sender_email_address=username@domain.com\nsender_email_password=password123\nsender_email_smtp=smtp.domain.com\n\
+                                       enable_reminder_emails=false\nenable_expired_emails=true\nserver_ip=127.0.0.1\nserver_port=8085\n") {
+            Ok(options) => assert!(options.outbox_max_attempts() == 5 && options.outbox_retry_seconds() == 60),
+            Err(()) => assert!(false),
+        }
+    }
+    
+    #[test]
+    #[allow(clippy::assertions_on_constants)]
+    fn valid_idempotency_window() {
+        match Options::from_file_data("sender_email_address=username@domain.com\nsender_email_password=password123\nsender_email_smtp=smtp.domain.com\n\
+                                       enable_reminder_emails=false\nenable_expired_emails=true\nserver_ip=127.0.0.1\nserver_port=8085\n\
+                                       outbox_max_attempts=3\noutbox_retry_seconds=30\nidempotency_window_hours=48\n") {
+            Ok(options) => assert!(options.idempotency_window_hours() == 48),
+            Err(()) => assert!(false),
+        }
+    }
+    
+    #[test]
+    #[allow(clippy::assertions_on_constants)]
+    fn valid_trash_retention() {
+        match Options::from_file_data("sender_email_address=username@domain.com\nsender_email_password=password123\nsender_email_smtp=smtp.domain.com\n\
+                                       enable_reminder_emails=false\nenable_expired_emails=true\nserver_ip=127.0.0.1\nserver_port=8085\n\
+                                       outbox_max_attempts=3\noutbox_retry_seconds=30\nidempotency_window_hours=48\ntrash_retention_days=7\n") {
+            Ok(options) => assert!(options.trash_retention_days() == 7),
+            Err(()) => assert!(false),
+        }
+    }
+    
+    #[test]
+    #[allow(clippy::assertions_on_constants)]
+    fn valid_webhook_allowed_hosts() {
+        match Options::from_file_data("sender_email_address=username@domain.com\nsender_email_password=password123\nsender_email_smtp=smtp.domain.com\n\
+                                       enable_reminder_emails=false\nenable_expired_emails=true\nserver_ip=127.0.0.1\nserver_port=8085\n\
+                                       outbox_max_attempts=3\noutbox_retry_seconds=30\nidempotency_window_hours=48\ntrash_retention_days=7\n\
+                                       webhook_allowed_hosts=ntfy.lan, 192.168.1.20\n") {
+            Ok(options) => assert!(options.webhook_allowed_hosts() == [String::from("ntfy.lan"), String::from("192.168.1.20")]),
+            Err(()) => assert!(false),
+        }
+    }
+    
+    #[test]
+    #[allow(clippy::assertions_on_constants)]
+    fn invalid_outbox_attempts() {
+        match Options::from_file_data("sender_email_address=username@domain.com\nsender_email_password=password123\nsender_email_smtp=smtp.domain.com\n\
+                                       enable_reminder_emails=false\nenable_expired_emails=true\nserver_ip=127.0.0.1\nserver_port=8085\n\
+                                       outbox_max_attempts=many\n") {
+            Ok(_) => assert!(false),
+            Err(()) => assert!(true),
+        }
+    }
+    
     #[test]
     fn invalid_empty() {
         match Options::from_file_data("") {
@@ -352,7 +523,9 @@ mod tests {
         assert_eq!(
             d.to_string(),
             String::from("sender_email_address=username@domain.com\nsender_email_password=password123\nsender_email_smtp=smtp.domain.com\n\
-                          enable_reminder_emails=false\nenable_expired_emails=false\nserver_ip=127.0.0.1\nserver_port=8085\n")
+                          enable_reminder_emails=false\nenable_expired_emails=false\nserver_ip=127.0.0.1\nserver_port=8085\n\
+                          outbox_max_attempts=5\noutbox_retry_seconds=60\nidempotency_window_hours=24\ntrash_retention_days=30\n\
+                          webhook_allowed_hosts=\n")
         );
     }
 }
\ No newline at end of file
@@ -0,0 +1,348 @@
+/*************************************************************************
+    "task_server"
+    import/import.rs
+    10/18/26
+    © Matthew Bentivegna
+*************************************************************************/
+use std::collections::HashMap;
+use chrono::{
+    NaiveDate,
+    Datelike,
+};
+use crate::date::date::Date;
+use crate::calendar::{
+    ical,
+    caldav::todo_entry_write,
+};
+use crate::models::{
+    entry::EntryWrite,
+    reminder::ReminderWrite,
+    import::{
+        FORMAT_ICS,
+        FORMAT_CSV,
+        FORMAT_TODOTXT,
+    },
+};
+
+/* the entry fields a CSV column can be mapped to ("due" is the whole date in one column) */
+pub const CSV_FIELDS: [&str; 10] = ["year", "month", "day", "due", "task", "reminder", "reminders", "completed", "tags", "list_id"];
+
+/* one item of an import file: its row (line for CSV/todo.txt, position for iCalendar), and the entry it describes */
+#[derive(Debug)]
+pub struct ImportItem {
+    pub row: usize,
+    pub write: Result<EntryWrite, String>,
+}
+
+/* an error for the file as a whole (unreadable, missing header, ...) is returned as Err */
+pub fn import_items(format: &str, body: &str, map: Option<&str>) -> Result<Vec<ImportItem>, String> {
+    match format {
+        FORMAT_ICS => {
+            let items = ical::parse_todos(body)?
+                .iter()
+                .enumerate()
+                .map(|(i, todo)| ImportItem {
+                    row: i + 1,
+                    write: todo_entry_write(todo),
+                })
+                .collect();
+
+            Ok(items)
+        },
+        FORMAT_CSV => csv_items(body, map),
+        FORMAT_TODOTXT => Ok(todotxt_items(body)),
+        _ => Err(String::from("Invalid format")),
+    }
+}
+
+/* YYYY-MM-DD, YYYYMMDD or MM/DD/YYYY */
+pub fn parse_due_date(value: &str) -> Result<Date, String> {
+    let date = ["%Y-%m-%d", "%Y%m%d", "%m/%d/%Y"]
+        .iter()
+        .find_map(|f| NaiveDate::parse_from_str(value.trim(), f).ok())
+        .ok_or_else(|| format!["Invalid date: {}", value])?;
+
+    Date::new(date.year(), date.month(), date.day()).map_err(|_| format!["Invalid date: {}", value])
+}
+
+/*----------------------------------------------------------------------*/
+/* CSV */
+/*----------------------------------------------------------------------*/
+fn csv_items(body: &str, map: Option<&str>) -> Result<Vec<ImportItem>, String> {
+    let mapping = csv_mapping(map)?;
+    let mut reader = csv::ReaderBuilder::new()
+        .flexible(true)
+        .trim(csv::Trim::All)
+        .from_reader(body.as_bytes());
+
+    let headers = reader.headers().map_err(|e| e.to_string())?.clone();
+    let fields: Vec<Option<String>> = headers
+        .iter()
+        .map(|h| {
+            let h = h.to_lowercase();
+
+            match mapping.get(&h) {
+                Some(field) => Some(field.clone()),
+                None => CSV_FIELDS.contains(&h.as_str()).then_some(h),
+            }
+        })
+        .collect();
+
+    if !fields.contains(&Some(String::from("task"))) {
+        return Err(String::from("No task column present"));
+    }
+
+    let items = reader
+        .records()
+        .enumerate()
+        .map(|(i, record)| match record {
+            Ok(record) => ImportItem {
+                row: record.position().map(|p| p.line() as usize).unwrap_or(i + 2),
+                write: csv_entry_write(&fields, &record),
+            },
+            Err(e) => ImportItem {
+                row: e.position().map(|p| p.line() as usize).unwrap_or(i + 2),
+                write: Err(e.to_string()),
+            },
+        })
+        .collect();
+
+    Ok(items)
+}
+
+/* "Title:task,Due Date:due" -> {"title": "task", "due date": "due"} */
+fn csv_mapping(map: Option<&str>) -> Result<HashMap<String, String>, String> {
+    let mut mapping = HashMap::new();
+
+    for pair in map.unwrap_or("").split(',').filter(|p| !p.trim().is_empty()) {
+        let (column, field) = pair.rsplit_once(':').ok_or_else(|| format!["Invalid mapping: {}", pair])?;
+        let field = field.trim().to_lowercase();
+
+        if !CSV_FIELDS.contains(&field.as_str()) {
+            return Err(format!["Invalid mapping field: {}", field]);
+        }
+
+        mapping.insert(column.trim().to_lowercase(), field);
+    }
+
+    Ok(mapping)
+}
+
+fn csv_entry_write(fields: &[Option<String>], record: &csv::StringRecord) -> Result<EntryWrite, String> {
+    let mut values: HashMap<&str, &str> = HashMap::new();
+
+    for (field, value) in fields.iter().zip(record.iter()) {
+        if let Some(field) = field {
+            if !value.is_empty() {
+                values.insert(field.as_str(), value);
+            }
+        }
+    }
+
+    let (year, month, day) = match values.get("due") {
+        Some(due) => {
+            let due = parse_due_date(due)?;
+            (due.year().to_string(), due.month().to_string(), due.day().to_string())
+        },
+        None => match (values.get("year"), values.get("month"), values.get("day")) {
+            (Some(y), Some(m), Some(d)) => (y.to_string(), m.to_string(), d.to_string()),
+            _ => { return Err(String::from("No due date present")); },
+        },
+    };
+
+    /* "reminders" is a list of offsets, each with an optional channel, e.g. "7;1:email;0" */
+    let reminders = values.get("reminders").map(|list| {
+        list
+            .split(';')
+            .map(|o| o.trim())
+            .filter(|o| !o.is_empty())
+            .map(|o| match o.split_once(':') {
+                Some((offset, channel)) => ReminderWrite {
+                    offset: String::from(offset.trim()),
+                    channel: Some(String::from(channel.trim())),
+                },
+                None => ReminderWrite {
+                    offset: String::from(o),
+                    channel: None,
+                },
+            })
+            .collect()
+    });
+
+    /* "tags" are separated by commas, as in "?tag=" */
+    let tags = values.get("tags").map(|list| {
+        list
+            .split(',')
+            .map(|t| t.trim())
+            .filter(|t| !t.is_empty())
+            .map(String::from)
+            .collect()
+    });
+
+    Ok(EntryWrite {
+        year,
+        month,
+        day,
+        task: values.get("task").map(|t| t.to_string()).unwrap_or_default(),
+        reminder: values.get("reminder").map(|r| r.to_string()),
+        reminders,
+        completed: values.get("completed").map(|c| completed_value(c)),
+        tags,
+        list_id: values.get("list_id").map(|l| l.to_string()),
+    })
+}
+
+/* spreadsheets write booleans in many ways; anything unrecognised is left for validation to reject */
+fn completed_value(value: &str) -> String {
+    match value.to_lowercase().as_str() {
+        "true" | "yes" | "y" | "x" | "1" => String::from("true"),
+        "false" | "no" | "n" | "0" => String::from("false"),
+        other => String::from(other),
+    }
+}
+
+/*----------------------------------------------------------------------*/
+/* todo.txt */
+/*----------------------------------------------------------------------*/
+fn todotxt_items(body: &str) -> Vec<ImportItem> {
+    body
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(i, line)| ImportItem {
+            row: i + 1,
+            write: todotxt_entry_write(line),
+        })
+        .collect()
+}
+
+/* "x 2024-06-02 (A) 2024-06-01 Pay rent +home due:2024-06-15" */
+fn todotxt_entry_write(line: &str) -> Result<EntryWrite, String> {
+    let is_date = |w: &&str| NaiveDate::parse_from_str(w, "%Y-%m-%d").is_ok();
+    let is_priority = |w: &&str| w.len() == 3 && w.starts_with('(') && w.ends_with(')') && w.as_bytes()[1].is_ascii_uppercase();
+
+    let mut words: Vec<&str> = line.split_whitespace().collect();
+    let mut completed = false;
+
+    /* completion marker and date, priority, creation date - each optional, in that order */
+    if words.first() == Some(&"x") {
+        completed = true;
+        words.remove(0);
+
+        if words.first().is_some_and(is_date) {
+            words.remove(0);
+        }
+    }
+    if words.first().is_some_and(is_priority) {
+        words.remove(0);
+    }
+    if words.first().is_some_and(is_date) {
+        words.remove(0);
+    }
+
+    let mut due = None;
+    let mut task = vec![];
+
+    for word in words {
+        match word.strip_prefix("due:") {
+            Some(value) => due = Some(parse_due_date(value)?),
+            None => task.push(word),
+        }
+    }
+
+    let due = due.ok_or("No due date present")?;
+
+    Ok(EntryWrite {
+        year: due.year().to_string(),
+        month: due.month().to_string(),
+        day: due.day().to_string(),
+        task: task.join(" "),
+        reminder: None,
+        reminders: None,
+        completed: Some(completed.to_string()),
+        tags: None,
+        list_id: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ok(item: &ImportItem) -> &EntryWrite {
+        item.write.as_ref().unwrap()
+    }
+
+    #[test]
+    fn due_dates() {
+        let date = Date::new(2030, 6, 15).unwrap();
+
+        assert!(
+            parse_due_date("2030-06-15") == Ok(date) &&
+            parse_due_date("20300615") == Ok(date) &&
+            parse_due_date("06/15/2030") == Ok(date) &&
+            parse_due_date("15.06.2030").is_err()
+        );
+    }
+
+    #[test]
+    fn ics_items() {
+        let body = "BEGIN:VCALENDAR\r\nBEGIN:VTODO\r\nSUMMARY:Pay rent\r\nDUE;VALUE=DATE:20300615\r\nEND:VTODO\r\nBEGIN:VTODO\r\nSUMMARY:Someday\r\nEND:VTODO\r\nEND:VCALENDAR\r\n";
+        let items = import_items(FORMAT_ICS, body, None).unwrap();
+
+        assert!(
+            items.len() == 2 &&
+            ok(&items[0]).task == "Pay rent" && ok(&items[0]).day == "15" &&
+            items[1].row == 2 && items[1].write.is_err()
+        );
+    }
+
+    #[test]
+    fn csv_default_columns() {
+        let body = "task,due,reminders,completed\n\"Pay rent, water\",2030-06-15,7;1,yes\nNo date,,,\n";
+        let items = import_items(FORMAT_CSV, body, None).unwrap();
+        let write = ok(&items[0]);
+
+        assert!(
+            write.task == "Pay rent, water" &&
+            write.month == "6" &&
+            write.completed == Some(String::from("true")) &&
+            write.reminders.as_ref().unwrap().len() == 2 &&
+            items[0].row == 2 &&
+            items[1].row == 3 && items[1].write.as_ref().unwrap_err() == "No due date present"
+        );
+    }
+
+    #[test]
+    fn csv_mapped_columns() {
+        let body = "Title,Year,Month,Day,Notes\nFile taxes,2030,4,15,ignored\n";
+        let items = import_items(FORMAT_CSV, body, Some("Title:task")).unwrap();
+
+        assert!(ok(&items[0]).task == "File taxes" && ok(&items[0]).year == "2030" && ok(&items[0]).reminder.is_none());
+    }
+
+    #[test]
+    fn csv_invalid() {
+        assert!(
+            import_items(FORMAT_CSV, "name,due\nx,2030-01-01\n", None).is_err() &&
+            import_items(FORMAT_CSV, "name,due\n", Some("name:title")).is_err()
+        );
+    }
+
+    #[test]
+    fn todotxt_lines() {
+        let body = "(A) 2030-06-01 Pay rent +home @bank due:2030-06-15\n\nx 2030-06-02 2030-06-01 Call mom due:06/20/2030\nNo due date here\n";
+        let items = import_items(FORMAT_TODOTXT, body, None).unwrap();
+
+        assert!(
+            items.len() == 3 &&
+            ok(&items[0]).task == "Pay rent +home @bank" &&
+            ok(&items[0]).completed == Some(String::from("false")) &&
+            ok(&items[1]).task == "Call mom" &&
+            ok(&items[1]).day == "20" &&
+            ok(&items[1]).completed == Some(String::from("true")) &&
+            items[1].row == 3 &&
+            items[2].write.is_err()
+        );
+    }
+}
\ No newline at end of file
@@ -0,0 +1,8 @@
+/*************************************************************************
+	"task_server"
+	import/mod.rs
+	10/18/26
+	© Matthew Bentivegna
+*************************************************************************/
+#[allow(clippy::module_inception)]
+pub mod import;
\ No newline at end of file
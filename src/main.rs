@@ -10,6 +10,12 @@ mod repository;
 mod date;
 mod timer;
 mod file;
+mod events;
+mod calendar;
+mod import;
+mod export;
+mod patch;
+mod openapi;
 mod constants;
 
 use std::sync::Arc;
@@ -25,6 +31,11 @@ use actix_web::{
 };
 use crate::constants::constants as program_constants;
 use crate::file::config::config_load;
+use crate::models::audit::{
+    AuditEventWrite,
+    AuditLogins,
+    AUDIT_CONFIG_LOADED,
+};
 
 #[derive(Serialize)]
 pub struct Response {
@@ -48,16 +59,34 @@ async fn not_found() -> Result<HttpResponse> {
 
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
-    let options = Arc::new(config_load(program_constants::CONFIG_FILENAME));
+    let (options, source) = config_load(program_constants::CONFIG_FILENAME);
+    let options = Arc::new(options);
     let options_local = Arc::clone(&options);
+    let options_data = web::Data::from(Arc::clone(&options));
     let database = web::Data::new(repository::database::Database::new());
+    let logins = web::Data::new(AuditLogins::default());
+
+    database.create_audit_event(AuditEventWrite::new(
+        AUDIT_CONFIG_LOADED,
+        None,
+        Some(program_constants::CONFIG_FILENAME),
+        None,
+        Some(source),
+        chrono::Utc::now().naive_utc(),
+    ));
 
-    crate::timer::timer::run(database.clone(), options);
+    crate::timer::timer::run(database.clone(), Arc::clone(&options));
+    crate::timer::outbox::run(database.clone(), Arc::clone(&options));
+    crate::timer::deliveries::run(database.clone(), options);
     
     HttpServer::new(move ||
         App::new()
             .app_data(database.clone())
+            .app_data(options_data.clone())
+            .app_data(logins.clone())
+            .configure(api::v2::config)
             .configure(api::api::config)
+            .configure(api::caldav::config)
             .service(healthcheck)
             .default_service(web::route().to(not_found))
             .wrap(actix_web::middleware::Logger::default())
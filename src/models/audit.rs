@@ -0,0 +1,239 @@
+/*************************************************************************
+    "task_server"
+    models/audit.rs
+    10/18/26
+    © Matthew Bentivegna
+*************************************************************************/
+use std::{
+    collections::HashMap,
+    sync::Mutex,
+};
+use chrono::{
+    Duration,
+    NaiveDate,
+    NaiveDateTime,
+};
+use serde::{
+    Deserialize,
+    Serialize
+};
+use diesel::{
+    Queryable,
+    Insertable,
+};
+use schemars::JsonSchema;
+
+pub const AUDIT_LOGIN: &str = "login";
+pub const AUDIT_LOGIN_FAILED: &str = "login.failed";
+pub const AUDIT_USER_CREATED: &str = "user.created";
+pub const AUDIT_USER_DELETED: &str = "user.deleted";
+pub const AUDIT_PASSWORD_CHANGED: &str = "user.password_changed";
+pub const AUDIT_ENTRIES_DELETED: &str = "entries.deleted";
+pub const AUDIT_CONFIG_LOADED: &str = "config.loaded";
+
+pub const AUDIT_ACTIONS: [&str; 7] = [
+    AUDIT_LOGIN,
+    AUDIT_LOGIN_FAILED,
+    AUDIT_USER_CREATED,
+    AUDIT_USER_DELETED,
+    AUDIT_PASSWORD_CHANGED,
+    AUDIT_ENTRIES_DELETED,
+    AUDIT_CONFIG_LOADED,
+];
+
+/* every request is authenticated, so requests from the same user and address within this long are one login */
+pub const AUDIT_LOGIN_WINDOW_MINUTES: i64 = 30;
+
+/* a username (if one could be read) and an address */
+type AuditFailedKey = (Option<String>, Option<String>);
+
+/* when each user last logged in from each address, so that a login is recorded once per window without asking the database on every
+   request; shared by the workers of this process */
+#[derive(Debug, Default)]
+pub struct AuditLogins {
+    last: Mutex<HashMap<(String, Option<String>), NaiveDateTime>>,
+    /* the failures of each username and address in the current window: when it started, and how many so far */
+    failed: Mutex<HashMap<AuditFailedKey, (NaiveDateTime, u64)>>,
+}
+
+impl AuditLogins {
+    /* true (and the login is remembered) when it is the first from the user and address within the window */
+    pub fn record(&self, username: &str, ip: Option<&str>, now: NaiveDateTime) -> bool {
+        let since = now - Duration::minutes(AUDIT_LOGIN_WINDOW_MINUTES);
+        let key = (String::from(username), ip.map(String::from));
+        let mut last = self.last.lock().unwrap();
+
+        if last.get(&key).is_some_and(|time| *time >= since) {
+            return false;
+        }
+
+        last.retain(|_, time| *time >= since);
+        last.insert(key, now);
+        true
+    }
+
+    /* the number of failures from the username and address in the window so far, when this one is to be recorded: the first, then the
+       10th, 100th, ... (so repeated guessing adds a few rows per window rather than one per request) */
+    pub fn record_failed(&self, username: Option<&str>, ip: Option<&str>, now: NaiveDateTime) -> Option<(u64, NaiveDateTime)> {
+        let since = now - Duration::minutes(AUDIT_LOGIN_WINDOW_MINUTES);
+        let key = (username.map(String::from), ip.map(String::from));
+        let mut failed = self.failed.lock().unwrap();
+
+        failed.retain(|_, (start, _)| *start >= since);
+
+        let (start, count) = failed.entry(key).or_insert((now, 0));
+        *count += 1;
+
+        match *count == 10_u64.pow(count.ilog10()) {
+            true => Some((*count, *start)),
+            false => None,
+        }
+    }
+}
+
+/* most events returned by a query; narrow it with "since" and "until", or use the export */
+pub const AUDIT_PAGE_SIZE: i64 = 500;
+
+/* one security-relevant action; rows are only ever added (the database refuses updates and deletes) */
+#[derive(Serialize, Debug, Clone, Queryable, JsonSchema)]
+#[diesel(table_name = crate::models::schema::audit_log)]
+pub struct AuditEvent {
+    pub id: i64,
+    pub action: String,
+    pub actor: Option<String>,
+    pub target: Option<String>,
+    pub ip: Option<String>,
+    pub detail: Option<String>,
+    #[schemars(with = "String")]
+    pub created: NaiveDateTime,
+}
+
+/* the id is assigned by the database */
+#[derive(Debug, Clone, Insertable)]
+#[diesel(table_name = crate::models::schema::audit_log)]
+pub struct AuditEventWrite {
+    pub action: String,
+    pub actor: Option<String>,
+    pub target: Option<String>,
+    pub ip: Option<String>,
+    pub detail: Option<String>,
+    pub created: NaiveDateTime,
+}
+
+impl AuditEventWrite {
+    pub fn new(action: &str, actor: Option<&str>, target: Option<&str>, ip: Option<&str>, detail: Option<&str>, now: NaiveDateTime) -> Self {
+        Self {
+            action: String::from(action),
+            actor: actor.map(String::from),
+            target: target.map(String::from),
+            ip: ip.map(String::from),
+            detail: detail.map(String::from),
+            created: now,
+        }
+    }
+}
+
+#[derive(Debug, Default, Deserialize, JsonSchema)]
+pub struct AuditParams {
+    pub action: Option<String>,
+    pub actor: Option<String>,
+    pub ip: Option<String>,
+    pub since: Option<String>,
+    pub until: Option<String>,
+}
+
+/* the checked form of AuditParams */
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AuditFilter {
+    pub action: Option<String>,
+    pub actor: Option<String>,
+    pub ip: Option<String>,
+    pub since: Option<NaiveDateTime>,
+    pub until: Option<NaiveDateTime>,
+}
+
+/* "2026-10-18T09:30:00" (UTC), or a date for midnight at its start */
+pub fn audit_time_parse(value: &str) -> Result<NaiveDateTime, String> {
+    NaiveDateTime::parse_from_str(value, "%Y-%m-%dT%H:%M:%S")
+        .or_else(|_| NaiveDate::parse_from_str(value, "%Y-%m-%d").map(|d| d.and_hms_opt(0, 0, 0).unwrap_or_default()))
+        .map_err(|_| format!["Invalid time \"{}\"", value])
+}
+
+pub fn audit_filter(params: &AuditParams) -> Result<AuditFilter, String> {
+    if let Some(action) = &params.action {
+        if !AUDIT_ACTIONS.contains(&action.as_str()) {
+            return Err(format!["Invalid action \"{}\"", action]);
+        }
+    }
+
+    Ok(AuditFilter {
+        action: params.action.clone(),
+        actor: params.actor.clone(),
+        ip: params.ip.clone(),
+        since: params.since.as_deref().map(audit_time_parse).transpose()?,
+        until: params.until.as_deref().map(audit_time_parse).transpose()?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn time_parse() {
+        assert!(
+            audit_time_parse("2026-10-18T09:30:00") == Ok(NaiveDateTime::parse_from_str("2026-10-18 09:30:00", "%Y-%m-%d %H:%M:%S").unwrap()) &&
+            audit_time_parse("2026-10-18") == Ok(NaiveDateTime::parse_from_str("2026-10-18 00:00:00", "%Y-%m-%d %H:%M:%S").unwrap()) &&
+            audit_time_parse("yesterday").is_err()
+        );
+    }
+
+    #[test]
+    fn filter() {
+        let params = AuditParams {
+            action: Some(String::from(AUDIT_LOGIN_FAILED)),
+            since: Some(String::from("2026-10-01")),
+            ..Default::default()
+        };
+        let filter = audit_filter(&params).unwrap();
+
+        assert!(filter.action.as_deref() == Some("login.failed") && filter.since.is_some() && filter.until.is_none());
+    }
+
+    #[test]
+    fn filter_invalid() {
+        let action = AuditParams { action: Some(String::from("entry.created")), ..Default::default() };
+        let until = AuditParams { until: Some(String::from("18/10/2026")), ..Default::default() };
+
+        assert!(audit_filter(&action).is_err() && audit_filter(&until).is_err());
+    }
+    #[test]
+    fn logins_recorded_once_per_window() {
+        let logins = AuditLogins::default();
+        let now = audit_time_parse("2026-10-18T09:30:00").unwrap();
+        let later = now + Duration::minutes(AUDIT_LOGIN_WINDOW_MINUTES + 1);
+
+        assert!(
+            logins.record("bob", Some("127.0.0.1"), now) &&
+            !logins.record("bob", Some("127.0.0.1"), now + Duration::minutes(5)) &&
+            logins.record("bob", Some("10.0.0.2"), now) &&
+            logins.record("bob", None, now) &&
+            logins.record("bob", Some("127.0.0.1"), later)
+        );
+    }
+
+    #[test]
+    fn failed_logins_aggregated() {
+        let logins = AuditLogins::default();
+        let now = audit_time_parse("2026-10-18T09:30:00").unwrap();
+        let later = now + Duration::minutes(AUDIT_LOGIN_WINDOW_MINUTES + 1);
+        let recorded: Vec<u64> = (0..150)
+            .filter_map(|_| logins.record_failed(Some("bob"), Some("10.0.0.9"), now))
+            .map(|(count, _)| count)
+            .collect();
+
+        assert_eq!(recorded, vec![1, 10, 100]);
+        assert_eq!(logins.record_failed(None, Some("10.0.0.9"), now), Some((1, now)));
+        assert_eq!(logins.record_failed(Some("bob"), Some("10.0.0.9"), later), Some((1, later)));
+    }
+}
\ No newline at end of file
@@ -0,0 +1,211 @@
+/*************************************************************************
+    "task_server"
+    models/batch.rs
+    10/18/26
+    © Matthew Bentivegna
+*************************************************************************/
+use serde::{
+    Deserialize,
+    Serialize
+};
+use schemars::JsonSchema;
+use crate::models::{
+    entry::{
+        Entry,
+        EntryRead,
+        EntryWrite,
+        EntryPatch,
+    },
+    reminder::ReminderWrite,
+};
+
+pub const OP_CREATE: &str = "create";
+pub const OP_PATCH: &str = "patch";
+pub const OP_DELETE: &str = "delete";
+
+/* all-or-nothing (the default), or apply whatever is valid */
+pub const MODE_ATOMIC: &str = "atomic";
+pub const MODE_BEST_EFFORT: &str = "best_effort";
+
+pub const ITEM_APPLIED: &str = "applied";
+pub const ITEM_FAILED: &str = "failed";
+pub const ITEM_SKIPPED: &str = "skipped";
+pub const ITEM_ROLLED_BACK: &str = "rolled_back";
+
+pub const BATCH_MAX_OPERATIONS: usize = 1000;
+
+#[derive(Debug, Default, Deserialize, JsonSchema)]
+pub struct BatchParams {
+    pub mode: Option<String>,
+}
+
+impl BatchParams {
+    pub fn mode(&self) -> Option<&'static str> {
+        match self.mode.as_deref() {
+            None | Some(MODE_ATOMIC) => Some(MODE_ATOMIC),
+            Some(MODE_BEST_EFFORT) => Some(MODE_BEST_EFFORT),
+            Some(_) => None,
+        }
+    }
+}
+
+/* "entry" is an EntryWrite for create and an EntryPatch for patch; it is read per operation, so one malformed item does not reject the request */
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct BatchOperation {
+    pub op: String,
+    pub id: Option<String>,
+    pub entry: Option<serde_json::Value>,
+    /* the entry's ETag, as for If-Match: a patch or delete of any other version fails */
+    #[serde(default)]
+    pub if_match: Option<String>,
+}
+
+#[derive(Debug)]
+pub enum BatchAction {
+    Create(EntryWrite),
+    Patch(String, EntryPatch),
+    Delete(String),
+}
+
+/* a checked operation, ready to be written */
+#[derive(Debug)]
+pub enum BatchChange {
+    /* the tags to set, if the operation sets them */
+    Create(Entry, Vec<ReminderWrite>, Option<Vec<String>>),
+    Update(Entry, Vec<ReminderWrite>, Option<Vec<String>>),
+    /* the entry as it was checked */
+    Delete(Entry),
+}
+
+#[derive(Debug, Serialize)]
+pub struct BatchItem {
+    pub index: usize,
+    pub op: String,
+    pub status: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub entry: Option<EntryRead>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl BatchItem {
+    pub fn new(index: usize, op: &str, id: Option<String>) -> Self {
+        Self {
+            index,
+            op: String::from(op),
+            status: String::from(ITEM_SKIPPED),
+            id,
+            entry: None,
+            error: None,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct BatchReport {
+    pub mode: String,
+    pub applied: usize,
+    pub failed: usize,
+    pub skipped: usize,
+    pub rolled_back: usize,
+    pub items: Vec<BatchItem>,
+}
+
+impl BatchReport {
+    pub fn new(mode: &str, items: Vec<BatchItem>) -> Self {
+        let count = |status: &str| items.iter().filter(|i| i.status == status).count();
+
+        Self {
+            mode: String::from(mode),
+            applied: count(ITEM_APPLIED),
+            failed: count(ITEM_FAILED),
+            skipped: count(ITEM_SKIPPED),
+            rolled_back: count(ITEM_ROLLED_BACK),
+            items,
+        }
+    }
+}
+
+pub fn batch_action(operation: &BatchOperation) -> Result<BatchAction, String> {
+    let id = || operation.id.clone().filter(|id| !id.is_empty()).ok_or("No id present");
+    let entry = || operation.entry.clone().ok_or("No entry present");
+
+    match operation.op.as_str() {
+        OP_CREATE => {
+            let write = serde_json::from_value(entry()?).map_err(|e| format!["Invalid entry: {}", e])?;
+            Ok(BatchAction::Create(write))
+        },
+        OP_PATCH => {
+            let patch = serde_json::from_value(entry()?).map_err(|e| format!["Invalid entry: {}", e])?;
+            Ok(BatchAction::Patch(id()?, patch))
+        },
+        OP_DELETE => Ok(BatchAction::Delete(id()?)),
+        _ => Err(format!["Invalid op: {}", operation.op]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn operation(json: &str) -> BatchOperation {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn actions() {
+        let create = batch_action(&operation(r#"{"op": "create", "entry": {"year": "2030", "month": "6", "day": "15", "task": "Pay rent"}}"#));
+        let patch = batch_action(&operation(r#"{"op": "patch", "id": "entry-1", "entry": {"completed": "true"}}"#));
+        let delete = batch_action(&operation(r#"{"op": "delete", "id": "entry-1"}"#));
+
+        assert!(matches!(create, Ok(BatchAction::Create(w)) if w.task == "Pay rent"));
+        assert!(matches!(patch, Ok(BatchAction::Patch(id, p)) if id == "entry-1" && p.completed.as_deref() == Some("true")));
+        assert!(matches!(delete, Ok(BatchAction::Delete(id)) if id == "entry-1"));
+    }
+
+    #[test]
+    fn actions_invalid() {
+        assert!(
+            batch_action(&operation(r#"{"op": "create", "entry": {"task": "No date"}}"#)).unwrap_err().starts_with("Invalid entry") &&
+            batch_action(&operation(r#"{"op": "create"}"#)).unwrap_err() == "No entry present" &&
+            batch_action(&operation(r#"{"op": "delete", "id": ""}"#)).unwrap_err() == "No id present" &&
+            batch_action(&operation(r#"{"op": "move", "id": "entry-1"}"#)).unwrap_err() == "Invalid op: move"
+        );
+    }
+
+    #[test]
+    fn mode_param() {
+        let params = |mode: Option<&str>| BatchParams { mode: mode.map(String::from) };
+
+        assert!(
+            params(None).mode() == Some(MODE_ATOMIC) &&
+            params(Some("best_effort")).mode() == Some(MODE_BEST_EFFORT) &&
+            params(Some("all")).mode().is_none()
+        );
+    }
+
+    #[test]
+    fn report_counts() {
+        let mut items: Vec<BatchItem> = (0..4).map(|i| BatchItem::new(i, OP_DELETE, None)).collect();
+        items[0].status = String::from(ITEM_APPLIED);
+        items[1].status = String::from(ITEM_FAILED);
+
+        let report = BatchReport::new(MODE_BEST_EFFORT, items);
+
+        assert!(report.applied == 1 && report.failed == 1 && report.skipped == 2 && report.rolled_back == 0);
+    }
+
+    #[test]
+    fn report_counts_rolled_back() {
+        let mut items: Vec<BatchItem> = (0..4).map(|i| BatchItem::new(i, OP_DELETE, None)).collect();
+        items[0].status = String::from(ITEM_ROLLED_BACK);
+        items[1].status = String::from(ITEM_ROLLED_BACK);
+        items[2].status = String::from(ITEM_FAILED);
+
+        let report = BatchReport::new(MODE_ATOMIC, items);
+
+        assert!(report.applied == 0 && report.failed == 1 && report.skipped == 1 && report.rolled_back == 2);
+    }
+}
\ No newline at end of file
@@ -0,0 +1,195 @@
+/*************************************************************************
+    "task_server"
+    models/channel.rs
+    10/18/26
+    © Matthew Bentivegna
+*************************************************************************/
+use std::collections::HashMap;
+use serde::{
+    Deserialize,
+    Serialize
+};
+use schemars::JsonSchema;
+use diesel::{
+    Queryable,
+    Insertable,
+    AsChangeset
+};
+use crate::models::problem::{
+    FieldError,
+    field_errors_result,
+    CODE_INVALID_CHANNEL,
+    CODE_INVALID_ENABLED,
+    CODE_INVALID_URL,
+    CODE_URL_MISSING,
+    CODE_INVALID_HEADER_NAME,
+};
+
+pub const CHANNEL_EMAIL: &str = "email";
+pub const CHANNEL_WEBHOOK: &str = "webhook";
+
+/* a reminder on the "default" channel goes out on every channel the user has enabled */
+pub const CHANNEL_DEFAULT: &str = "default";
+
+pub const DELIVERY_CHANNELS: [&str; 2] = [CHANNEL_EMAIL, CHANNEL_WEBHOOK];
+
+#[derive(Serialize, Deserialize, Debug, Clone, Queryable, Insertable, AsChangeset)]
+#[diesel(table_name = crate::models::schema::user_channels)]
+#[diesel(treat_none_as_null = true)]
+pub struct UserChannel {
+    pub id: String,
+    pub user_id: String,
+    pub channel: String,
+    pub enabled: String,
+    pub url: Option<String>,
+    pub headers: Option<String>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct UserChannelWrite {
+    pub enabled: Option<String>,
+    pub url: Option<String>,
+    pub headers: Option<HashMap<String, String>>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct UserChannelRead {
+    pub channel: String,
+    pub enabled: String,
+    pub url: Option<String>,
+    pub headers: HashMap<String, String>,
+}
+
+impl UserChannel {
+    pub fn headers(&self) -> HashMap<String, String> {
+        match &self.headers {
+            Some(headers) => serde_json::from_str(headers).unwrap_or_default(),
+            None => HashMap::new(),
+        }
+    }
+}
+
+impl UserChannelRead {
+    pub fn new(channel: &UserChannel) -> Self {
+        Self {
+            channel: channel.channel.clone(),
+            enabled: channel.enabled.clone(),
+            url: channel.url.clone(),
+            headers: channel.headers(),
+        }
+    }
+}
+
+pub fn user_channel_from_write(src: &UserChannelWrite, channel: &str, user_id: &str) -> UserChannel {
+    UserChannel {
+        id: String::new(),
+        user_id: String::from(user_id),
+        channel: String::from(channel),
+        enabled: src.enabled.clone().unwrap_or(true.to_string()),
+        url: src.url.clone(),
+        headers: src.headers.as_ref().map(|h| serde_json::to_string(h).unwrap()),
+    }
+}
+
+pub fn user_channel_validate(channel: &UserChannel) -> Result<(), Vec<FieldError>> {
+    let mut errors = vec![];
+
+    if !DELIVERY_CHANNELS.contains(&channel.channel.as_str()) {
+        errors.push(FieldError::new("/channel", CODE_INVALID_CHANNEL, "Invalid channel"));
+    }
+
+    if channel.enabled != "true" && channel.enabled != "false" {
+        errors.push(FieldError::new("/enabled", CODE_INVALID_ENABLED, "Invalid enabled value"));
+    }
+
+    if channel.channel == CHANNEL_WEBHOOK {
+        match &channel.url {
+            Some(url) => {
+                if !url.starts_with("http://") && !url.starts_with("https://") {
+                    errors.push(FieldError::new("/url", CODE_INVALID_URL, "Invalid webhook url"));
+                }
+            },
+            None => { errors.push(FieldError::new("/url", CODE_URL_MISSING, "Webhook url required")); },
+        }
+    }
+
+    for name in channel.headers().keys() {
+        if name.is_empty() || name.contains(|c: char| c == ':' || c.is_whitespace() || c.is_control()) {
+            errors.push(FieldError::new(&format!["/headers/{}", name.replace('~', "~0").replace('/', "~1")], CODE_INVALID_HEADER_NAME, "Invalid header name"));
+        }
+    }
+
+    field_errors_result(errors)
+}
+
+/* the channels a user's "default" notifications go out on; email when nothing is configured */
+pub fn user_channels_enabled(channels: &[UserChannel]) -> Vec<String> {
+    if channels.is_empty() {
+        return vec![String::from(CHANNEL_EMAIL)];
+    }
+
+    channels
+        .iter()
+        .filter(|c| c.enabled == "true")
+        .map(|c| c.channel.clone())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn channel(name: &str, enabled: &str, url: Option<&str>) -> UserChannel {
+        UserChannel {
+            id: String::new(),
+            user_id: String::from("user-1"),
+            channel: String::from(name),
+            enabled: String::from(enabled),
+            url: url.map(String::from),
+            headers: None,
+        }
+    }
+
+    #[test]
+    fn enabled_defaults_to_email() {
+        assert_eq!(user_channels_enabled(&[]), vec![String::from(CHANNEL_EMAIL)]);
+    }
+
+    #[test]
+    fn enabled_preferences() {
+        let channels = vec![
+            channel(CHANNEL_EMAIL, "false", None),
+            channel(CHANNEL_WEBHOOK, "true", Some("https://push.example.com/topic")),
+        ];
+
+        assert_eq!(user_channels_enabled(&channels), vec![String::from(CHANNEL_WEBHOOK)]);
+    }
+
+    #[test]
+    fn validate_webhook_needs_url() {
+        assert!(
+            user_channel_validate(&channel(CHANNEL_WEBHOOK, "true", None)).is_err() &&
+            user_channel_validate(&channel(CHANNEL_WEBHOOK, "true", Some("ftp://host"))).is_err() &&
+            user_channel_validate(&channel(CHANNEL_WEBHOOK, "true", Some("http://localhost:9000/hook"))).is_ok()
+        );
+    }
+
+    #[test]
+    fn validate_invalid_channel() {
+        assert!(user_channel_validate(&channel("pigeon", "true", None)).is_err());
+    }
+
+    #[test]
+    fn validate_header_names() {
+        let mut headers = HashMap::new();
+        headers.insert(String::from("Bad Header"), String::from("x"));
+
+        let write = UserChannelWrite {
+            enabled: None,
+            url: Some(String::from("https://hooks.example.com")),
+            headers: Some(headers),
+        };
+
+        assert!(user_channel_validate(&user_channel_from_write(&write, CHANNEL_WEBHOOK, "user-1")).is_err());
+    }
+}
\ No newline at end of file
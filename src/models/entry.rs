@@ -9,16 +9,40 @@ use serde::{
     Deserialize,
     Serialize
 };
+use schemars::JsonSchema;
 use diesel::{
     Queryable,
     Insertable,
     AsChangeset
 };
 use crate::date::date::Date;
+use crate::models::{
+    reminder::{
+        Reminder,
+        ReminderWrite,
+        reminders_validate,
+        reminders_from_legacy,
+        reminder_legacy_from_list,
+    },
+    tag::tags_validate,
+    list::LIST_INBOX,
+    problem::{
+        FieldError,
+        field_errors_result,
+        CODE_INVALID_YEAR,
+        CODE_INVALID_MONTH,
+        CODE_INVALID_DAY,
+        CODE_INVALID_DATE,
+        CODE_DATE_PASSED,
+        CODE_TASK_MISSING,
+        CODE_INVALID_COMPLETED,
+    },
+};
 
-#[derive(Serialize, Deserialize, Debug, Clone, Queryable, Insertable, AsChangeset)]
+#[derive(Serialize, Deserialize, Debug, Clone, Queryable, Insertable, AsChangeset, JsonSchema)]
 #[diesel(belongs_to(User))]
 #[diesel(table_name = crate::models::schema::entries)]
+#[diesel(treat_none_as_null = true)]
 pub struct Entry {
     #[serde(default)]
     pub id: String,
@@ -29,30 +53,101 @@ pub struct Entry {
     pub task: String,
     pub reminder: Option<String>,
     pub expired: String,
+    #[schemars(with = "String")]
     pub created: chrono::NaiveDateTime,
+    #[schemars(with = "String")]
     pub updated: chrono::NaiveDateTime,
     pub user_id: String,
+    pub completed: String,
+    /* set while the entry is in the trash */
+    #[serde(default)]
+    #[schemars(with = "Option<String>")]
+    pub deleted_at: Option<chrono::NaiveDateTime>,
+    /* None for the inbox */
+    #[serde(default)]
+    pub list_id: Option<String>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
 pub struct EntryWrite {
     pub year: String,
     pub month: String,
     pub day: String,
     pub task: String,
     pub reminder: Option<String>,
+    pub reminders: Option<Vec<ReminderWrite>>,
+    pub completed: Option<String>,
+    /* left as they are when omitted */
+    pub tags: Option<Vec<String>>,
+    /* "inbox" takes the entry out of its list; left as it is when omitted */
+    pub list_id: Option<String>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
 pub struct EntryPatch {
     pub year: Option<String>,
     pub month: Option<String>,
     pub day: Option<String>,
     pub task: Option<String>,
     pub reminder: Option<String>,
+    pub reminders: Option<Vec<ReminderWrite>>,
+    pub completed: Option<String>,
+    pub tags: Option<Vec<String>>,
+    pub list_id: Option<String>,
+}
+
+/* the fields a merge-patch or json-patch document applies to */
+#[derive(Debug, Serialize, Deserialize)]
+pub struct EntryDocument {
+    pub year: String,
+    pub month: String,
+    pub day: String,
+    pub task: String,
+    pub reminder: Option<String>,
+    #[serde(default)]
+    pub reminders: Vec<ReminderWrite>,
+    pub completed: String,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub list_id: Option<String>,
 }
 
-#[derive(Debug, Deserialize)]
+impl EntryDocument {
+    pub fn new(entry: &Entry, reminders: Vec<ReminderWrite>, tags: Vec<String>) -> Self {
+        Self {
+            year: entry.year.clone(),
+            month: entry.month.clone(),
+            day: entry.day.clone(),
+            task: entry.task.clone(),
+            reminder: entry.reminder.clone(),
+            reminders,
+            completed: entry.completed.clone(),
+            tags,
+            list_id: entry.list_id.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct EntryRead {
+    #[serde(flatten)]
+    pub entry: Entry,
+    pub reminders: Vec<Reminder>,
+    pub tags: Vec<String>,
+}
+
+impl EntryRead {
+    pub fn new(entry: Entry, reminders: Vec<Reminder>, tags: Vec<String>) -> Self {
+        Self {
+            entry,
+            reminders,
+            tags,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize, JsonSchema)]
 pub struct EntryParams {
     pub username: Option<String>,
     pub year: Option<String>,
@@ -60,45 +155,122 @@ pub struct EntryParams {
     pub day: Option<String>,
     pub reminder: Option<String>,
     pub expired: Option<String>,
+    pub completed: Option<String>,
+    /* comma-separated: entries with every tag listed, with any of them, or with none of them */
+    pub tag: Option<String>,
+    pub tag_any: Option<String>,
+    pub tag_none: Option<String>,
+    /* a list's id, or "inbox" for the entries in no list */
+    pub list_id: Option<String>,
 }
 
-pub fn entry_validate(entry: &Entry) -> Result<(), String> {
-    /* validate date */
-    let year = convert_str_to_t::<i32>(&entry.year, "Invalid year")?;
-    let month = convert_str_to_t::<u32>(&entry.month, "Invalid month")?;
-    let day = convert_str_to_t::<u32>(&entry.day, "Invalid day")?;
-    
-    let test_date = match Date::new(year, month, day) {
-        Ok(td) => td,
-        Err(_) => { return Err("Invalid date".to_string()); },
+/* every failed check is reported; the legacy "reminder" always mirrors the reminder list, so only the list is checked. An edit ("orig"
+   is the entry before it) that keeps the due date (and the reminder offsets) is not re-checked against today, so overdue entries can
+   still be ticked off */
+#[allow(clippy::bool_comparison, clippy::len_zero)]
+pub fn entry_validate_edit(entry: &Entry, reminders: &[ReminderWrite], orig: Option<(&Entry, &[ReminderWrite])>) -> Result<(), Vec<FieldError>> {
+    let mut errors = vec![];
+    let offsets = |list: &[ReminderWrite]| {
+        let mut offsets: Vec<String> = list.iter().map(|r| r.offset.clone()).collect();
+        offsets.sort();
+        offsets
     };
+    let same_date = orig.is_some_and(|(orig, _)| entry_due_date_same(orig, entry));
+    let same_reminders = same_date && orig.is_some_and(|(_, orig_reminders)| offsets(orig_reminders) == offsets(reminders));
+    
+    /* validate date */
+    let test_date = entry_due_date_validate(entry, &mut errors);
     
-    if Date::is_today_or_later(&test_date) == false {
-        return Err("Date has passed".to_string());
+    if let Some(date) = &test_date {
+        if !same_date && Date::is_today_or_later(date) == false {
+            errors.push(FieldError::new("/day", CODE_DATE_PASSED, "Date has passed"));
+        }
     }
     
     /* validate task */
     if entry.task.len() < 1 {
-        return Err("No task present".to_string());
+        errors.push(FieldError::new("/task", CODE_TASK_MISSING, "No task present"));
     }
     
-    /* validate reminder */
-    if let Some(reminder) = &entry.reminder
-    {
-        let reminder = match reminder.parse::<i64>() {
-            Ok(r) => r,
-            Err(_) => { return Err("Invalid reminder".to_string()); },
-        };
-        
-        let today = Date::today();
-        let diff = Date::difference(&test_date, &today);
-        
-        if reminder > 0 && reminder > diff {
-            return Err("Reminder too great for due date".to_string());
-        }
+    /* validate completed */
+    if entry.completed != "true" && entry.completed != "false" {
+        errors.push(FieldError::new("/completed", CODE_INVALID_COMPLETED, "Invalid completed"));
     }
     
-    Ok(())
+    /* validate reminders (kept ones only for form) */
+    if let Err(e) = reminders_validate(reminders, if same_reminders { None } else { test_date.as_ref() }) {
+        errors.extend(e);
+    }
+    
+    field_errors_result(errors)
+}
+
+/* entry_validate_edit, with the tags a write sets checked as well */
+pub fn entry_validate_with_tags(entry: &Entry, reminders: &[ReminderWrite], tags: &[String], orig: Option<(&Entry, &[ReminderWrite])>) -> Result<(), Vec<FieldError>> {
+    let mut errors = entry_validate_edit(entry, reminders, orig).err().unwrap_or_default();
+    
+    errors.extend(tags_validate(tags).err().unwrap_or_default());
+    field_errors_result(errors)
+}
+
+/* year, month and day are each checked on their own (the day against the longest month), then the date they make together */
+fn entry_due_date_validate(entry: &Entry, errors: &mut Vec<FieldError>) -> Option<Date> {
+    let year = entry.year.parse::<i32>().ok()
+        .filter(|year| Date::new(*year, 1, 1).is_ok())
+        .ok_or_else(|| FieldError::new("/year", CODE_INVALID_YEAR, "Invalid year"));
+    let month = entry.month.parse::<u32>().ok()
+        .filter(|month| Date::new(2000, *month, 1).is_ok())
+        .ok_or_else(|| FieldError::new("/month", CODE_INVALID_MONTH, "Invalid month"));
+    let day = entry.day.parse::<u32>().ok()
+        .filter(|day| Date::new(2000, 1, *day).is_ok())
+        .ok_or_else(|| FieldError::new("/day", CODE_INVALID_DAY, "Invalid day"));
+    
+    match (year, month, day) {
+        (Ok(year), Ok(month), Ok(day)) => match Date::new(year, month, day) {
+            Ok(date) => Some(date),
+            Err(_) => {
+                errors.push(FieldError::new("/day", CODE_INVALID_DATE, "Invalid date"));
+                None
+            },
+        },
+        (year, month, day) => {
+            errors.extend([year.err(), month.err(), day.err()].into_iter().flatten());
+            None
+        },
+    }
+}
+
+/* "2030", "06", "05" is the same due date as "2030", "6", "5" */
+pub fn entry_due_date_same(a: &Entry, b: &Entry) -> bool {
+    match (entry_due_date(a), entry_due_date(b)) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => a.year == b.year && a.month == b.month && a.day == b.day,
+    }
+}
+
+/* a date part as it is stored and queried: the number without padding (anything else is left for validation to report) */
+pub fn date_part_normalize(part: &str) -> String {
+    match part.parse::<u32>() {
+        Ok(n) => n.to_string(),
+        Err(_) => String::from(part),
+    }
+}
+
+pub fn entry_date_normalize(entry: &mut Entry) {
+    entry.year = date_part_normalize(&entry.year);
+    entry.month = date_part_normalize(&entry.month);
+    entry.day = date_part_normalize(&entry.day);
+}
+
+pub fn entry_due_date(entry: &Entry) -> Result<Date, String> {
+    let year = convert_str_to_t::<i32>(&entry.year, "Invalid year")?;
+    let month = convert_str_to_t::<u32>(&entry.month, "Invalid month")?;
+    let day = convert_str_to_t::<u32>(&entry.day, "Invalid day")?;
+    
+    match Date::new(year, month, day) {
+        Ok(date) => Ok(date),
+        Err(_) => Err("Invalid date".to_string()),
+    }
 }
 
 pub fn entry_from_entry_write(src: &EntryWrite, username: &str) -> Entry {
@@ -109,11 +281,14 @@ pub fn entry_from_entry_write(src: &EntryWrite, username: &str) -> Entry {
         month: src.month.clone(),
         day: src.day.clone(),
         task: src.task.clone(),
-        reminder: src.reminder.clone(),
+        reminder: entry_write_reminder(src),
         expired: false.to_string(),
         created: NaiveDateTime::MIN,
         updated: NaiveDateTime::MIN,
         user_id: String::new(),
+        completed: src.completed.clone().unwrap_or(false.to_string()),
+        deleted_at: None,
+        list_id: src.list_id.as_deref().and_then(list_id_from_write),
     }
 }
 
@@ -125,11 +300,105 @@ pub fn entry_from_entry_write_edit(write: &EntryWrite, orig: &Entry) -> Entry {
         month: write.month.clone(),
         day: write.day.clone(),
         task: write.task.clone(),
-        reminder: write.reminder.clone(),
+        reminder: entry_write_reminder(write),
         expired: orig.expired.clone(),
         created: orig.created.clone(),
         updated: orig.updated.clone(),
         user_id: orig.user_id.clone(),
+        completed: write.completed.clone().unwrap_or(orig.completed.clone()),
+        deleted_at: orig.deleted_at,
+        list_id: match &write.list_id {
+            Some(list_id) => list_id_from_write(list_id),
+            None => orig.list_id.clone(),
+        },
+    }
+}
+
+/* applies the fields present in a patch; the reminder list is returned when the patch sets one */
+pub fn entry_patch_apply(entry: &mut Entry, patch: &EntryPatch) -> Option<Vec<ReminderWrite>> {
+    if let Some(year) = &patch.year {
+        entry.year = year.clone();
+    }
+    if let Some(month) = &patch.month {
+        entry.month = month.clone();
+    }
+    if let Some(day) = &patch.day {
+        entry.day = day.clone();
+    }
+    if let Some(task) = &patch.task {
+        entry.task = task.clone();
+    }
+    if let Some(completed) = &patch.completed {
+        entry.completed = completed.clone();
+    }
+    if let Some(list_id) = &patch.list_id {
+        entry.list_id = list_id_from_write(list_id);
+    }
+    
+    /* an explicit reminder list wins over the legacy single reminder */
+    if let Some(reminders) = &patch.reminders {
+        entry.reminder = reminder_legacy_from_list(reminders);
+        Some(reminders.clone())
+    } else if let Some(reminder) = &patch.reminder {
+        entry.reminder = Some(reminder.clone());
+        Some(reminders_from_legacy(&entry.reminder))
+    } else {
+        None
+    }
+}
+
+/* the changes between a document and its patched form, as a patch; a cleared "reminder" clears the reminder list */
+pub fn entry_patch_from_document(before: &EntryDocument, after: serde_json::Value) -> Result<EntryPatch, String> {
+    let after: EntryDocument = serde_json::from_value(after).map_err(|e| format!["Invalid entry: {}", e])?;
+    let changed = |b: &String, a: String| if *b != a { Some(a) } else { None };
+    
+    let (reminder, reminders) = if after.reminders != before.reminders {
+        (None, Some(after.reminders))
+    } else if after.reminder != before.reminder {
+        match after.reminder {
+            Some(reminder) => (Some(reminder), None),
+            None => (None, Some(vec![])),
+        }
+    } else {
+        (None, None)
+    };
+    
+    Ok(EntryPatch {
+        year: changed(&before.year, after.year),
+        month: changed(&before.month, after.month),
+        day: changed(&before.day, after.day),
+        task: changed(&before.task, after.task),
+        reminder,
+        reminders,
+        completed: changed(&before.completed, after.completed),
+        tags: if after.tags != before.tags { Some(after.tags) } else { None },
+        list_id: if after.list_id != before.list_id { Some(after.list_id.unwrap_or(String::from(LIST_INBOX))) } else { None },
+    })
+}
+
+/* a strong validator for the stored entry: it changes whenever "updated" does */
+pub fn entry_etag(entry: &Entry) -> String {
+    format!["\"{}\"", entry.updated.format("%Y%m%d%H%M%S%6f")]
+}
+
+pub fn entry_write_reminders(src: &EntryWrite) -> Vec<ReminderWrite> {
+    match &src.reminders {
+        Some(reminders) => reminders.clone(),
+        None => reminders_from_legacy(&src.reminder),
+    }
+}
+
+fn list_id_from_write(list_id: &str) -> Option<String> {
+    match list_id {
+        LIST_INBOX => None,
+        _ => Some(String::from(list_id)),
+    }
+}
+
+fn entry_write_reminder(src: &EntryWrite) -> Option<String> {
+    match &src.reminders {
+        Some(reminders) => reminder_legacy_from_list(reminders),
+        None => src.reminder.clone(),
     }
 }
 
@@ -138,4 +407,198 @@ fn convert_str_to_t<'a, T: std::str::FromStr>(s: &'a str, err: &'a str) -> Resul
         Ok(u) => Ok(u),
         Err(_) => Err(err),
     }
+}
+
+/* an entry for tests, which override the fields they are about: `Entry { day: String::from("1"), ..entry_fixture() }` */
+#[cfg(test)]
+pub fn entry_fixture() -> Entry {
+    let timestamp = NaiveDateTime::parse_from_str("2030-06-01 12:30:00", "%Y-%m-%d %H:%M:%S").unwrap();
+
+    Entry {
+        id: String::from("entry-1"),
+        username: String::from("user"),
+        year: String::from("2030"),
+        month: String::from("6"),
+        day: String::from("15"),
+        task: String::from("Pay rent"),
+        reminder: None,
+        expired: String::from("false"),
+        created: timestamp,
+        updated: timestamp,
+        user_id: String::from("user-1"),
+        completed: String::from("false"),
+        deleted_at: None,
+        list_id: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn document() -> EntryDocument {
+        EntryDocument {
+            year: String::from("2030"),
+            month: String::from("6"),
+            day: String::from("15"),
+            task: String::from("Pay rent"),
+            reminder: Some(String::from("7")),
+            reminders: vec![ReminderWrite { offset: String::from("7"), channel: Some(String::from("email")) }],
+            completed: String::from("false"),
+            tags: vec![String::from("home")],
+            list_id: Some(String::from("list-1")),
+        }
+    }
+
+    fn patched(change: serde_json::Value) -> Result<EntryPatch, String> {
+        let mut after = serde_json::to_value(document()).unwrap();
+        crate::patch::patch::merge_patch(&mut after, &change);
+
+        entry_patch_from_document(&document(), after)
+    }
+
+    #[test]
+    fn document_changes() {
+        let patch = patched(json!({"task": "Pay rent early", "completed": "true"})).unwrap();
+
+        assert!(
+            patch.task.as_deref() == Some("Pay rent early") &&
+            patch.completed.as_deref() == Some("true") &&
+            patch.year.is_none() && patch.reminder.is_none() && patch.reminders.is_none() && patch.tags.is_none()
+        );
+    }
+
+    #[test]
+    fn document_list() {
+        let moved = patched(json!({"list_id": "list-2"})).unwrap();
+        let inbox = patched(json!({"list_id": null})).unwrap();
+
+        assert!(moved.list_id.as_deref() == Some("list-2") && inbox.list_id.as_deref() == Some(LIST_INBOX));
+    }
+
+    #[test]
+    fn document_tags() {
+        let patch = patched(json!({"tags": ["home", "bills"]})).unwrap();
+
+        assert!(patch.tags == Some(vec![String::from("home"), String::from("bills")]) && patch.task.is_none());
+    }
+
+    #[test]
+    fn document_clears_reminders() {
+        let by_reminder = patched(json!({"reminder": null})).unwrap();
+        let by_list = patched(json!({"reminders": null})).unwrap();
+        let replaced = patched(json!({"reminder": "1"})).unwrap();
+
+        assert!(
+            by_reminder.reminders == Some(vec![]) &&
+            by_list.reminders == Some(vec![]) &&
+            replaced.reminder.as_deref() == Some("1") && replaced.reminders.is_none()
+        );
+    }
+
+    fn entry(year: &str, month: &str, day: &str, task: &str) -> Entry {
+        Entry {
+            year: String::from(year),
+            month: String::from(month),
+            day: String::from(day),
+            task: String::from(task),
+            ..entry_fixture()
+        }
+    }
+
+    #[test]
+    fn validate_okay() {
+        let reminders = vec![ReminderWrite { offset: String::from("7"), channel: None }];
+
+        assert!(entry_validate_edit(&entry("2080", "1", "1", "Pay rent"), &reminders, None).is_ok());
+    }
+
+    #[test]
+    fn validate_all_reported() {
+        let mut entry = entry("2080", "13", "x", "");
+        entry.completed = String::from("yes");
+
+        let reminders = vec![ReminderWrite { offset: String::from("soon"), channel: None }];
+        let errors = entry_validate_edit(&entry, &reminders, None).unwrap_err();
+        let codes: Vec<&str> = errors.iter().map(|e| e.code.as_str()).collect();
+
+        assert_eq!(codes, vec![CODE_INVALID_MONTH, CODE_INVALID_DAY, CODE_TASK_MISSING, CODE_INVALID_COMPLETED, crate::models::problem::CODE_INVALID_REMINDER]);
+        assert_eq!((errors[0].pointer.as_str(), errors[1].pointer.as_str()), ("/month", "/day"));
+    }
+
+    #[test]
+    fn validate_date_parts() {
+        let pointers = |year: &str, month: &str, day: &str| -> Vec<(String, String)> {
+            entry_validate_edit(&entry(year, month, day, "Pay rent"), &[], None)
+                .unwrap_err()
+                .into_iter()
+                .map(|e| (e.pointer, e.code))
+                .collect()
+        };
+
+        assert_eq!(pointers("2080", "13", "1"), vec![(String::from("/month"), String::from(CODE_INVALID_MONTH))]);
+        assert_eq!(pointers("1900", "1", "32"), vec![
+            (String::from("/year"), String::from(CODE_INVALID_YEAR)),
+            (String::from("/day"), String::from(CODE_INVALID_DAY)),
+        ]);
+        assert_eq!(pointers("2080", "4", "31"), vec![(String::from("/day"), String::from(CODE_INVALID_DATE))]);
+    }
+
+    #[test]
+    fn validate_date() {
+        let passed = entry_validate_edit(&entry("2001", "1", "1", "Pay rent"), &[], None).unwrap_err();
+        let invalid = entry_validate_edit(&entry("2080", "2", "30", "Pay rent"), &[], None).unwrap_err();
+
+        assert!(
+            passed.len() == 1 && passed[0].code == CODE_DATE_PASSED &&
+            invalid.len() == 1 && invalid[0].code == CODE_INVALID_DATE
+        );
+    }
+
+    #[test]
+    fn validate_edit_overdue() {
+        let reminders = vec![ReminderWrite { offset: String::from("3"), channel: None }];
+        let orig = entry("2001", "1", "1", "Pay rent");
+        let mut ticked = orig.clone();
+        ticked.completed = String::from("true");
+        let mut moved = ticked.clone();
+        moved.day = String::from("2");
+        let mut invalid = ticked.clone();
+        invalid.completed = String::from("yes");
+        let added = vec![ReminderWrite { offset: String::from("4"), channel: None }];
+        
+        assert!(
+            entry_validate_edit(&ticked, &reminders, Some((&orig, &reminders))).is_ok() &&
+            entry_validate_edit(&ticked, &reminders, None).is_err() &&
+            entry_validate_edit(&moved, &reminders, Some((&orig, &reminders))).is_err() &&
+            entry_validate_edit(&invalid, &reminders, Some((&orig, &reminders))).is_err() &&
+            entry_validate_edit(&ticked, &added, Some((&orig, &reminders))).is_err()
+        );
+    }
+
+    #[test]
+    fn validate_edit_padded_date() {
+        let orig = entry("2001", "01", "05", "Pay rent");
+        let mut ticked = entry("2001", "1", "5", "Pay rent");
+        ticked.completed = String::from("true");
+
+        assert!(entry_validate_edit(&ticked, &[], Some((&orig, &[]))).is_ok());
+    }
+
+    #[test]
+    fn date_normalized() {
+        let mut entry = entry("2030", "06", "05", "Pay rent");
+        entry_date_normalize(&mut entry);
+
+        assert!(entry.month == "6" && entry.day == "5" && date_part_normalize("x") == "x");
+    }
+
+    #[test]
+    fn document_invalid() {
+        assert!(
+            patched(json!({"task": null})).is_err() &&
+            patched(json!({"day": 15})).is_err()
+        );
+    }
 }
\ No newline at end of file
@@ -0,0 +1,154 @@
+/*************************************************************************
+    "task_server"
+    models/entry_event.rs
+    10/18/26
+    © Matthew Bentivegna
+*************************************************************************/
+use chrono::NaiveDateTime;
+use serde::Serialize;
+use diesel::{
+    Queryable,
+    Insertable,
+};
+use crate::models::list::LIST_INBOX;
+
+pub const LAST_EVENT_ID_HEADER: &str = "Last-Event-ID";
+pub const CONTENT_TYPE_EVENT_STREAM: &str = "text/event-stream";
+
+/* sent instead of the missed events when a client resumes from one that is no longer kept */
+pub const EVENT_RESET: &str = "reset";
+
+/* how long events are kept for clients to resume from */
+pub const ENTRY_EVENT_RETENTION_HOURS: i64 = 24;
+
+/* how many events the broadcast holds for a stream or socket that is slow to take them (one that falls further behind reads them from the database) */
+pub const ENTRY_EVENT_BROADCAST_CAPACITY: usize = 256;
+
+/* every event dispatched for an entry, numbered in order so a stream can resume after the last one it saw */
+#[derive(Serialize, Debug, Clone, Queryable)]
+#[diesel(table_name = crate::models::schema::entry_events)]
+pub struct EntryEvent {
+    pub id: i64,
+    pub entry_id: String,
+    pub user_id: String,
+    pub event: String,
+    pub payload: String,
+    pub created: NaiveDateTime,
+    /* the entry's list before an update ("inbox" for none); None for other events */
+    pub previous_list: Option<String>,
+}
+
+/* the id is assigned by the database */
+#[derive(Debug, Clone, Insertable)]
+#[diesel(table_name = crate::models::schema::entry_events)]
+pub struct EntryEventWrite {
+    pub entry_id: String,
+    pub user_id: String,
+    pub event: String,
+    pub payload: String,
+    pub created: NaiveDateTime,
+    pub previous_list: Option<String>,
+}
+
+impl EntryEventWrite {
+    pub fn new(entry_id: &str, user_id: &str, event: &str, payload: &str, previous_list: Option<&str>, now: NaiveDateTime) -> Self {
+        Self {
+            entry_id: String::from(entry_id),
+            user_id: String::from(user_id),
+            event: String::from(event),
+            payload: String::from(payload),
+            created: now,
+            previous_list: previous_list.map(String::from),
+        }
+    }
+}
+
+/* an event as it is broadcast to the streams and sockets open in this process, with the lists it concerns (the entry's list before and
+   after the change; "inbox" for none) */
+#[derive(Debug, Clone)]
+pub struct EntryEventNotice {
+    pub event: EntryEvent,
+    pub lists: Vec<String>,
+}
+
+/* the lists the event concerns, whether it is broadcast or read back from the database: the entry's list before the change (if it
+   was recorded) and after it, from the event payload; "inbox" for none */
+pub fn entry_event_lists(event: &EntryEvent) -> Vec<String> {
+    let payload = serde_json::from_str::<serde_json::Value>(&event.payload).unwrap_or_default();
+    let after = String::from(payload["data"]["list_id"].as_str().unwrap_or(LIST_INBOX));
+
+    match &event.previous_list {
+        Some(before) if *before != after => vec![before.clone(), after],
+        _ => vec![after],
+    }
+}
+
+/* one Server-Sent Events message; the payload is compact JSON, so it fits on a single "data" line */
+pub fn entry_event_message(event: &EntryEvent) -> String {
+    format!["id: {}\nevent: {}\ndata: {}\n\n", event.id, event.event, event.payload]
+}
+
+/* tells the client to fetch the entries again, and where to resume from afterwards */
+pub fn entry_event_reset_message(last_id: i64) -> String {
+    format!["id: {}\nevent: {}\ndata: {{}}\n\n", last_id, EVENT_RESET]
+}
+
+/* None when the header is absent or not one of our ids */
+pub fn last_event_id_parse(value: Option<&str>) -> Option<i64> {
+    value?.trim().parse::<i64>().ok().filter(|id| *id >= 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn message() {
+        let event = EntryEvent {
+            id: 42,
+            entry_id: String::from("1"),
+            user_id: String::from("u"),
+            event: String::from("entry.created"),
+            payload: String::from(r#"{"event":"entry.created"}"#),
+            created: NaiveDateTime::MIN,
+            previous_list: None,
+        };
+
+        assert_eq!(entry_event_message(&event), "id: 42\nevent: entry.created\ndata: {\"event\":\"entry.created\"}\n\n");
+    }
+
+    #[test]
+    fn lists() {
+        let event = |payload: &str, previous_list: Option<&str>| EntryEvent {
+            id: 1,
+            entry_id: String::from("1"),
+            user_id: String::from("u"),
+            event: String::from("entry.updated"),
+            payload: String::from(payload),
+            created: NaiveDateTime::MIN,
+            previous_list: previous_list.map(String::from),
+        };
+
+        assert!(
+            entry_event_lists(&event(r#"{"data":{"list_id":null}}"#, Some("list-1"))) == vec!["list-1", "inbox"] &&
+            entry_event_lists(&event(r#"{"data":{"list_id":"list-1"}}"#, Some("list-1"))) == vec!["list-1"] &&
+            entry_event_lists(&event(r#"{"data":{"list_id":"list-2"}}"#, None)) == vec!["list-2"]
+        );
+    }
+
+    #[test]
+    fn reset_message() {
+        assert_eq!(entry_event_reset_message(7), "id: 7\nevent: reset\ndata: {}\n\n");
+    }
+
+    #[test]
+    fn last_event_id() {
+        assert!(
+            last_event_id_parse(Some("12")) == Some(12) &&
+            last_event_id_parse(Some(" 0 ")) == Some(0) &&
+            last_event_id_parse(Some("-1")).is_none() &&
+            last_event_id_parse(Some("abc")).is_none() &&
+            last_event_id_parse(None).is_none()
+        );
+    }
+}
\ No newline at end of file
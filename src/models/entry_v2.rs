@@ -0,0 +1,376 @@
+/*************************************************************************
+    "task_server"
+    models/entry_v2.rs
+    10/18/26
+    © Matthew Bentivegna
+*************************************************************************/
+use chrono::{
+    NaiveDate,
+    NaiveDateTime,
+    SecondsFormat,
+};
+use serde::{
+    Deserialize,
+    Serialize
+};
+use schemars::JsonSchema;
+use crate::models::{
+    entry::{
+        EntryRead,
+        EntryWrite,
+        EntryPatch,
+        EntryParams,
+    },
+    reminder::ReminderWrite,
+    list::LIST_INBOX,
+    problem::{
+        FieldError,
+        CODE_INVALID_DATE,
+    },
+};
+
+/* /api/v2 carries the same entries as v1, with native JSON types: an ISO-8601 "due" date, whole-day reminder offsets, booleans and RFC 3339 timestamps */
+const DUE_FORMAT: &str = "%Y-%m-%d";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct ReminderV2 {
+    pub offset: i64,
+    #[serde(default)]
+    pub channel: Option<String>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct EntryV2Read {
+    pub id: String,
+    pub username: String,
+    pub due: String,
+    pub task: String,
+    pub reminders: Vec<ReminderV2>,
+    pub completed: bool,
+    pub expired: bool,
+    pub tags: Vec<String>,
+    pub list_id: Option<String>,
+    pub created: String,
+    pub updated: String,
+}
+
+/* also the document a merge-patch or json-patch applies to */
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct EntryV2Write {
+    pub due: String,
+    pub task: String,
+    #[serde(default)]
+    pub reminders: Vec<ReminderV2>,
+    #[serde(default)]
+    pub completed: bool,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub list_id: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize, JsonSchema)]
+pub struct EntryV2Patch {
+    pub due: Option<String>,
+    pub task: Option<String>,
+    pub reminders: Option<Vec<ReminderV2>>,
+    pub completed: Option<bool>,
+    pub tags: Option<Vec<String>>,
+    pub list_id: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize, JsonSchema)]
+pub struct EntryV2Params {
+    pub username: Option<String>,
+    pub due: Option<String>,
+    pub completed: Option<bool>,
+    pub expired: Option<bool>,
+    pub tag: Option<String>,
+    pub tag_any: Option<String>,
+    pub tag_none: Option<String>,
+    pub list_id: Option<String>,
+}
+
+impl EntryV2Read {
+    pub fn new(read: &EntryRead) -> Self {
+        let entry = &read.entry;
+
+        Self {
+            id: entry.id.clone(),
+            username: entry.username.clone(),
+            due: format!["{:0>4}-{:0>2}-{:0>2}", entry.year, entry.month, entry.day],
+            task: entry.task.clone(),
+            reminders: read.reminders
+                .iter()
+                .map(|r| ReminderV2 {
+                    offset: r.offset.parse().unwrap_or_default(),
+                    channel: Some(r.channel.clone()),
+                })
+                .collect(),
+            completed: entry.completed == "true",
+            expired: entry.expired == "true",
+            tags: read.tags.clone(),
+            list_id: entry.list_id.clone(),
+            created: timestamp_v2(&entry.created),
+            updated: timestamp_v2(&entry.updated),
+        }
+    }
+}
+
+impl EntryV2Write {
+    pub fn new(read: &EntryRead) -> Self {
+        let read = EntryV2Read::new(read);
+
+        Self {
+            due: read.due,
+            task: read.task,
+            reminders: read.reminders,
+            completed: read.completed,
+            tags: read.tags,
+            list_id: read.list_id,
+        }
+    }
+}
+
+/* stored times are UTC */
+pub fn timestamp_v2(time: &NaiveDateTime) -> String {
+    time.and_utc().to_rfc3339_opts(SecondsFormat::Secs, true)
+}
+
+/* v1's year, month and day strings */
+fn due_v1(due: &str) -> Result<(String, String, String), FieldError> {
+    match NaiveDate::parse_from_str(due, DUE_FORMAT) {
+        Ok(date) => Ok((date.format("%Y").to_string(), date.format("%-m").to_string(), date.format("%-d").to_string())),
+        Err(_) => Err(FieldError::new("/due", CODE_INVALID_DATE, "Invalid due date (expected YYYY-MM-DD)")),
+    }
+}
+
+fn reminders_v1(reminders: &[ReminderV2]) -> Vec<ReminderWrite> {
+    reminders
+        .iter()
+        .map(|r| ReminderWrite {
+            offset: r.offset.to_string(),
+            channel: r.channel.clone(),
+        })
+        .collect()
+}
+
+pub fn entry_write_from_v2(src: &EntryV2Write) -> Result<EntryWrite, Vec<FieldError>> {
+    let (year, month, day) = due_v1(&src.due).map_err(|e| vec![e])?;
+
+    Ok(EntryWrite {
+        year,
+        month,
+        day,
+        task: src.task.clone(),
+        reminder: None,
+        /* none given leaves room for the list's default reminder */
+        reminders: if src.reminders.is_empty() { None } else { Some(reminders_v1(&src.reminders)) },
+        completed: Some(src.completed.to_string()),
+        tags: Some(src.tags.clone()),
+        list_id: src.list_id.clone(),
+    })
+}
+
+pub fn entry_patch_from_v2(src: &EntryV2Patch) -> Result<EntryPatch, Vec<FieldError>> {
+    let (year, month, day) = match &src.due {
+        Some(due) => {
+            let (year, month, day) = due_v1(due).map_err(|e| vec![e])?;
+            (Some(year), Some(month), Some(day))
+        },
+        None => (None, None, None),
+    };
+
+    Ok(EntryPatch {
+        year,
+        month,
+        day,
+        task: src.task.clone(),
+        reminder: None,
+        reminders: src.reminders.as_deref().map(reminders_v1),
+        completed: src.completed.map(|c| c.to_string()),
+        tags: src.tags.clone(),
+        list_id: src.list_id.clone(),
+    })
+}
+
+/* a patched document sets every field; unchanged ones are simply rewritten */
+pub fn entry_v2_patch_from_document(document: serde_json::Value) -> Result<EntryV2Patch, String> {
+    let after: EntryV2Write = serde_json::from_value(document).map_err(|e| format!["Invalid entry: {}", e])?;
+
+    Ok(EntryV2Patch {
+        due: Some(after.due),
+        task: Some(after.task),
+        reminders: Some(after.reminders),
+        completed: Some(after.completed),
+        tags: Some(after.tags),
+        list_id: Some(after.list_id.unwrap_or(String::from(LIST_INBOX))),
+    })
+}
+
+pub fn entry_params_from_v2(src: &EntryV2Params) -> Result<EntryParams, FieldError> {
+    let (year, month, day) = match &src.due {
+        Some(due) => {
+            let (year, month, day) = due_v1(due)?;
+            (Some(year), Some(month), Some(day))
+        },
+        None => (None, None, None),
+    };
+
+    Ok(EntryParams {
+        username: src.username.clone(),
+        year,
+        month,
+        day,
+        reminder: None,
+        expired: src.expired.map(|e| e.to_string()),
+        completed: src.completed.map(|c| c.to_string()),
+        tag: src.tag.clone(),
+        tag_any: src.tag_any.clone(),
+        tag_none: src.tag_none.clone(),
+        list_id: src.list_id.clone(),
+    })
+}
+
+/* the v2 address of a v1 entry route, sent to v1 clients as its successor; None for routes v2 does not replace */
+pub fn entry_v2_successor(path: &str) -> Option<String> {
+    let rest = path.strip_prefix("/api/entries")?;
+
+    if rest.is_empty() {
+        return Some(String::from("/api/v2/entries"));
+    }
+
+    match rest.strip_prefix('/') {
+        Some(id) if !id.is_empty() && !id.contains('/') && !["import", "export", "batch", "events", "all"].contains(&id) => {
+            Some(format!["/api/v2/entries/{}", id])
+        },
+        _ => None,
+    }
+}
+
+/* the shared validation reports v1 fields; the date parts are all "due" in v2 */
+pub fn field_errors_v2(errors: Vec<FieldError>) -> Vec<FieldError> {
+    errors
+        .into_iter()
+        .map(|e| match e.pointer.as_str() {
+            "/year" | "/month" | "/day" => FieldError { pointer: String::from("/due"), ..e },
+            _ => e,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{
+        entry::{
+            Entry,
+            entry_fixture,
+        },
+        reminder::Reminder,
+        problem::CODE_DATE_PASSED,
+    };
+
+    fn read() -> EntryRead {
+        let time = NaiveDate::from_ymd_opt(2026, 10, 18).unwrap().and_hms_opt(9, 30, 0).unwrap();
+
+        EntryRead::new(
+            Entry {
+                id: String::from("1"),
+                year: String::from("2027"),
+                day: String::from("5"),
+                task: String::from("Task"),
+                reminder: Some(String::from("2")),
+                created: time,
+                updated: time,
+                user_id: String::from("u"),
+                completed: String::from("true"),
+                ..entry_fixture()
+            },
+            vec![Reminder {
+                id: String::from("r"),
+                entry_id: String::from("1"),
+                offset: String::from("2"),
+                channel: String::from("email"),
+            }],
+            vec![String::from("home")],
+        )
+    }
+
+    #[test]
+    fn read_typed() {
+        let v2 = serde_json::to_value(EntryV2Read::new(&read())).unwrap();
+
+        assert_eq!(v2, serde_json::json!({
+            "id": "1",
+            "username": "user",
+            "due": "2027-06-05",
+            "task": "Task",
+            "reminders": [{ "offset": 2, "channel": "email" }],
+            "completed": true,
+            "expired": false,
+            "tags": ["home"],
+            "list_id": null,
+            "created": "2026-10-18T09:30:00Z",
+            "updated": "2026-10-18T09:30:00Z",
+        }));
+    }
+
+    #[test]
+    fn write_to_v1() {
+        let write = entry_write_from_v2(&EntryV2Write::new(&read())).unwrap();
+
+        assert!(
+            write.year == "2027" &&
+            write.month == "6" &&
+            write.day == "5" &&
+            write.completed.as_deref() == Some("true") &&
+            write.tags == Some(vec![String::from("home")]) &&
+            write.reminders == Some(vec![ReminderWrite { offset: String::from("2"), channel: Some(String::from("email")) }])
+        );
+    }
+
+    #[test]
+    fn due_invalid() {
+        for due in ["2027-02-30", "6/5/2027", "2027-6"] {
+            let write = EntryV2Write { due: String::from(due), task: String::from("Task"), reminders: vec![], completed: false, tags: vec![], list_id: None };
+            let errors = entry_write_from_v2(&write).unwrap_err();
+
+            assert!(errors[0].pointer == "/due" && errors[0].code == CODE_INVALID_DATE);
+        }
+    }
+
+    #[test]
+    fn patch_to_v1() {
+        let patch = entry_patch_from_v2(&EntryV2Patch { completed: Some(false), ..Default::default() }).unwrap();
+
+        assert!(patch.completed.as_deref() == Some("false") && patch.year.is_none() && patch.reminders.is_none());
+    }
+
+    #[test]
+    fn params_to_v1() {
+        let params = entry_params_from_v2(&EntryV2Params { due: Some(String::from("2027-06-05")), expired: Some(true), ..Default::default() }).unwrap();
+
+        assert!(params.year.as_deref() == Some("2027") && params.day.as_deref() == Some("5") && params.expired.as_deref() == Some("true"));
+    }
+
+    #[test]
+    fn successor() {
+        assert!(
+            entry_v2_successor("/api/entries").as_deref() == Some("/api/v2/entries") &&
+            entry_v2_successor("/api/entries/abc").as_deref() == Some("/api/v2/entries/abc") &&
+            entry_v2_successor("/api/entries/export").is_none() &&
+            entry_v2_successor("/api/entries/events").is_none() &&
+            entry_v2_successor("/api/entries/all").is_none() &&
+            entry_v2_successor("/api/entriesx").is_none() &&
+            entry_v2_successor("/api/users").is_none()
+        );
+    }
+
+    #[test]
+    fn errors_point_to_due() {
+        let errors = field_errors_v2(vec![FieldError::new("/day", CODE_DATE_PASSED, "Date has passed")]);
+
+        assert_eq!(errors[0].pointer, "/due");
+    }
+}
\ No newline at end of file
@@ -0,0 +1,54 @@
+/*************************************************************************
+    "task_server"
+    models/export.rs
+    10/18/26
+    © Matthew Bentivegna
+*************************************************************************/
+use serde::Deserialize;
+use schemars::JsonSchema;
+
+pub const FORMAT_CSV: &str = "csv";
+pub const FORMAT_JSONL: &str = "jsonl";
+pub const FORMAT_MD: &str = "md";
+
+pub const FORMATS: [&str; 3] = [FORMAT_CSV, FORMAT_JSONL, FORMAT_MD];
+
+/* the entry filters are read separately, as EntryParams */
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct ExportParams {
+    pub format: Option<String>,
+}
+
+impl ExportParams {
+    /* CSV unless another known format is asked for */
+    pub fn format(&self) -> Option<&'static str> {
+        match &self.format {
+            None => Some(FORMAT_CSV),
+            Some(format) => FORMATS.iter().find(|f| *f == format).copied(),
+        }
+    }
+}
+
+pub fn export_content_type(format: &str) -> &'static str {
+    match format {
+        FORMAT_JSONL => "application/jsonl; charset=utf-8",
+        FORMAT_MD => "text/markdown; charset=utf-8",
+        _ => "text/csv; charset=utf-8",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn format_param() {
+        let params = |format: Option<&str>| ExportParams { format: format.map(String::from) };
+
+        assert!(
+            params(None).format() == Some(FORMAT_CSV) &&
+            params(Some("md")).format() == Some(FORMAT_MD) &&
+            params(Some("xml")).format().is_none()
+        );
+    }
+}
\ No newline at end of file
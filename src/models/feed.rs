@@ -0,0 +1,75 @@
+/*************************************************************************
+    "task_server"
+    models/feed.rs
+    10/18/26
+    © Matthew Bentivegna
+*************************************************************************/
+use chrono::NaiveDateTime;
+use serde::{
+    Deserialize,
+    Serialize
+};
+use schemars::JsonSchema;
+use diesel::{
+    Queryable,
+    Insertable,
+    AsChangeset
+};
+
+#[derive(Serialize, Deserialize, Debug, Clone, Queryable, Insertable, AsChangeset)]
+#[diesel(table_name = crate::models::schema::feeds)]
+pub struct Feed {
+    pub id: String,
+    pub user_id: String,
+    pub token: String,
+    pub created: NaiveDateTime,
+}
+
+#[derive(Debug, Serialize)]
+pub struct FeedRead {
+    pub token: String,
+    pub url: String,
+    pub created: NaiveDateTime,
+}
+
+/* "component" is "vtodo" (default) or "vevent" */
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct FeedParams {
+    pub component: Option<String>,
+}
+
+impl FeedRead {
+    pub fn new(feed: &Feed) -> Self {
+        Self {
+            token: feed.token.clone(),
+            url: feed_path(&feed.token),
+            created: feed.created,
+        }
+    }
+}
+
+/* the token is the only credential for the feed, so it is long and random, and unrelated to the password */
+pub fn feed_token_generate() -> String {
+    format!["{}{}", uuid::Uuid::new_v4().simple(), uuid::Uuid::new_v4().simple()]
+}
+
+pub fn feed_path(token: &str) -> String {
+    format!["/api/feeds/{}.ics", token]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn token_unique() {
+        let token = feed_token_generate();
+
+        assert!(token.len() == 64 && token != feed_token_generate());
+    }
+
+    #[test]
+    fn path() {
+        assert_eq!(feed_path("abc"), "/api/feeds/abc.ics");
+    }
+}
\ No newline at end of file
@@ -0,0 +1,129 @@
+/*************************************************************************
+    "task_server"
+    models/idempotency.rs
+    10/18/26
+    © Matthew Bentivegna
+*************************************************************************/
+use chrono::NaiveDateTime;
+use serde::{
+    Deserialize,
+    Serialize
+};
+use diesel::{
+    Queryable,
+    Insertable,
+    AsChangeset
+};
+use sha2::{
+    Digest,
+    Sha256,
+};
+
+pub const IDEMPOTENCY_KEY_HEADER: &str = "Idempotency-Key";
+pub const IDEMPOTENT_REPLAYED_HEADER: &str = "Idempotent-Replayed";
+
+pub const IDEMPOTENCY_KEY_MAX_LENGTH: usize = 255;
+
+/* a request still without a response after this long is taken to have died, and its key can be used again */
+pub const IDEMPOTENCY_PENDING_SECONDS: i64 = 60;
+
+/* one key, per user and endpoint; the response is empty until the first request with it has finished */
+#[derive(Serialize, Deserialize, Debug, Clone, Queryable, Insertable, AsChangeset)]
+#[diesel(table_name = crate::models::schema::idempotency_keys)]
+#[diesel(treat_none_as_null = true)]
+pub struct IdempotencyKey {
+    pub id: String,
+    pub scope: String,
+    pub idempotency_key: String,
+    pub request_hash: String,
+    pub response_status: Option<i32>,
+    pub response_headers: Option<String>,
+    pub response_body: Option<String>,
+    pub created: NaiveDateTime,
+}
+
+impl IdempotencyKey {
+    pub fn new(scope: &str, key: &str, request_hash: &str, now: NaiveDateTime) -> Self {
+        Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            scope: String::from(scope),
+            idempotency_key: String::from(key),
+            request_hash: String::from(request_hash),
+            response_status: None,
+            response_headers: None,
+            response_body: None,
+            created: now,
+        }
+    }
+
+    /* the stored response headers, as name/value pairs */
+    pub fn headers(&self) -> Vec<(String, String)> {
+        self.response_headers
+            .as_deref()
+            .and_then(|h| serde_json::from_str(h).ok())
+            .unwrap_or_default()
+    }
+}
+
+/* printable ASCII, as a header value, of a sensible length */
+pub fn idempotency_key_validate(key: &str) -> Result<(), String> {
+    if key.is_empty() || key.len() > IDEMPOTENCY_KEY_MAX_LENGTH {
+        return Err(format!["Idempotency-Key must be 1 to {} characters", IDEMPOTENCY_KEY_MAX_LENGTH]);
+    }
+
+    if !key.chars().all(|c| c.is_ascii_graphic()) {
+        return Err(String::from("Invalid Idempotency-Key"));
+    }
+
+    Ok(())
+}
+
+/* a retry must repeat the method, path and body exactly */
+pub fn idempotency_request_hash(method: &str, path: &str, body: &[u8]) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(method.as_bytes());
+    hasher.update(b" ");
+    hasher.update(path.as_bytes());
+    hasher.update(b"\n");
+    hasher.update(body);
+
+    hasher.finalize().iter().map(|b| format!["{:02x}", b]).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn key_validate() {
+        assert!(
+            idempotency_key_validate("8e03978e-40d5-43e8-bc93-6894a57f9324").is_ok() &&
+            idempotency_key_validate("").is_err() &&
+            idempotency_key_validate("has space").is_err() &&
+            idempotency_key_validate(&"k".repeat(IDEMPOTENCY_KEY_MAX_LENGTH + 1)).is_err()
+        );
+    }
+
+    #[test]
+    fn request_hash() {
+        let hash = idempotency_request_hash("POST", "/api/entries", b"{\"task\": \"Pay rent\"}");
+
+        assert!(
+            hash.len() == 64 &&
+            hash == idempotency_request_hash("POST", "/api/entries", b"{\"task\": \"Pay rent\"}") &&
+            hash != idempotency_request_hash("POST", "/api/entries", b"{\"task\": \"Pay rent!\"}") &&
+            hash != idempotency_request_hash("POST", "/api/users", b"{\"task\": \"Pay rent\"}")
+        );
+    }
+
+    #[test]
+    fn stored_headers() {
+        let mut key = IdempotencyKey::new("user-1 /api/entries", "key-1", "hash", NaiveDateTime::MIN);
+
+        assert!(key.headers().is_empty());
+
+        key.response_headers = Some(String::from(r#"[["content-type", "application/json"]]"#));
+
+        assert!(key.headers() == vec![(String::from("content-type"), String::from("application/json"))]);
+    }
+}
\ No newline at end of file
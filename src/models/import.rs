@@ -0,0 +1,105 @@
+/*************************************************************************
+    "task_server"
+    models/import.rs
+    10/18/26
+    © Matthew Bentivegna
+*************************************************************************/
+use serde::{
+    Deserialize,
+    Serialize
+};
+use schemars::JsonSchema;
+
+pub const FORMAT_ICS: &str = "ics";
+pub const FORMAT_CSV: &str = "csv";
+pub const FORMAT_TODOTXT: &str = "todotxt";
+
+pub const ROW_CREATED: &str = "created";
+pub const ROW_VALID: &str = "valid";
+pub const ROW_REJECTED: &str = "rejected";
+
+/* "map" renames CSV columns, e.g. "Title:task,Due Date:due" */
+#[derive(Debug, Default, Deserialize, JsonSchema)]
+pub struct ImportParams {
+    pub format: Option<String>,
+    pub dry_run: Option<String>,
+    pub map: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ImportRow {
+    pub row: usize,
+    pub status: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub task: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ImportReport {
+    pub dry_run: bool,
+    pub created: usize,
+    pub rejected: usize,
+    pub rows: Vec<ImportRow>,
+}
+
+impl ImportReport {
+    pub fn new(dry_run: bool, rows: Vec<ImportRow>) -> Self {
+        Self {
+            dry_run,
+            created: rows.iter().filter(|r| r.status == ROW_CREATED).count(),
+            rejected: rows.iter().filter(|r| r.status == ROW_REJECTED).count(),
+            rows,
+        }
+    }
+}
+
+/* the format from the "format" parameter, or else from the Content-Type */
+pub fn import_format(param: Option<&str>, content_type: &str) -> Option<&'static str> {
+    let from_content_type = || match content_type.split(';').next().unwrap_or("").trim() {
+        "text/calendar" => Some(FORMAT_ICS),
+        "text/csv" => Some(FORMAT_CSV),
+        "text/plain" => Some(FORMAT_TODOTXT),
+        _ => None,
+    };
+
+    match param {
+        Some(FORMAT_ICS) => Some(FORMAT_ICS),
+        Some(FORMAT_CSV) => Some(FORMAT_CSV),
+        Some(FORMAT_TODOTXT) => Some(FORMAT_TODOTXT),
+        Some(_) => None,
+        None => from_content_type(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn format_detection() {
+        assert!(
+            import_format(Some("csv"), "text/plain") == Some(FORMAT_CSV) &&
+            import_format(None, "text/calendar; charset=utf-8") == Some(FORMAT_ICS) &&
+            import_format(None, "application/json").is_none() &&
+            import_format(Some("xlsx"), "text/csv").is_none()
+        );
+    }
+
+    #[test]
+    fn report_counts() {
+        let row = |status: &str| ImportRow {
+            row: 1,
+            status: String::from(status),
+            id: None,
+            task: None,
+            error: None,
+        };
+        let report = ImportReport::new(false, vec![row(ROW_CREATED), row(ROW_REJECTED), row(ROW_CREATED)]);
+
+        assert!(report.created == 2 && report.rejected == 1);
+    }
+}
\ No newline at end of file
@@ -0,0 +1,187 @@
+/*************************************************************************
+    "task_server"
+    models/list.rs
+    10/18/26
+    © Matthew Bentivegna
+*************************************************************************/
+use chrono::NaiveDateTime;
+use serde::{
+    Deserialize,
+    Serialize
+};
+use diesel::{
+    Queryable,
+    Insertable,
+    AsChangeset
+};
+use schemars::JsonSchema;
+use crate::models::{
+    reminder::ReminderWrite,
+    problem::{
+        FieldError,
+        field_errors_result,
+        CODE_NAME_MISSING,
+        CODE_INVALID_COLOR,
+        CODE_INVALID_REMINDER,
+    },
+};
+
+/* the "list_id" of an entry in no list, as a filter ("?list_id=inbox") and to move an entry out of its list */
+pub const LIST_INBOX: &str = "inbox";
+
+/* what deleting a list does with the entries still in it */
+pub const LIST_ENTRIES_INBOX: &str = "inbox";
+pub const LIST_ENTRIES_DELETE: &str = "delete";
+
+pub const LIST_COLOR_DEFAULT: &str = "#808080";
+pub const LIST_NAME_MAX: usize = 64;
+
+/* a named group of one user's entries; lists are shown in "sort_order", and new entries in the list get its default reminder */
+#[derive(Serialize, Deserialize, Debug, Clone, Queryable, Insertable, AsChangeset, JsonSchema)]
+#[diesel(table_name = crate::models::schema::lists)]
+#[diesel(treat_none_as_null = true)]
+pub struct EntryList {
+    pub id: String,
+    pub user_id: String,
+    pub name: String,
+    pub color: String,
+    pub default_reminder: Option<String>,
+    pub sort_order: i32,
+    #[schemars(with = "String")]
+    pub created: NaiveDateTime,
+    #[schemars(with = "String")]
+    pub updated: NaiveDateTime,
+}
+
+/* the body of POST and PUT; "sort_order" defaults to after the user's other lists */
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct EntryListWrite {
+    pub name: String,
+    pub color: Option<String>,
+    pub default_reminder: Option<String>,
+    pub sort_order: Option<i32>,
+}
+
+#[derive(Debug, Default, Deserialize, JsonSchema)]
+pub struct ListDeleteParams {
+    pub entries: Option<String>,
+}
+
+impl ListDeleteParams {
+    /* moving the entries to the inbox is the default */
+    pub fn cascade(&self) -> Option<bool> {
+        match self.entries.as_deref() {
+            None | Some(LIST_ENTRIES_INBOX) => Some(false),
+            Some(LIST_ENTRIES_DELETE) => Some(true),
+            Some(_) => None,
+        }
+    }
+}
+
+impl EntryList {
+    /* the reminder list a new entry in this list starts with, when it sets none itself */
+    pub fn default_reminders(&self) -> Vec<ReminderWrite> {
+        match &self.default_reminder {
+            Some(offset) => vec![
+                ReminderWrite {
+                    offset: offset.clone(),
+                    channel: None,
+                }
+            ],
+            None => vec![],
+        }
+    }
+}
+
+/* "orig" is the list being replaced by a PUT, whose id and creation time are kept */
+pub fn list_from_write(src: &EntryListWrite, user_id: &str, sort_order: i32, orig: Option<&EntryList>) -> EntryList {
+    EntryList {
+        id: orig.map(|l| l.id.clone()).unwrap_or_default(),
+        user_id: String::from(user_id),
+        name: src.name.trim().to_string(),
+        color: src.color.clone().unwrap_or(String::from(LIST_COLOR_DEFAULT)).to_lowercase(),
+        default_reminder: src.default_reminder.clone(),
+        sort_order: src.sort_order.unwrap_or(sort_order),
+        created: orig.map(|l| l.created).unwrap_or(NaiveDateTime::MIN),
+        updated: NaiveDateTime::MIN,
+    }
+}
+
+/* the name must also be unique among the user's lists, which needs the database */
+pub fn list_validate(list: &EntryList) -> Result<(), Vec<FieldError>> {
+    let mut errors = vec![];
+
+    if list.name.is_empty() || list.name.chars().count() > LIST_NAME_MAX {
+        errors.push(FieldError::new("/name", CODE_NAME_MISSING, &format!["No name present (1 to {} characters)", LIST_NAME_MAX]));
+    }
+
+    if !color_valid(&list.color) {
+        errors.push(FieldError::new("/color", CODE_INVALID_COLOR, "Invalid color (expected #rrggbb)"));
+    }
+
+    if let Some(offset) = &list.default_reminder {
+        if offset.parse::<u32>().is_err() {
+            errors.push(FieldError::new("/default_reminder", CODE_INVALID_REMINDER, "Invalid reminder"));
+        }
+    }
+
+    field_errors_result(errors)
+}
+
+fn color_valid(color: &str) -> bool {
+    color.len() == 7 && color.starts_with('#') && color[1..].chars().all(|c| c.is_ascii_hexdigit())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write(name: &str, color: Option<&str>, default_reminder: Option<&str>) -> EntryListWrite {
+        EntryListWrite {
+            name: String::from(name),
+            color: color.map(String::from),
+            default_reminder: default_reminder.map(String::from),
+            sort_order: None,
+        }
+    }
+
+    #[test]
+    fn defaults() {
+        let list = list_from_write(&write(" Work ", None, None), "user-1", 3, None);
+
+        assert!(
+            list.name == "Work" &&
+            list.color == LIST_COLOR_DEFAULT &&
+            list.sort_order == 3 &&
+            list.default_reminders().is_empty() &&
+            list_validate(&list).is_ok()
+        );
+    }
+
+    #[test]
+    fn validate_all_reported() {
+        let list = list_from_write(&write("", Some("red"), Some("-1")), "user-1", 0, None);
+        let errors = list_validate(&list).unwrap_err();
+        let pointers: Vec<&str> = errors.iter().map(|e| e.pointer.as_str()).collect();
+
+        assert_eq!(pointers, vec!["/name", "/color", "/default_reminder"]);
+    }
+
+    #[test]
+    fn default_reminders() {
+        let list = list_from_write(&write("Bills", Some("#FFAA00"), Some("3")), "user-1", 0, None);
+
+        assert!(list.color == "#ffaa00" && list.default_reminders() == vec![ReminderWrite { offset: String::from("3"), channel: None }]);
+    }
+
+    #[test]
+    fn delete_params() {
+        let params = |entries: Option<&str>| ListDeleteParams { entries: entries.map(String::from) };
+
+        assert!(
+            params(None).cascade() == Some(false) &&
+            params(Some(LIST_ENTRIES_DELETE)).cascade() == Some(true) &&
+            params(Some("archive")).cascade().is_none()
+        );
+    }
+}
\ No newline at end of file
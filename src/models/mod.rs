@@ -5,5 +5,24 @@
 	© Matthew Bentivegna
 *************************************************************************/
 pub mod entry;
+pub mod entry_v2;
+pub mod entry_event;
+pub mod sync;
+pub mod revision;
+pub mod audit;
+pub mod tag;
+pub mod list;
 pub mod user;
+pub mod channel;
+pub mod reminder;
+pub mod notification;
+pub mod outbox;
+pub mod webhook;
+pub mod feed;
+pub mod import;
+pub mod export;
+pub mod batch;
+pub mod idempotency;
+pub mod socket;
+pub mod problem;
 pub mod schema;
\ No newline at end of file
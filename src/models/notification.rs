@@ -0,0 +1,229 @@
+/*************************************************************************
+    "task_server"
+    models/notification.rs
+    10/18/26
+    © Matthew Bentivegna
+*************************************************************************/
+use chrono::{
+    NaiveDateTime,
+    Duration,
+};
+use serde::{
+    Deserialize,
+    Serialize
+};
+use diesel::{
+    Queryable,
+    Insertable,
+    AsChangeset
+};
+use crate::models::{
+    entry::{
+        Entry,
+        entry_due_date,
+    },
+    reminder::Reminder,
+    channel::CHANNEL_DEFAULT,
+};
+
+pub const KIND_REMINDER: &str = "reminder";
+pub const KIND_EXPIRED: &str = "expired";
+
+pub const STATUS_PLANNED: &str = "planned";
+pub const STATUS_SENDING: &str = "sending";
+pub const STATUS_QUEUED: &str = "queued";
+pub const STATUS_FAILED: &str = "failed";
+pub const STATUS_SKIPPED: &str = "skipped";
+
+/* how long a reminder due at expiry itself (offset 0) stays deliverable */
+pub const REMINDER_LATE_HOURS: i64 = 1;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Queryable, Insertable, AsChangeset)]
+#[diesel(table_name = crate::models::schema::notifications)]
+pub struct Notification {
+    pub id: String,
+    pub dedupe_key: String,
+    pub entry_id: String,
+    pub user_id: String,
+    pub kind: String,
+    pub channel: String,
+    pub scheduled: NaiveDateTime,
+    pub status: String,
+    pub created: NaiveDateTime,
+    pub processed: Option<NaiveDateTime>,
+}
+
+/* every notification an entry should produce; the dedupe key includes the due date, so moving it re-arms everything */
+pub fn notifications_plan(entry: &Entry, reminders: &[Reminder], channels: &[String], utc_offset: i32, now: NaiveDateTime) -> Result<Vec<Notification>, String> {
+    let due_date = entry_due_date(entry)?;
+    let due_key = format!["{:04}-{:02}-{:02}", due_date.year(), due_date.month(), due_date.day()];
+    let mut list: Vec<Notification> = vec![];
+
+    for reminder in reminders {
+        let offset = match reminder.offset.parse::<i64>() {
+            Ok(offset) => offset,
+            Err(_) => { return Err("Invalid reminder".to_string()); },
+        };
+
+        for channel in notification_channels(&reminder.channel, channels) {
+            let dedupe_key = format!["{}:{}:{}:{}:{}", KIND_REMINDER, entry.id, channel, reminder.offset, due_key];
+
+            /* a "default" reminder and an explicit one can land on the same channel */
+            if list.iter().any(|n| n.dedupe_key == dedupe_key) {
+                continue;
+            }
+
+            list.push(
+                notification_new(entry, dedupe_key, KIND_REMINDER, &channel, due_date.add_days(-offset).end_of_day_utc(utc_offset), now)
+            );
+        }
+    }
+
+    for channel in channels {
+        let dedupe_key = format!["{}:{}:{}:{}", KIND_EXPIRED, entry.id, channel, due_key];
+
+        list.push(
+            notification_new(entry, dedupe_key, KIND_EXPIRED, channel, due_date.end_of_day_utc(utc_offset), now)
+        );
+    }
+
+    Ok(list)
+}
+
+/* a reminder caught up after downtime is pointless once the entry has expired, but one scheduled at expiry itself still goes out */
+pub fn reminder_deliverable(scheduled: NaiveDateTime, expires: NaiveDateTime, now: NaiveDateTime) -> bool {
+    if scheduled < expires {
+        now < expires
+    } else {
+        now < scheduled + Duration::hours(REMINDER_LATE_HOURS)
+    }
+}
+
+fn notification_channels(channel: &str, defaults: &[String]) -> Vec<String> {
+    if channel == CHANNEL_DEFAULT {
+        defaults.to_vec()
+    } else {
+        vec![String::from(channel)]
+    }
+}
+
+fn notification_new(entry: &Entry, dedupe_key: String, kind: &str, channel: &str, scheduled: NaiveDateTime, now: NaiveDateTime) -> Notification {
+    Notification {
+        id: uuid::Uuid::new_v4().to_string(),
+        dedupe_key,
+        entry_id: entry.id.clone(),
+        user_id: entry.user_id.clone(),
+        kind: String::from(kind),
+        channel: String::from(channel),
+        scheduled,
+        status: String::from(STATUS_PLANNED),
+        created: now,
+        processed: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+    use crate::models::entry::entry_fixture;
+    use crate::models::channel::{
+        CHANNEL_EMAIL,
+        CHANNEL_WEBHOOK,
+    };
+
+    fn entry(year: &str, month: &str, day: &str) -> Entry {
+        Entry {
+            year: String::from(year),
+            month: String::from(month),
+            day: String::from(day),
+            ..entry_fixture()
+        }
+    }
+
+    fn reminder(offset: &str) -> Reminder {
+        reminder_on(offset, CHANNEL_EMAIL)
+    }
+
+    fn reminder_on(offset: &str, channel: &str) -> Reminder {
+        Reminder {
+            id: String::from("reminder-1"),
+            entry_id: String::from("entry-1"),
+            offset: String::from(offset),
+            channel: String::from(channel),
+        }
+    }
+
+    fn email() -> Vec<String> {
+        vec![String::from(CHANNEL_EMAIL)]
+    }
+
+    #[test]
+    fn plan_schedules() {
+        let list = notifications_plan(&entry("2030", "6", "15"), &[reminder("7"), reminder("1")], &email(), -5, NaiveDateTime::MIN).unwrap();
+        let at = |m, d, h| NaiveDate::from_ymd_opt(2030, m, d).unwrap().and_hms_opt(h, 0, 0).unwrap();
+
+        assert!(
+            list.len() == 3 &&
+            list[0].scheduled == at(6, 9, 5) &&
+            list[1].scheduled == at(6, 15, 5) &&
+            list[2].kind == KIND_EXPIRED &&
+            list[2].scheduled == at(6, 16, 5)
+        );
+    }
+
+    #[test]
+    fn plan_keys_follow_due_date() {
+        let a = notifications_plan(&entry("2030", "6", "15"), &[reminder("1")], &email(), 0, NaiveDateTime::MIN).unwrap();
+        let b = notifications_plan(&entry("2030", "6", "15"), &[reminder("1")], &email(), 3, NaiveDateTime::MIN).unwrap();
+        let c = notifications_plan(&entry("2030", "6", "16"), &[reminder("1")], &email(), 0, NaiveDateTime::MIN).unwrap();
+
+        assert!(
+            a[0].dedupe_key == b[0].dedupe_key &&
+            a[0].dedupe_key != c[0].dedupe_key &&
+            a[1].dedupe_key != c[1].dedupe_key
+        );
+    }
+
+    #[test]
+    fn plan_offset_zero_deliverable() {
+        let list = notifications_plan(&entry("2030", "6", "15"), &[reminder("0"), reminder("1")], &email(), 0, NaiveDateTime::MIN).unwrap();
+        let expires = list[2].scheduled;
+
+        assert!(
+            list[0].scheduled == expires &&
+            reminder_deliverable(list[0].scheduled, expires, expires) &&
+            !reminder_deliverable(list[0].scheduled, expires, expires + Duration::hours(REMINDER_LATE_HOURS)) &&
+            !reminder_deliverable(list[1].scheduled, expires, expires) &&
+            reminder_deliverable(list[1].scheduled, expires, expires - Duration::minutes(1))
+        );
+    }
+
+    #[test]
+    fn plan_invalid_date() {
+        assert!(notifications_plan(&entry("2030", "13", "1"), &[], &email(), 0, NaiveDateTime::MIN).is_err());
+    }
+
+    #[test]
+    fn plan_default_channels() {
+        let channels = vec![String::from(CHANNEL_EMAIL), String::from(CHANNEL_WEBHOOK)];
+        let reminders = [reminder_on("1", CHANNEL_DEFAULT), reminder_on("1", CHANNEL_EMAIL), reminder_on("2", CHANNEL_WEBHOOK)];
+        let list = notifications_plan(&entry("2030", "6", "15"), &reminders, &channels, 0, NaiveDateTime::MIN).unwrap();
+        let count = |kind: &str, channel: &str| list.iter().filter(|n| n.kind == kind && n.channel == channel).count();
+
+        assert!(
+            list.len() == 5 &&
+            count(KIND_REMINDER, CHANNEL_EMAIL) == 1 &&
+            count(KIND_REMINDER, CHANNEL_WEBHOOK) == 2 &&
+            count(KIND_EXPIRED, CHANNEL_EMAIL) == 1 &&
+            count(KIND_EXPIRED, CHANNEL_WEBHOOK) == 1
+        );
+    }
+
+    #[test]
+    fn plan_no_channels() {
+        let list = notifications_plan(&entry("2030", "6", "15"), &[reminder_on("1", CHANNEL_DEFAULT)], &[], 0, NaiveDateTime::MIN).unwrap();
+
+        assert!(list.is_empty());
+    }
+}
\ No newline at end of file
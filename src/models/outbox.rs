@@ -0,0 +1,142 @@
+/*************************************************************************
+    "task_server"
+    models/outbox.rs
+    10/18/26
+    © Matthew Bentivegna
+*************************************************************************/
+use chrono::NaiveDateTime;
+use serde::{
+    Deserialize,
+    Serialize
+};
+use schemars::JsonSchema;
+use diesel::{
+    Queryable,
+    Insertable,
+    AsChangeset
+};
+use crate::models::user::User;
+
+pub const OUTBOX_PENDING: &str = "pending";
+pub const OUTBOX_SENDING: &str = "sending";
+pub const OUTBOX_SENT: &str = "sent";
+pub const OUTBOX_DEAD: &str = "dead";
+
+/* longest wait between two attempts, regardless of the attempt count */
+const BACKOFF_MAX_SECONDS: i64 = 24 * 60 * 60;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Queryable, Insertable, AsChangeset)]
+#[diesel(table_name = crate::models::schema::outbox)]
+pub struct OutboxMessage {
+    pub id: String,
+    pub notification_id: Option<String>,
+    pub user_id: Option<String>,
+    pub channel: String,
+    pub recipient_name: String,
+    pub recipient_email: String,
+    pub subject: String,
+    pub body: String,
+    pub status: String,
+    pub attempts: i32,
+    pub next_attempt: NaiveDateTime,
+    pub last_error: Option<String>,
+    pub created: NaiveDateTime,
+    pub updated: NaiveDateTime,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct OutboxParams {
+    pub status: Option<String>,
+}
+
+impl OutboxMessage {
+    pub fn new(notification_id: Option<&str>, user: &User, channel: &str, subject: &str, body: &str, now: NaiveDateTime) -> Self {
+        Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            notification_id: notification_id.map(String::from),
+            user_id: Some(user.id.clone()),
+            channel: String::from(channel),
+            recipient_name: user.username.clone(),
+            recipient_email: user.email.clone(),
+            subject: String::from(subject),
+            body: String::from(body),
+            status: String::from(OUTBOX_PENDING),
+            attempts: 0,
+            next_attempt: now,
+            last_error: None,
+            created: now,
+            updated: now,
+        }
+    }
+}
+
+/* only a message that gave up ("dead") or is waiting out a backoff after a failed attempt can be retried */
+pub fn outbox_retryable(message: &OutboxMessage) -> bool {
+    message.status == OUTBOX_DEAD || (message.status == OUTBOX_PENDING && message.attempts > 0)
+}
+
+/* delay before the next attempt, doubling after every failed attempt */
+pub fn outbox_backoff(attempts: i32, base_seconds: u32) -> chrono::Duration {
+    let exponent = (attempts.max(1) - 1).min(30) as u32;
+    let seconds = (base_seconds as i64).saturating_mul(2_i64.pow(exponent));
+
+    chrono::Duration::seconds(seconds.min(BACKOFF_MAX_SECONDS))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backoff_doubles() {
+        assert!(
+            outbox_backoff(1, 60) == chrono::Duration::seconds(60) &&
+            outbox_backoff(2, 60) == chrono::Duration::seconds(120) &&
+            outbox_backoff(4, 60) == chrono::Duration::seconds(480)
+        );
+    }
+
+    #[test]
+    fn backoff_capped() {
+        assert_eq!(outbox_backoff(40, 60), chrono::Duration::seconds(BACKOFF_MAX_SECONDS));
+    }
+
+    fn user() -> User {
+        User {
+            id: String::from("user-1"),
+            username: String::from("user"),
+            password: String::from("password"),
+            email: String::from("user@domain.com"),
+            utc_offset: None,
+        }
+    }
+
+    #[test]
+    fn new_is_pending() {
+        let message = OutboxMessage::new(None, &user(), "email", "subject", "body", NaiveDateTime::MIN);
+
+        assert!(
+            message.status == OUTBOX_PENDING &&
+            message.recipient_email == "user@domain.com" &&
+            message.attempts == 0 &&
+            message.next_attempt == NaiveDateTime::MIN
+        );
+    }
+
+    #[test]
+    fn retryable_states() {
+        let message = |status: &str, attempts: i32| OutboxMessage {
+            status: String::from(status),
+            attempts,
+            ..OutboxMessage::new(None, &user(), "email", "subject", "body", NaiveDateTime::MIN)
+        };
+
+        assert!(
+            outbox_retryable(&message(OUTBOX_DEAD, 5)) &&
+            outbox_retryable(&message(OUTBOX_PENDING, 2)) &&
+            !outbox_retryable(&message(OUTBOX_PENDING, 0)) &&
+            !outbox_retryable(&message(OUTBOX_SENDING, 1)) &&
+            !outbox_retryable(&message(OUTBOX_SENT, 1))
+        );
+    }
+}
\ No newline at end of file
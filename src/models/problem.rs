@@ -0,0 +1,118 @@
+/*************************************************************************
+    "task_server"
+    models/problem.rs
+    10/18/26
+    © Matthew Bentivegna
+*************************************************************************/
+use serde::Serialize;
+
+/* error codes are part of the API: clients match on them, so they must not change once released */
+
+/* request */
+pub const CODE_UNAUTHENTICATED: &str = "unauthenticated";
+pub const CODE_FORBIDDEN: &str = "forbidden";
+pub const CODE_INVALID_BODY: &str = "invalid_body";
+pub const CODE_INVALID_PARAMETER: &str = "invalid_parameter";
+pub const CODE_VALIDATION_FAILED: &str = "validation_failed";
+pub const CODE_UNSUPPORTED_MEDIA_TYPE: &str = "unsupported_media_type";
+pub const CODE_PRECONDITION_FAILED: &str = "precondition_failed";
+pub const CODE_TOO_MANY_OPERATIONS: &str = "too_many_operations";
+pub const CODE_IDEMPOTENCY_KEY_INVALID: &str = "idempotency_key_invalid";
+pub const CODE_IDEMPOTENCY_KEY_REUSED: &str = "idempotency_key_reused";
+pub const CODE_IDEMPOTENCY_KEY_IN_PROGRESS: &str = "idempotency_key_in_progress";
+pub const CODE_MESSAGE_ALREADY_SENT: &str = "message_already_sent";
+pub const CODE_MESSAGE_NOT_RETRYABLE: &str = "message_not_retryable";
+pub const CODE_UPGRADE_REQUIRED: &str = "upgrade_required";
+pub const CODE_INTERNAL_ERROR: &str = "internal_error";
+
+/* missing resources */
+pub const CODE_USER_NOT_FOUND: &str = "user_not_found";
+pub const CODE_ENTRY_NOT_FOUND: &str = "entry_not_found";
+pub const CODE_CHANNEL_NOT_FOUND: &str = "channel_not_found";
+pub const CODE_WEBHOOK_NOT_FOUND: &str = "webhook_not_found";
+pub const CODE_FEED_NOT_FOUND: &str = "feed_not_found";
+pub const CODE_MESSAGE_NOT_FOUND: &str = "message_not_found";
+pub const CODE_REVISION_NOT_FOUND: &str = "revision_not_found";
+pub const CODE_LIST_NOT_FOUND: &str = "list_not_found";
+
+/* fields */
+pub const CODE_INVALID_YEAR: &str = "invalid_year";
+pub const CODE_INVALID_MONTH: &str = "invalid_month";
+pub const CODE_INVALID_DAY: &str = "invalid_day";
+pub const CODE_INVALID_DATE: &str = "invalid_date";
+pub const CODE_DATE_PASSED: &str = "date_passed";
+pub const CODE_TASK_MISSING: &str = "task_missing";
+pub const CODE_INVALID_COMPLETED: &str = "invalid_completed";
+pub const CODE_INVALID_REMINDER: &str = "invalid_reminder";
+pub const CODE_REMINDER_TOO_GREAT: &str = "reminder_too_great";
+pub const CODE_INVALID_REMINDER_CHANNEL: &str = "invalid_reminder_channel";
+pub const CODE_DUPLICATE_REMINDER: &str = "duplicate_reminder";
+pub const CODE_INVALID_TAG: &str = "invalid_tag";
+pub const CODE_TOO_MANY_TAGS: &str = "too_many_tags";
+pub const CODE_INVALID_LIST: &str = "invalid_list";
+pub const CODE_NAME_MISSING: &str = "name_missing";
+pub const CODE_NAME_IN_USE: &str = "name_in_use";
+pub const CODE_INVALID_COLOR: &str = "invalid_color";
+pub const CODE_USERNAME_IN_USE: &str = "username_in_use";
+pub const CODE_EMAIL_IN_USE: &str = "email_in_use";
+pub const CODE_INVALID_EMAIL: &str = "invalid_email";
+pub const CODE_INVALID_UTC_OFFSET: &str = "invalid_utc_offset";
+pub const CODE_INVALID_CHANNEL: &str = "invalid_channel";
+pub const CODE_INVALID_ENABLED: &str = "invalid_enabled";
+pub const CODE_INVALID_URL: &str = "invalid_url";
+pub const CODE_URL_MISSING: &str = "url_missing";
+pub const CODE_URL_NOT_ALLOWED: &str = "url_not_allowed";
+pub const CODE_INVALID_HEADER_NAME: &str = "invalid_header_name";
+pub const CODE_EVENTS_MISSING: &str = "events_missing";
+pub const CODE_INVALID_EVENT: &str = "invalid_event";
+pub const CODE_SECRET_TOO_SHORT: &str = "secret_too_short";
+
+/* one failed check; "pointer" (RFC 6901) names the field of the request body it concerns */
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FieldError {
+    pub pointer: String,
+    pub code: String,
+    pub detail: String,
+}
+
+impl FieldError {
+    pub fn new(pointer: &str, code: &str, detail: &str) -> Self {
+        Self {
+            pointer: String::from(pointer),
+            code: String::from(code),
+            detail: String::from(detail),
+        }
+    }
+}
+
+pub fn field_errors_result(errors: Vec<FieldError>) -> Result<(), Vec<FieldError>> {
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
+/* for places that report a single line of text (CalDAV, import rows) */
+pub fn field_errors_detail(errors: &[FieldError]) -> String {
+    errors
+        .iter()
+        .map(|e| e.detail.as_str())
+        .collect::<Vec<&str>>()
+        .join("; ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detail_joined() {
+        let errors = vec![
+            FieldError::new("/day", CODE_DATE_PASSED, "Date has passed"),
+            FieldError::new("/task", CODE_TASK_MISSING, "No task present"),
+        ];
+
+        assert_eq!(field_errors_detail(&errors), "Date has passed; No task present");
+    }
+}
\ No newline at end of file
@@ -0,0 +1,208 @@
+/*************************************************************************
+    "task_server"
+    models/reminder.rs
+    10/18/26
+    © Matthew Bentivegna
+*************************************************************************/
+use serde::{
+    Deserialize,
+    Serialize
+};
+use schemars::JsonSchema;
+use diesel::{
+    Queryable,
+    Insertable,
+    AsChangeset
+};
+use crate::date::date::Date;
+use crate::models::{
+    channel::{
+        CHANNEL_DEFAULT,
+        DELIVERY_CHANNELS,
+    },
+    problem::{
+        FieldError,
+        field_errors_result,
+        CODE_INVALID_REMINDER,
+        CODE_REMINDER_TOO_GREAT,
+        CODE_INVALID_REMINDER_CHANNEL,
+        CODE_DUPLICATE_REMINDER,
+    },
+};
+
+#[derive(Serialize, Deserialize, Debug, Clone, Queryable, Insertable, AsChangeset, JsonSchema)]
+#[diesel(belongs_to(Entry))]
+#[diesel(table_name = crate::models::schema::entry_reminders)]
+pub struct Reminder {
+    pub id: String,
+    pub entry_id: String,
+    pub offset: String,
+    pub channel: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct ReminderWrite {
+    pub offset: String,
+    pub channel: Option<String>,
+}
+
+impl ReminderWrite {
+    pub fn channel(&self) -> &str {
+        match &self.channel {
+            Some(channel) => channel,
+            None => CHANNEL_DEFAULT,
+        }
+    }
+}
+
+/* converts the legacy single "reminder" field into a reminder list */
+pub fn reminders_from_legacy(reminder: &Option<String>) -> Vec<ReminderWrite> {
+    match reminder {
+        Some(offset) => vec![
+            ReminderWrite {
+                offset: offset.clone(),
+                channel: None,
+            }
+        ],
+        None => vec![],
+    }
+}
+
+pub fn reminders_to_write(reminders: &[Reminder]) -> Vec<ReminderWrite> {
+    reminders
+        .iter()
+        .map(|r| ReminderWrite {
+            offset: r.offset.clone(),
+            channel: Some(r.channel.clone()),
+        })
+        .collect()
+}
+
+/* the legacy "reminder" field mirrors the earliest reminder (largest offset) */
+pub fn reminder_legacy_from_list(reminders: &[ReminderWrite]) -> Option<String> {
+    reminders
+        .iter()
+        .filter_map(|r| r.offset.parse::<i64>().ok().map(|o| (o, r.offset.clone())))
+        .max_by_key(|(o, _)| *o)
+        .map(|(_, s)| s)
+}
+
+/* the due date is None when it is itself invalid; the offset is then only checked for form */
+pub fn reminder_validate(offset: &str, due_date: Option<&Date>, pointer: &str) -> Result<(), FieldError> {
+    let offset = match offset.parse::<i64>() {
+        Ok(r) => r,
+        Err(_) => { return Err(FieldError::new(pointer, CODE_INVALID_REMINDER, "Invalid reminder")); },
+    };
+
+    if let Some(due_date) = due_date {
+        let today = Date::today();
+        let diff = Date::difference(due_date, &today);
+
+        if offset > 0 && offset > diff {
+            return Err(FieldError::new(pointer, CODE_REMINDER_TOO_GREAT, "Reminder too great for due date"));
+        }
+    }
+
+    Ok(())
+}
+
+/* every problem with the list is reported, each pointing at its reminder */
+pub fn reminders_validate(reminders: &[ReminderWrite], due_date: Option<&Date>) -> Result<(), Vec<FieldError>> {
+    let mut errors = vec![];
+
+    for (i, reminder) in reminders.iter().enumerate() {
+        if let Err(e) = reminder_validate(&reminder.offset, due_date, &format!["/reminders/{}/offset", i]) {
+            errors.push(e);
+        }
+
+        if reminder.channel() != CHANNEL_DEFAULT && !DELIVERY_CHANNELS.contains(&reminder.channel()) {
+            errors.push(FieldError::new(&format!["/reminders/{}/channel", i], CODE_INVALID_REMINDER_CHANNEL, "Invalid reminder channel"));
+        }
+
+        if reminders[..i].iter().any(|r| r.offset == reminder.offset && r.channel() == reminder.channel()) {
+            errors.push(FieldError::new(&format!["/reminders/{}", i], CODE_DUPLICATE_REMINDER, "Duplicate reminder"));
+        }
+    }
+
+    field_errors_result(errors)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write(offset: &str, channel: Option<&str>) -> ReminderWrite {
+        ReminderWrite {
+            offset: String::from(offset),
+            channel: channel.map(String::from),
+        }
+    }
+
+    #[test]
+    fn legacy_conversion() {
+        let reminders = reminders_from_legacy(&Some(String::from("3")));
+
+        assert!(
+            reminders.len() == 1 &&
+            reminders[0].offset == "3" &&
+            reminders[0].channel() == CHANNEL_DEFAULT
+        );
+    }
+
+    #[test]
+    fn legacy_from_list_largest() {
+        let reminders = vec![write("1", None), write("7", None), write("0", None)];
+
+        assert_eq!(reminder_legacy_from_list(&reminders), Some(String::from("7")));
+    }
+
+    #[test]
+    fn validate_okay() {
+        let due = Date::new(2080, 1, 1).unwrap();
+        let reminders = vec![write("7", None), write("1", Some("email")), write("1", Some("webhook")), write("0", None)];
+
+        assert!(reminders_validate(&reminders, Some(&due)).is_ok());
+    }
+
+    #[test]
+    fn validate_too_great() {
+        let due = Date::today();
+        let reminders = vec![write("7", None)];
+
+        assert!(reminders_validate(&reminders, Some(&due)).is_err());
+    }
+
+    #[test]
+    fn validate_invalid_channel() {
+        let due = Date::new(2080, 1, 1).unwrap();
+        let reminders = vec![write("1", Some("pigeon"))];
+
+        assert!(reminders_validate(&reminders, Some(&due)).is_err());
+    }
+
+    #[test]
+    fn validate_all_reported() {
+        let due = Date::today();
+        let reminders = vec![write("7", None), write("soon", Some("pigeon")), write("7", None)];
+        let errors = reminders_validate(&reminders, Some(&due)).unwrap_err();
+        let pointers: Vec<&str> = errors.iter().map(|e| e.pointer.as_str()).collect();
+
+        assert_eq!(pointers, vec!["/reminders/0/offset", "/reminders/1/offset", "/reminders/1/channel", "/reminders/2/offset", "/reminders/2"]);
+    }
+
+    #[test]
+    fn validate_without_date() {
+        let reminders = vec![write("700", None), write("x", None)];
+        let errors = reminders_validate(&reminders, None).unwrap_err();
+
+        assert!(errors.len() == 1 && errors[0].code == CODE_INVALID_REMINDER);
+    }
+
+    #[test]
+    fn validate_duplicate() {
+        let due = Date::new(2080, 1, 1).unwrap();
+        let reminders = vec![write("1", None), write("1", Some("default"))];
+
+        assert!(reminders_validate(&reminders, Some(&due)).is_err());
+    }
+}
\ No newline at end of file
@@ -0,0 +1,353 @@
+/*************************************************************************
+    "task_server"
+    models/revision.rs
+    10/18/26
+    © Matthew Bentivegna
+*************************************************************************/
+use chrono::NaiveDateTime;
+use serde::Serialize;
+use serde_json::{
+    Map,
+    Value,
+};
+use diesel::{
+    Queryable,
+    Insertable,
+};
+use schemars::JsonSchema;
+use crate::models::{
+    entry::{
+        EntryPatch,
+        EntryRead,
+    },
+    reminder::{
+        Reminder,
+        ReminderWrite,
+        reminders_to_write,
+    },
+    list::LIST_INBOX,
+};
+
+pub const ACTION_CREATED: &str = "created";
+pub const ACTION_UPDATED: &str = "updated";
+pub const ACTION_DELETED: &str = "deleted";
+pub const ACTION_RESTORED: &str = "restored";
+
+/* the actor recorded for changes the server makes on its own (e.g. marking an entry expired) */
+pub const ACTOR_TIMER: &str = "timer";
+
+/* the entry fields a revision records, followed by its reminder list and tags */
+pub const REVISION_FIELDS: [&str; 11] = ["year", "month", "day", "task", "reminder", "completed", "expired", "deleted_at", "list_id", "reminders", "tags"];
+
+/* one change to an entry: who made it, and the old and new value of every field it changed (as JSON objects) */
+#[derive(Serialize, Debug, Clone, Queryable)]
+#[diesel(table_name = crate::models::schema::entry_revisions)]
+pub struct EntryRevision {
+    pub id: i64,
+    pub entry_id: String,
+    pub user_id: String,
+    pub actor: String,
+    pub action: String,
+    pub fields: String,
+    pub old_values: String,
+    pub new_values: String,
+    pub created: NaiveDateTime,
+}
+
+/* the id is assigned by the database */
+#[derive(Debug, Clone, Insertable)]
+#[diesel(table_name = crate::models::schema::entry_revisions)]
+pub struct EntryRevisionWrite {
+    pub entry_id: String,
+    pub user_id: String,
+    pub actor: String,
+    pub action: String,
+    pub fields: String,
+    pub old_values: String,
+    pub new_values: String,
+    pub created: NaiveDateTime,
+}
+
+#[derive(Debug, PartialEq, Serialize, JsonSchema)]
+pub struct RevisionChange {
+    pub field: String,
+    pub old: Value,
+    pub new: Value,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct RevisionRead {
+    pub revision: i64,
+    pub entry_id: String,
+    pub actor: String,
+    pub action: String,
+    #[schemars(with = "String")]
+    pub created: NaiveDateTime,
+    pub changes: Vec<RevisionChange>,
+}
+
+impl EntryRevision {
+    pub fn fields(&self) -> Vec<String> {
+        self.fields
+            .split(',')
+            .filter(|f| !f.is_empty())
+            .map(String::from)
+            .collect()
+    }
+
+    fn values(values: &str) -> Map<String, Value> {
+        serde_json::from_str(values).unwrap_or_default()
+    }
+}
+
+impl RevisionRead {
+    pub fn new(revision: &EntryRevision) -> Self {
+        let old = EntryRevision::values(&revision.old_values);
+        let new = EntryRevision::values(&revision.new_values);
+
+        Self {
+            revision: revision.id,
+            entry_id: revision.entry_id.clone(),
+            actor: revision.actor.clone(),
+            action: revision.action.clone(),
+            created: revision.created,
+            changes: revision
+                .fields()
+                .into_iter()
+                .map(|field| RevisionChange {
+                    old: old.get(&field).cloned().unwrap_or(Value::Null),
+                    new: new.get(&field).cloned().unwrap_or(Value::Null),
+                    field,
+                })
+                .collect(),
+        }
+    }
+}
+
+fn entry_revision_values(read: &EntryRead) -> Map<String, Value> {
+    let entry = &read.entry;
+    let mut tags = read.tags.clone();
+    let mut values = Map::new();
+
+    tags.sort();
+
+    values.insert(String::from("year"), Value::from(entry.year.clone()));
+    values.insert(String::from("month"), Value::from(entry.month.clone()));
+    values.insert(String::from("day"), Value::from(entry.day.clone()));
+    values.insert(String::from("task"), Value::from(entry.task.clone()));
+    values.insert(String::from("reminder"), Value::from(entry.reminder.clone()));
+    values.insert(String::from("completed"), Value::from(entry.completed.clone()));
+    values.insert(String::from("expired"), Value::from(entry.expired.clone()));
+    values.insert(String::from("deleted_at"), serde_json::to_value(entry.deleted_at).unwrap_or_default());
+    values.insert(String::from("list_id"), Value::from(entry.list_id.clone()));
+    values.insert(String::from("reminders"), revision_reminders(&read.reminders));
+    values.insert(String::from("tags"), Value::from(tags));
+
+    values
+}
+
+/* offsets and channels only, in a fixed order (the list is stored again, under new ids, on every write) */
+fn revision_reminders(reminders: &[Reminder]) -> Value {
+    let mut list = reminders_to_write(reminders);
+
+    list.sort_by_key(|r| (r.offset.parse::<i64>().unwrap_or_default(), String::from(r.channel())));
+    serde_json::to_value(list).unwrap_or_default()
+}
+
+/* the revision for a change from "before" (None for a new entry) to "after"; None when no recorded field changed */
+pub fn entry_revision(before: Option<&EntryRead>, after: &EntryRead, actor: &str, action: &str, now: NaiveDateTime) -> Option<EntryRevisionWrite> {
+    let old = before.map(entry_revision_values).unwrap_or_default();
+    let new = entry_revision_values(after);
+    let fields: Vec<&str> = REVISION_FIELDS
+        .into_iter()
+        .filter(|f| before.is_none() || old.get(*f) != new.get(*f))
+        .collect();
+
+    if fields.is_empty() {
+        return None;
+    }
+
+    let pick = |values: &Map<String, Value>| -> String {
+        let picked: Map<String, Value> = fields
+            .iter()
+            .filter_map(|f| values.get(*f).map(|v| (String::from(*f), v.clone())))
+            .collect();
+        Value::Object(picked).to_string()
+    };
+
+    Some(EntryRevisionWrite {
+        entry_id: after.entry.id.clone(),
+        user_id: after.entry.user_id.clone(),
+        actor: String::from(actor),
+        action: String::from(action),
+        fields: fields.join(","),
+        old_values: pick(&old),
+        new_values: pick(&new),
+        created: now,
+    })
+}
+
+/* the patch that takes the entry back to how it was just after a revision, by undoing the revisions since (newest first) */
+pub fn entry_revert_patch(read: &EntryRead, later: &[EntryRevision]) -> EntryPatch {
+    let current = entry_revision_values(read);
+    let mut state = current.clone();
+
+    for revision in later.iter().rev() {
+        let old = EntryRevision::values(&revision.old_values);
+
+        for field in revision.fields() {
+            state.insert(field.clone(), old.get(&field).cloned().unwrap_or(Value::Null));
+        }
+    }
+
+    let changed = |field: &str| -> Option<String> {
+        match state.get(field) {
+            Some(value) if current.get(field) != Some(value) => value.as_str().map(String::from),
+            _ => None,
+        }
+    };
+
+    let restored = |field: &str| -> Option<Value> {
+        state.get(field).filter(|value| current.get(field) != Some(*value)).cloned()
+    };
+
+    /* revisions recorded before the reminder list was only have the legacy "reminder"; a cleared one clears the list, as in a merge patch */
+    let (reminder, reminders) = match (restored("reminders"), changed("reminder")) {
+        (Some(list), _) => (None, Some(serde_json::from_value::<Vec<ReminderWrite>>(list).unwrap_or_default())),
+        (None, Some(reminder)) => (Some(reminder), None),
+        (None, None) if state.get("reminder") != current.get("reminder") => (None, Some(Vec::<ReminderWrite>::new())),
+        (None, None) => (None, None),
+    };
+
+    /* an entry taken out of its list goes back to the inbox */
+    let list_id = match state.get("list_id") {
+        Some(value) if current.get("list_id") != Some(value) => Some(value.as_str().unwrap_or(LIST_INBOX).to_string()),
+        _ => None,
+    };
+
+    EntryPatch {
+        year: changed("year"),
+        month: changed("month"),
+        day: changed("day"),
+        task: changed("task"),
+        reminder,
+        reminders,
+        completed: changed("completed"),
+        tags: restored("tags").map(|tags| serde_json::from_value::<Vec<String>>(tags).unwrap_or_default()),
+        list_id,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::entry::{
+        Entry,
+        entry_fixture,
+    };
+
+    /* the legacy reminder is also the entry's one reminder, on the default channel */
+    fn entry(day: &str, reminder: Option<&str>) -> EntryRead {
+        let reminders = reminder
+            .map(|offset| vec![Reminder { id: String::from(offset), entry_id: String::from("entry-1"), offset: String::from(offset), channel: String::from("default") }])
+            .unwrap_or_default();
+        let entry = Entry {
+            username: String::from("bob"),
+            day: String::from(day),
+            reminder: reminder.map(String::from),
+            ..entry_fixture()
+        };
+
+        EntryRead::new(entry, reminders, vec![])
+    }
+
+    fn stored(id: i64, write: EntryRevisionWrite) -> EntryRevision {
+        EntryRevision {
+            id,
+            entry_id: write.entry_id,
+            user_id: write.user_id,
+            actor: write.actor,
+            action: write.action,
+            fields: write.fields,
+            old_values: write.old_values,
+            new_values: write.new_values,
+            created: write.created,
+        }
+    }
+
+    #[test]
+    fn created() {
+        let revision = entry_revision(None, &entry("15", None), "bob", ACTION_CREATED, NaiveDateTime::MIN).unwrap();
+
+        assert!(revision.fields == REVISION_FIELDS.join(",") && revision.old_values == "{}");
+    }
+
+    #[test]
+    fn changed_fields() {
+        let revision = entry_revision(Some(&entry("15", None)), &entry("20", Some("1")), "admin", ACTION_UPDATED, NaiveDateTime::MIN).unwrap();
+        let read = RevisionRead::new(&stored(3, revision));
+
+        assert_eq!(read.changes, vec![
+            RevisionChange { field: String::from("day"), old: Value::from("15"), new: Value::from("20") },
+            RevisionChange { field: String::from("reminder"), old: Value::Null, new: Value::from("1") },
+            RevisionChange { field: String::from("reminders"), old: serde_json::json!([]), new: serde_json::json!([{"offset": "1", "channel": "default"}]) },
+        ]);
+        assert!(read.revision == 3 && read.actor == "admin");
+    }
+
+    #[test]
+    fn unchanged() {
+        assert!(entry_revision(Some(&entry("15", None)), &entry("15", None), "bob", ACTION_UPDATED, NaiveDateTime::MIN).is_none());
+    }
+
+    #[test]
+    fn revert() {
+        let first = stored(1, entry_revision(Some(&entry("15", None)), &entry("20", Some("1")), "bob", ACTION_UPDATED, NaiveDateTime::MIN).unwrap());
+        let second = stored(2, entry_revision(Some(&entry("20", Some("1"))), &entry("25", Some("1")), "bob", ACTION_UPDATED, NaiveDateTime::MIN).unwrap());
+
+        /* back to just after the first revision */
+        let patch = entry_revert_patch(&entry("25", Some("1")), std::slice::from_ref(&second));
+        assert!(patch.day.as_deref() == Some("20") && patch.reminder.is_none() && patch.reminders.is_none() && patch.task.is_none());
+
+        /* back to before both, which clears the reminder */
+        let patch = entry_revert_patch(&entry("25", Some("1")), &[first, second]);
+        assert!(patch.day.as_deref() == Some("15") && patch.reminders.is_some_and(|r| r.is_empty()));
+    }
+
+    #[test]
+    fn revert_reminders_and_tags() {
+        let mut before = entry("15", Some("7"));
+        before.reminders[0].channel = String::from("email");
+        before.tags = vec![String::from("home"), String::from("bills")];
+        let after = entry("15", Some("1"));
+        let revision = stored(1, entry_revision(Some(&before), &after, "bob", ACTION_UPDATED, NaiveDateTime::MIN).unwrap());
+
+        assert_eq!(revision.fields(), vec!["reminder", "reminders", "tags"]);
+
+        let patch = entry_revert_patch(&after, &[revision]);
+        assert!(
+            patch.reminder.is_none() &&
+            patch.reminders == Some(vec![ReminderWrite { offset: String::from("7"), channel: Some(String::from("email")) }]) &&
+            patch.tags == Some(vec![String::from("bills"), String::from("home")])
+        );
+    }
+
+    #[test]
+    fn revert_legacy_reminder() {
+        /* recorded before revisions kept the reminder list */
+        let revision = EntryRevision {
+            id: 1,
+            entry_id: String::from("entry-1"),
+            user_id: String::from("user-1"),
+            actor: String::from("bob"),
+            action: String::from(ACTION_UPDATED),
+            fields: String::from("reminder"),
+            old_values: String::from(r#"{"reminder":"3"}"#),
+            new_values: String::from(r#"{"reminder":"1"}"#),
+            created: NaiveDateTime::MIN,
+        };
+        let patch = entry_revert_patch(&entry("15", Some("1")), &[revision]);
+
+        assert!(patch.reminder.as_deref() == Some("3") && patch.reminders.is_none() && patch.tags.is_none());
+    }
+}
\ No newline at end of file
@@ -1,5 +1,17 @@
 // @generated automatically by Diesel CLI.
 
+diesel::table! {
+    audit_log (id) {
+        id -> Int8,
+        action -> Text,
+        actor -> Nullable<Text>,
+        target -> Nullable<Text>,
+        ip -> Nullable<Text>,
+        detail -> Nullable<Text>,
+        created -> Timestamp,
+    }
+}
+
 diesel::table! {
     entries (id) {
         #[max_length = 255]
@@ -14,6 +26,167 @@ diesel::table! {
         created -> Timestamp,
         updated -> Timestamp,
         user_id -> Text,
+        completed -> Text,
+        deleted_at -> Nullable<Timestamp>,
+        #[max_length = 255]
+        list_id -> Nullable<Varchar>,
+    }
+}
+
+diesel::table! {
+    entry_changes (entry_id) {
+        #[max_length = 255]
+        entry_id -> Varchar,
+        user_id -> Text,
+        seq -> Int8,
+        deleted -> Bool,
+        changed -> Timestamp,
+    }
+}
+
+diesel::table! {
+    entry_events (id) {
+        id -> Int8,
+        #[max_length = 255]
+        entry_id -> Varchar,
+        user_id -> Text,
+        event -> Text,
+        payload -> Text,
+        created -> Timestamp,
+        previous_list -> Nullable<Text>,
+    }
+}
+
+diesel::table! {
+    entry_reminders (id) {
+        #[max_length = 255]
+        id -> Varchar,
+        #[max_length = 255]
+        entry_id -> Varchar,
+        offset -> Text,
+        channel -> Text,
+    }
+}
+
+diesel::table! {
+    entry_revisions (id) {
+        id -> Int8,
+        #[max_length = 255]
+        entry_id -> Varchar,
+        user_id -> Text,
+        actor -> Text,
+        action -> Text,
+        fields -> Text,
+        old_values -> Text,
+        new_values -> Text,
+        created -> Timestamp,
+    }
+}
+
+diesel::table! {
+    entry_tags (entry_id, tag_id) {
+        #[max_length = 255]
+        entry_id -> Varchar,
+        #[max_length = 255]
+        tag_id -> Varchar,
+    }
+}
+
+diesel::table! {
+    feeds (id) {
+        #[max_length = 255]
+        id -> Varchar,
+        user_id -> Text,
+        token -> Text,
+        created -> Timestamp,
+    }
+}
+
+diesel::table! {
+    idempotency_keys (id) {
+        #[max_length = 255]
+        id -> Varchar,
+        scope -> Text,
+        idempotency_key -> Text,
+        request_hash -> Text,
+        response_status -> Nullable<Int4>,
+        response_headers -> Nullable<Text>,
+        response_body -> Nullable<Text>,
+        created -> Timestamp,
+    }
+}
+
+diesel::table! {
+    lists (id) {
+        #[max_length = 255]
+        id -> Varchar,
+        user_id -> Text,
+        name -> Text,
+        color -> Text,
+        default_reminder -> Nullable<Text>,
+        sort_order -> Int4,
+        created -> Timestamp,
+        updated -> Timestamp,
+    }
+}
+
+diesel::table! {
+    notifications (id) {
+        #[max_length = 255]
+        id -> Varchar,
+        dedupe_key -> Text,
+        #[max_length = 255]
+        entry_id -> Varchar,
+        user_id -> Text,
+        kind -> Text,
+        channel -> Text,
+        scheduled -> Timestamp,
+        status -> Text,
+        created -> Timestamp,
+        processed -> Nullable<Timestamp>,
+    }
+}
+
+diesel::table! {
+    outbox (id) {
+        #[max_length = 255]
+        id -> Varchar,
+        #[max_length = 255]
+        notification_id -> Nullable<Varchar>,
+        user_id -> Nullable<Text>,
+        channel -> Text,
+        recipient_name -> Text,
+        recipient_email -> Text,
+        subject -> Text,
+        body -> Text,
+        status -> Text,
+        attempts -> Int4,
+        next_attempt -> Timestamp,
+        last_error -> Nullable<Text>,
+        created -> Timestamp,
+        updated -> Timestamp,
+    }
+}
+
+diesel::table! {
+    tags (id) {
+        #[max_length = 255]
+        id -> Varchar,
+        user_id -> Text,
+        name -> Text,
+        created -> Timestamp,
+    }
+}
+
+diesel::table! {
+    user_channels (id) {
+        #[max_length = 255]
+        id -> Varchar,
+        user_id -> Text,
+        channel -> Text,
+        enabled -> Text,
+        url -> Nullable<Text>,
+        headers -> Nullable<Text>,
     }
 }
 
@@ -28,9 +201,60 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    webhook_deliveries (id) {
+        #[max_length = 255]
+        id -> Varchar,
+        #[max_length = 255]
+        webhook_id -> Varchar,
+        event -> Text,
+        payload -> Text,
+        status -> Text,
+        attempts -> Int4,
+        next_attempt -> Timestamp,
+        response_status -> Nullable<Int4>,
+        last_error -> Nullable<Text>,
+        created -> Timestamp,
+        updated -> Timestamp,
+    }
+}
+
+diesel::table! {
+    webhooks (id) {
+        #[max_length = 255]
+        id -> Varchar,
+        user_id -> Text,
+        url -> Text,
+        events -> Text,
+        secret -> Text,
+        created -> Timestamp,
+    }
+}
+
+diesel::joinable!(entries -> lists (list_id));
 diesel::joinable!(entries -> users (user_id));
+diesel::joinable!(entry_reminders -> entries (entry_id));
+diesel::joinable!(entry_tags -> entries (entry_id));
+diesel::joinable!(entry_tags -> tags (tag_id));
+diesel::joinable!(notifications -> entries (entry_id));
+diesel::joinable!(webhook_deliveries -> webhooks (webhook_id));
 
 diesel::allow_tables_to_appear_in_same_query!(
+    audit_log,
     entries,
+    entry_changes,
+    entry_events,
+    entry_reminders,
+    entry_revisions,
+    entry_tags,
+    feeds,
+    idempotency_keys,
+    lists,
+    notifications,
+    outbox,
+    tags,
+    user_channels,
     users,
+    webhook_deliveries,
+    webhooks,
 );
@@ -0,0 +1,136 @@
+/*************************************************************************
+    "task_server"
+    models/socket.rs
+    10/18/26
+    © Matthew Bentivegna
+*************************************************************************/
+use serde::{
+    Deserialize,
+    Serialize
+};
+use crate::models::{
+    entry::EntryRead,
+    batch::BatchOperation,
+    entry_event::EntryEvent,
+};
+
+pub const MESSAGE_RESULT: &str = "result";
+pub const MESSAGE_ERROR: &str = "error";
+pub const MESSAGE_EVENT: &str = "event";
+
+/* the command that picks the lists whose events the socket is sent */
+pub const OP_SUBSCRIBE: &str = "subscribe";
+
+/* a command is a batch operation ("op", "id", "entry"), with the client's own id to match it to its reply; a subscribe has "lists"
+   instead (list ids or "inbox", none for every list) */
+#[derive(Debug, Deserialize)]
+pub struct SocketCommand {
+    pub request_id: Option<String>,
+    #[serde(flatten)]
+    pub operation: BatchOperation,
+    #[serde(default)]
+    pub lists: Option<Vec<String>>,
+}
+
+/* the reply to one command: the stored entry (none for a delete), or a problem document */
+#[derive(Debug, Serialize)]
+pub struct SocketReply {
+    #[serde(rename = "type")]
+    pub message_type: String,
+    pub request_id: Option<String>,
+    pub status: u16,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub entry: Option<EntryRead>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<serde_json::Value>,
+}
+
+impl SocketReply {
+    pub fn result(request_id: Option<String>, status: u16, entry: Option<EntryRead>) -> Self {
+        Self {
+            message_type: String::from(MESSAGE_RESULT),
+            request_id,
+            status,
+            entry,
+            error: None,
+        }
+    }
+
+    pub fn error(request_id: Option<String>, status: u16, problem: &str) -> Self {
+        Self {
+            message_type: String::from(MESSAGE_ERROR),
+            request_id,
+            status,
+            entry: None,
+            error: serde_json::from_str(problem).ok(),
+        }
+    }
+}
+
+/* the webhook payload ("event", "timestamp", "data"), with the message type and the event id */
+pub fn socket_event_message(event: &EntryEvent) -> String {
+    let mut message = match serde_json::from_str::<serde_json::Value>(&event.payload) {
+        Ok(serde_json::Value::Object(payload)) => payload,
+        _ => serde_json::Map::new(),
+    };
+
+    message.insert(String::from("type"), serde_json::json!(MESSAGE_EVENT));
+    message.insert(String::from("id"), serde_json::json!(event.id));
+
+    serde_json::Value::Object(message).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDateTime;
+
+    #[test]
+    fn command() {
+        let command: SocketCommand = serde_json::from_str(r#"{"request_id": "r1", "op": "patch", "id": "entry-1", "entry": {"completed": "true"}}"#).unwrap();
+
+        assert!(
+            command.request_id.as_deref() == Some("r1") &&
+            command.operation.op == "patch" &&
+            command.operation.id.as_deref() == Some("entry-1") &&
+            command.operation.entry.is_some()
+        );
+    }
+
+    #[test]
+    fn subscribe_command() {
+        let command: SocketCommand = serde_json::from_str(r#"{"request_id": "r2", "op": "subscribe", "lists": ["list-1", "inbox"]}"#).unwrap();
+        let every: SocketCommand = serde_json::from_str(r#"{"op": "subscribe"}"#).unwrap();
+
+        assert!(
+            command.operation.op == OP_SUBSCRIBE &&
+            command.lists == Some(vec![String::from("list-1"), String::from("inbox")]) &&
+            every.lists.is_none()
+        );
+    }
+
+    #[test]
+    fn replies() {
+        let result = serde_json::to_value(SocketReply::result(Some(String::from("r1")), 204, None)).unwrap();
+        let error = serde_json::to_value(SocketReply::error(None, 404, r#"{"status": 404, "code": "entry_not_found"}"#)).unwrap();
+
+        assert_eq!(result, serde_json::json!({ "type": "result", "request_id": "r1", "status": 204 }));
+        assert_eq!(error, serde_json::json!({ "type": "error", "request_id": null, "status": 404, "error": { "status": 404, "code": "entry_not_found" } }));
+    }
+
+    #[test]
+    fn event_message() {
+        let event = EntryEvent {
+            id: 7,
+            entry_id: String::from("1"),
+            user_id: String::from("u"),
+            event: String::from("entry.deleted"),
+            payload: String::from(r#"{"event": "entry.deleted", "timestamp": "t", "data": {}}"#),
+            created: NaiveDateTime::MIN,
+            previous_list: None,
+        };
+        let message: serde_json::Value = serde_json::from_str(&socket_event_message(&event)).unwrap();
+
+        assert_eq!(message, serde_json::json!({ "type": "event", "id": 7, "event": "entry.deleted", "timestamp": "t", "data": {} }));
+    }
+}
\ No newline at end of file
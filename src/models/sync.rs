@@ -0,0 +1,138 @@
+/*************************************************************************
+    "task_server"
+    models/sync.rs
+    10/18/26
+    © Matthew Bentivegna
+*************************************************************************/
+use chrono::NaiveDateTime;
+use serde::{
+    Deserialize,
+    Serialize
+};
+use diesel::{
+    Queryable,
+    Insertable,
+};
+use schemars::JsonSchema;
+use crate::models::entry::EntryRead;
+
+/* changes per sync response; a client with more to fetch syncs again with the new token */
+pub const SYNC_PAGE_SIZE: i64 = 500;
+
+/* the latest change to each entry, numbered from one sequence so every change sorts after the ones before it; a deleted entry keeps its row as a tombstone */
+#[derive(Serialize, Debug, Clone, Queryable)]
+#[diesel(table_name = crate::models::schema::entry_changes)]
+pub struct EntryChange {
+    pub entry_id: String,
+    pub user_id: String,
+    pub seq: i64,
+    pub deleted: bool,
+    pub changed: NaiveDateTime,
+}
+
+/* the sequence number is assigned by the database */
+#[derive(Debug, Clone, Insertable)]
+#[diesel(table_name = crate::models::schema::entry_changes)]
+pub struct EntryChangeWrite {
+    pub entry_id: String,
+    pub user_id: String,
+    pub deleted: bool,
+    pub changed: NaiveDateTime,
+}
+
+impl EntryChangeWrite {
+    pub fn new(entry_id: &str, user_id: &str, deleted: bool, now: NaiveDateTime) -> Self {
+        Self {
+            entry_id: String::from(entry_id),
+            user_id: String::from(user_id),
+            deleted,
+            changed: now,
+        }
+    }
+}
+
+#[derive(Debug, Default, Deserialize, JsonSchema)]
+pub struct SyncParams {
+    pub since: Option<String>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct Tombstone {
+    pub id: String,
+    #[schemars(with = "String")]
+    pub deleted: NaiveDateTime,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct SyncResponse {
+    pub entries: Vec<EntryRead>,
+    pub deleted: Vec<Tombstone>,
+    pub token: String,
+    pub more: bool,
+}
+
+/* the token is the last change sequence number the client has seen; it is opaque to clients */
+pub fn sync_token(seq: i64) -> String {
+    seq.to_string()
+}
+
+/* no token is a full sync */
+pub fn sync_token_parse(since: Option<&str>) -> Result<i64, String> {
+    match since {
+        None | Some("") => Ok(0),
+        Some(token) => token.parse::<i64>().ok().filter(|seq| *seq >= 0).ok_or(String::from("Invalid sync token")),
+    }
+}
+
+/* the changes split into the entries to fetch and the tombstones, with the token for the next sync */
+pub fn sync_changes(since: i64, changes: &[EntryChange]) -> (Vec<String>, Vec<Tombstone>, String) {
+    let changed = changes.iter().filter(|c| !c.deleted).map(|c| c.entry_id.clone()).collect();
+    let deleted = changes
+        .iter()
+        .filter(|c| c.deleted)
+        .map(|c| Tombstone { id: c.entry_id.clone(), deleted: c.changed })
+        .collect();
+    let last = changes.last().map(|c| c.seq).unwrap_or(since);
+
+    (changed, deleted, sync_token(last))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn change(entry_id: &str, seq: i64, deleted: bool) -> EntryChange {
+        EntryChange {
+            entry_id: String::from(entry_id),
+            user_id: String::from("u"),
+            seq,
+            deleted,
+            changed: NaiveDateTime::MIN,
+        }
+    }
+
+    #[test]
+    fn token_parse() {
+        assert!(
+            sync_token_parse(None) == Ok(0) &&
+            sync_token_parse(Some("")) == Ok(0) &&
+            sync_token_parse(Some(&sync_token(42))) == Ok(42) &&
+            sync_token_parse(Some("-1")).is_err() &&
+            sync_token_parse(Some("abc")).is_err()
+        );
+    }
+
+    #[test]
+    fn changes() {
+        let (changed, deleted, token) = sync_changes(10, &[change("a", 11, false), change("b", 12, true), change("c", 15, false)]);
+
+        assert!(changed == vec!["a", "c"] && deleted.len() == 1 && deleted[0].id == "b" && token == "15");
+    }
+
+    #[test]
+    fn no_changes() {
+        let (changed, deleted, token) = sync_changes(10, &[]);
+
+        assert!(changed.is_empty() && deleted.is_empty() && token == "10");
+    }
+}
\ No newline at end of file
@@ -0,0 +1,131 @@
+/*************************************************************************
+    "task_server"
+    models/tag.rs
+    10/18/26
+    © Matthew Bentivegna
+*************************************************************************/
+use chrono::NaiveDateTime;
+use serde::Serialize;
+use diesel::{
+    Queryable,
+    Insertable,
+};
+use schemars::JsonSchema;
+use crate::models::problem::{
+    FieldError,
+    field_errors_result,
+    CODE_INVALID_TAG,
+    CODE_TOO_MANY_TAGS,
+};
+
+pub const TAG_NAME_MAX: usize = 32;
+pub const TAGS_MAX: usize = 20;
+
+/* a label one user puts on their entries; names are unique per user, so "Work" on two entries is one tag */
+#[derive(Serialize, Debug, Clone, Queryable, Insertable)]
+#[diesel(table_name = crate::models::schema::tags)]
+pub struct Tag {
+    pub id: String,
+    pub user_id: String,
+    pub name: String,
+    pub created: NaiveDateTime,
+}
+
+#[derive(Debug, Clone, Insertable)]
+#[diesel(table_name = crate::models::schema::entry_tags)]
+pub struct EntryTag {
+    pub entry_id: String,
+    pub tag_id: String,
+}
+
+/* a tag, and how many of the user's entries (outside the trash) carry it */
+#[derive(Debug, PartialEq, Serialize, JsonSchema)]
+pub struct TagRead {
+    pub name: String,
+    pub count: i64,
+}
+
+/* names are compared trimmed and in lower case, so "Work" and " work" are one tag */
+pub fn tag_normalize(name: &str) -> String {
+    name.trim().to_lowercase()
+}
+
+/* normalized, in the order given, without repeats */
+pub fn tags_normalize(names: &[String]) -> Vec<String> {
+    let mut list: Vec<String> = vec![];
+
+    for name in names.iter().map(|n| tag_normalize(n)) {
+        if !list.contains(&name) {
+            list.push(name);
+        }
+    }
+
+    list
+}
+
+/* a comma-separated list from a query string, e.g. "?tag=work,home" */
+pub fn tags_from_param(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(tag_normalize)
+        .filter(|n| !n.is_empty())
+        .collect()
+}
+
+pub fn tags_validate(names: &[String]) -> Result<(), Vec<FieldError>> {
+    let mut errors = vec![];
+
+    for (i, name) in names.iter().enumerate() {
+        let name = tag_normalize(name);
+
+        if name.is_empty() || name.chars().count() > TAG_NAME_MAX || name.contains(',') {
+            errors.push(FieldError::new(&format!["/tags/{}", i], CODE_INVALID_TAG, &format!["Invalid tag (1 to {} characters, no commas)", TAG_NAME_MAX]));
+        }
+    }
+
+    if tags_normalize(names).len() > TAGS_MAX {
+        errors.push(FieldError::new("/tags", CODE_TOO_MANY_TAGS, &format!["Too many tags (at most {})", TAGS_MAX]));
+    }
+
+    field_errors_result(errors)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(list: &[&str]) -> Vec<String> {
+        list.iter().map(|n| String::from(*n)).collect()
+    }
+
+    #[test]
+    fn normalize() {
+        assert_eq!(tags_normalize(&names(&["Work", " home ", "work"])), names(&["work", "home"]));
+    }
+
+    #[test]
+    fn from_param() {
+        assert_eq!(tags_from_param("Work, home,,"), names(&["work", "home"]));
+    }
+
+    #[test]
+    fn validate() {
+        let long = "x".repeat(TAG_NAME_MAX + 1);
+        let errors = tags_validate(&names(&["work", " ", "a,b", &long])).unwrap_err();
+        let pointers: Vec<&str> = errors.iter().map(|e| e.pointer.as_str()).collect();
+
+        assert!(tags_validate(&names(&["Work", "home"])).is_ok());
+        assert_eq!(pointers, vec!["/tags/1", "/tags/2", "/tags/3"]);
+    }
+
+    #[test]
+    fn validate_too_many() {
+        let many: Vec<String> = (0..=TAGS_MAX).map(|i| format!["tag{}", i]).collect();
+        let repeated = vec![String::from("work"); TAGS_MAX + 1];
+
+        assert!(
+            tags_validate(&many).unwrap_err()[0].code == CODE_TOO_MANY_TAGS &&
+            tags_validate(&repeated).is_ok()
+        );
+    }
+}
\ No newline at end of file
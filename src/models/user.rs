@@ -8,13 +8,14 @@ use serde::{
     Deserialize,
     Serialize
 };
+use schemars::JsonSchema;
 use diesel::{
     Queryable,
     Insertable,
     AsChangeset
 };
 
-#[derive(Serialize, Deserialize, Debug, Clone, Queryable, Insertable, AsChangeset)]
+#[derive(Serialize, Deserialize, Debug, Clone, Queryable, Insertable, AsChangeset, JsonSchema)]
 #[diesel(table_name = crate::models::schema::users)]
 pub struct User {
     #[serde(default)]
@@ -25,8 +26,34 @@ pub struct User {
     pub utc_offset: Option<String>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
 pub struct UserPatch {
     pub password: Option<String>,
     pub utc_offset: Option<String>,
 }
+
+/* the fields a merge-patch or json-patch document applies to; "utc_offset" can be cleared with null */
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UserDocument {
+    pub password: String,
+    pub utc_offset: Option<String>,
+}
+
+impl UserDocument {
+    pub fn new(user: &User) -> Self {
+        Self {
+            password: user.password.clone(),
+            utc_offset: user.utc_offset.clone(),
+        }
+    }
+}
+
+pub fn user_from_document(user: &User, document: serde_json::Value) -> Result<User, String> {
+    let document: UserDocument = serde_json::from_value(document).map_err(|e| format!["Invalid user: {}", e])?;
+
+    Ok(User {
+        password: document.password,
+        utc_offset: document.utc_offset,
+        ..user.clone()
+    })
+}
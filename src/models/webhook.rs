@@ -0,0 +1,221 @@
+/*************************************************************************
+    "task_server"
+    models/webhook.rs
+    10/18/26
+    © Matthew Bentivegna
+*************************************************************************/
+use chrono::NaiveDateTime;
+use serde::{
+    Deserialize,
+    Serialize
+};
+use schemars::JsonSchema;
+use diesel::{
+    Queryable,
+    Insertable,
+    AsChangeset
+};
+use crate::models::problem::{
+    FieldError,
+    field_errors_result,
+    CODE_INVALID_URL,
+    CODE_EVENTS_MISSING,
+    CODE_INVALID_EVENT,
+    CODE_SECRET_TOO_SHORT,
+};
+
+pub const EVENT_CREATED: &str = "entry.created";
+pub const EVENT_UPDATED: &str = "entry.updated";
+pub const EVENT_DELETED: &str = "entry.deleted";
+pub const EVENT_COMPLETED: &str = "entry.completed";
+pub const EVENT_EXPIRED: &str = "entry.expired";
+pub const EVENT_RESTORED: &str = "entry.restored";
+
+pub const EVENTS: [&str; 6] = [EVENT_CREATED, EVENT_UPDATED, EVENT_DELETED, EVENT_COMPLETED, EVENT_EXPIRED, EVENT_RESTORED];
+
+pub const DELIVERY_PENDING: &str = "pending";
+pub const DELIVERY_SENDING: &str = "sending";
+pub const DELIVERY_DELIVERED: &str = "delivered";
+pub const DELIVERY_DEAD: &str = "dead";
+
+#[derive(Serialize, Deserialize, Debug, Clone, Queryable, Insertable, AsChangeset)]
+#[diesel(table_name = crate::models::schema::webhooks)]
+pub struct WebhookSubscription {
+    pub id: String,
+    pub user_id: String,
+    pub url: String,
+    pub events: String,
+    pub secret: String,
+    pub created: NaiveDateTime,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct WebhookWrite {
+    pub url: String,
+    pub events: Vec<String>,
+    pub secret: Option<String>,
+}
+
+/* the secret is only ever shown when the subscription is created */
+#[derive(Debug, Serialize)]
+pub struct WebhookRead {
+    pub id: String,
+    pub user_id: String,
+    pub url: String,
+    pub events: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub secret: Option<String>,
+    pub created: NaiveDateTime,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Queryable, Insertable, AsChangeset)]
+#[diesel(table_name = crate::models::schema::webhook_deliveries)]
+#[diesel(treat_none_as_null = true)]
+pub struct WebhookDelivery {
+    pub id: String,
+    pub webhook_id: String,
+    pub event: String,
+    pub payload: String,
+    pub status: String,
+    pub attempts: i32,
+    pub next_attempt: NaiveDateTime,
+    pub response_status: Option<i32>,
+    pub last_error: Option<String>,
+    pub created: NaiveDateTime,
+    pub updated: NaiveDateTime,
+}
+
+impl WebhookSubscription {
+    pub fn events(&self) -> Vec<String> {
+        self.events
+            .split(',')
+            .filter(|e| !e.is_empty())
+            .map(String::from)
+            .collect()
+    }
+
+    pub fn subscribes_to(&self, event: &str) -> bool {
+        self.events().iter().any(|e| e == event)
+    }
+}
+
+impl WebhookRead {
+    pub fn new(webhook: &WebhookSubscription, show_secret: bool) -> Self {
+        Self {
+            id: webhook.id.clone(),
+            user_id: webhook.user_id.clone(),
+            url: webhook.url.clone(),
+            events: webhook.events(),
+            secret: if show_secret { Some(webhook.secret.clone()) } else { None },
+            created: webhook.created,
+        }
+    }
+}
+
+impl WebhookDelivery {
+    pub fn new(webhook_id: &str, event: &str, payload: &str, now: NaiveDateTime) -> Self {
+        Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            webhook_id: String::from(webhook_id),
+            event: String::from(event),
+            payload: String::from(payload),
+            status: String::from(DELIVERY_PENDING),
+            attempts: 0,
+            next_attempt: now,
+            response_status: None,
+            last_error: None,
+            created: now,
+            updated: now,
+        }
+    }
+}
+
+pub fn webhook_from_webhook_write(src: &WebhookWrite, user_id: &str) -> WebhookSubscription {
+    WebhookSubscription {
+        id: String::new(),
+        user_id: String::from(user_id),
+        url: src.url.clone(),
+        events: src.events.join(","),
+        secret: match &src.secret {
+            Some(secret) => secret.clone(),
+            None => format!["{}{}", uuid::Uuid::new_v4().simple(), uuid::Uuid::new_v4().simple()],
+        },
+        created: NaiveDateTime::MIN,
+    }
+}
+
+pub fn webhook_validate(webhook: &WebhookSubscription) -> Result<(), Vec<FieldError>> {
+    let mut errors = vec![];
+
+    if !webhook.url.starts_with("http://") && !webhook.url.starts_with("https://") {
+        errors.push(FieldError::new("/url", CODE_INVALID_URL, "Invalid webhook url"));
+    }
+
+    let events = webhook.events();
+
+    if events.is_empty() {
+        errors.push(FieldError::new("/events", CODE_EVENTS_MISSING, "No events present"));
+    }
+
+    for (i, event) in events.iter().enumerate() {
+        if !EVENTS.contains(&event.as_str()) {
+            errors.push(FieldError::new(&format!["/events/{}", i], CODE_INVALID_EVENT, "Invalid event"));
+        }
+    }
+
+    if webhook.secret.len() < 16 {
+        errors.push(FieldError::new("/secret", CODE_SECRET_TOO_SHORT, "Secret too short"));
+    }
+
+    field_errors_result(errors)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write(url: &str, events: &[&str], secret: Option<&str>) -> WebhookWrite {
+        WebhookWrite {
+            url: String::from(url),
+            events: events.iter().map(|e| String::from(*e)).collect(),
+            secret: secret.map(String::from),
+        }
+    }
+
+    #[test]
+    fn generated_secret() {
+        let webhook = webhook_from_webhook_write(&write("https://dash.example.com", &[EVENT_CREATED], None), "user-1");
+
+        assert!(webhook.secret.len() == 64 && webhook_validate(&webhook).is_ok());
+    }
+
+    #[test]
+    fn subscribes_to() {
+        let webhook = webhook_from_webhook_write(&write("https://dash.example.com", &[EVENT_CREATED, EVENT_EXPIRED], None), "user-1");
+
+        assert!(
+            webhook.subscribes_to(EVENT_EXPIRED) &&
+            !webhook.subscribes_to(EVENT_DELETED)
+        );
+    }
+
+    #[test]
+    fn validate_invalid() {
+        assert!(
+            webhook_validate(&webhook_from_webhook_write(&write("dash.example.com", &[EVENT_CREATED], None), "u")).is_err() &&
+            webhook_validate(&webhook_from_webhook_write(&write("https://dash.example.com", &[], None), "u")).is_err() &&
+            webhook_validate(&webhook_from_webhook_write(&write("https://dash.example.com", &["entry.renamed"], None), "u")).is_err() &&
+            webhook_validate(&webhook_from_webhook_write(&write("https://dash.example.com", &[EVENT_CREATED], Some("short")), "u")).is_err()
+        );
+    }
+
+    #[test]
+    fn read_hides_secret() {
+        let webhook = webhook_from_webhook_write(&write("https://dash.example.com", &[EVENT_CREATED], None), "user-1");
+
+        assert!(
+            WebhookRead::new(&webhook, false).secret.is_none() &&
+            WebhookRead::new(&webhook, true).secret == Some(webhook.secret.clone())
+        );
+    }
+}
\ No newline at end of file
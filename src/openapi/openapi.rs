@@ -0,0 +1,594 @@
+/*************************************************************************
+    "task_server"
+    openapi/openapi.rs
+    10/18/26
+    © Matthew Bentivegna
+*************************************************************************/
+use serde_json::{
+    json,
+    Map,
+    Value,
+};
+use schemars::{
+    JsonSchema,
+    generate::{
+        SchemaGenerator,
+        SchemaSettings,
+    },
+};
+use crate::api::error::CONTENT_TYPE_PROBLEM;
+use crate::patch::patch::{
+    CONTENT_TYPE_JSON,
+    CONTENT_TYPE_MERGE_PATCH,
+    CONTENT_TYPE_JSON_PATCH,
+};
+use crate::models::{
+    entry::{
+        EntryRead,
+        EntryWrite,
+        EntryPatch,
+        EntryParams,
+    },
+    user::{
+        User,
+        UserPatch,
+    },
+    channel::UserChannelWrite,
+    webhook::WebhookWrite,
+    batch::{
+        BatchParams,
+        BatchOperation,
+    },
+    import::ImportParams,
+    export::ExportParams,
+    feed::FeedParams,
+    outbox::OutboxParams,
+    sync::{
+        SyncParams,
+        SyncResponse,
+    },
+    revision::RevisionRead,
+    tag::TagRead,
+    list::{
+        EntryList,
+        EntryListWrite,
+        ListDeleteParams,
+    },
+    audit::{
+        AuditEvent,
+        AuditParams,
+    },
+    entry_v2::{
+        EntryV2Read,
+        EntryV2Write,
+        EntryV2Patch,
+        EntryV2Params,
+    },
+};
+
+const CONTENT_TYPE_CSV: &str = "text/csv";
+const CONTENT_TYPE_CALENDAR: &str = "text/calendar";
+const CONTENT_TYPE_TEXT: &str = "text/plain";
+
+/* one route (relative to /api) and its documentation; schemas are named by their type, "[]" marks a list and "" is any JSON */
+pub struct Operation {
+    pub method: &'static str,
+    pub path: &'static str,
+    pub summary: &'static str,
+    pub query: &'static [&'static str],
+    pub request: &'static [(&'static str, &'static str)],
+    pub status: u16,
+    pub response: &'static [(&'static str, &'static str)],
+    pub public: bool,
+}
+
+impl Operation {
+    const fn new(method: &'static str, path: &'static str, summary: &'static str) -> Self {
+        Self {
+            method,
+            path,
+            summary,
+            query: &[],
+            request: &[],
+            status: 200,
+            response: &[(CONTENT_TYPE_JSON, "")],
+            public: false,
+        }
+    }
+
+    const fn query(self, query: &'static [&'static str]) -> Self {
+        Self { query, ..self }
+    }
+
+    const fn request(self, request: &'static [(&'static str, &'static str)]) -> Self {
+        Self { request, ..self }
+    }
+
+    const fn response(self, status: u16, response: &'static [(&'static str, &'static str)]) -> Self {
+        Self { status, response, ..self }
+    }
+
+    /* needs no credentials */
+    const fn public(self) -> Self {
+        Self { public: true, ..self }
+    }
+}
+
+const ENTRY_PATCH: &[(&str, &str)] = &[
+    (CONTENT_TYPE_JSON, "EntryPatch"),
+    (CONTENT_TYPE_MERGE_PATCH, "EntryPatch"),
+    (CONTENT_TYPE_JSON_PATCH, "JsonPatch"),
+];
+
+const ENTRY_V2_PATCH: &[(&str, &str)] = &[
+    (CONTENT_TYPE_JSON, "EntryV2Patch"),
+    (CONTENT_TYPE_MERGE_PATCH, "EntryV2Patch"),
+    (CONTENT_TYPE_JSON_PATCH, "JsonPatch"),
+];
+
+const USER_PATCH: &[(&str, &str)] = &[
+    (CONTENT_TYPE_JSON, "UserPatch"),
+    (CONTENT_TYPE_MERGE_PATCH, "UserPatch"),
+    (CONTENT_TYPE_JSON_PATCH, "JsonPatch"),
+];
+
+/* the routes api::api::config and api::v2::config register (the tests check that the two agree) */
+pub const OPERATIONS: &[Operation] = &[
+    Operation::new("get", "/openapi.json", "This document").public(),
+
+    Operation::new("post", "/entries", "Create an entry")
+        .request(&[(CONTENT_TYPE_JSON, "EntryWrite")])
+        .response(201, &[(CONTENT_TYPE_JSON, "EntryRead")]),
+    Operation::new("get", "/entries", "List entries")
+        .query(&["EntryParams"])
+        .response(200, &[(CONTENT_TYPE_JSON, "EntryRead[]")]),
+    Operation::new("post", "/entries/import", "Import entries from iCalendar, CSV or todo.txt")
+        .query(&["ImportParams"])
+        .request(&[(CONTENT_TYPE_CALENDAR, ""), (CONTENT_TYPE_CSV, ""), (CONTENT_TYPE_TEXT, "")]),
+    Operation::new("get", "/entries/export", "Export entries as CSV, JSON Lines or Markdown")
+        .query(&["ExportParams", "EntryParams"])
+        .response(200, &[(CONTENT_TYPE_CSV, ""), ("application/jsonl", ""), ("text/markdown", "")]),
+    Operation::new("post", "/entries/batch", "Create, patch and delete entries together")
+        .query(&["BatchParams"])
+        .request(&[(CONTENT_TYPE_JSON, "BatchOperation[]")]),
+    Operation::new("get", "/entries/events", "Stream entry changes as Server-Sent Events")
+        .response(200, &[("text/event-stream", "")]),
+    Operation::new("get", "/entries/{id}", "Read an entry")
+        .response(200, &[(CONTENT_TYPE_JSON, "EntryRead")]),
+    Operation::new("put", "/entries/{id}", "Replace an entry")
+        .request(&[(CONTENT_TYPE_JSON, "EntryWrite")])
+        .response(200, &[(CONTENT_TYPE_JSON, "EntryRead")]),
+    Operation::new("patch", "/entries/{id}", "Update an entry")
+        .request(ENTRY_PATCH)
+        .response(200, &[(CONTENT_TYPE_JSON, "EntryRead")]),
+    Operation::new("delete", "/entries/{id}", "Move an entry to the trash (\"all\" deletes every entry, admin only)"),
+    Operation::new("get", "/entries/{id}/history", "List an entry's revisions")
+        .response(200, &[(CONTENT_TYPE_JSON, "RevisionRead[]")]),
+    Operation::new("post", "/entries/{id}/history/{revision}/revert", "Revert an entry to a revision")
+        .response(200, &[(CONTENT_TYPE_JSON, "EntryRead")]),
+
+    Operation::new("get", "/ws", "Open a WebSocket for entry commands and changes")
+        .response(101, &[]),
+    Operation::new("get", "/sync", "Entries changed and deleted since a sync token")
+        .query(&["SyncParams"])
+        .response(200, &[(CONTENT_TYPE_JSON, "SyncResponse")]),
+
+    Operation::new("get", "/trash", "List deleted entries")
+        .response(200, &[(CONTENT_TYPE_JSON, "EntryRead[]")]),
+    Operation::new("post", "/trash/{id}/restore", "Restore a deleted entry")
+        .response(200, &[(CONTENT_TYPE_JSON, "EntryRead")]),
+
+    Operation::new("get", "/tags", "List your tags, with how many entries carry each")
+        .response(200, &[(CONTENT_TYPE_JSON, "TagRead[]")]),
+
+    Operation::new("post", "/lists", "Create a list")
+        .request(&[(CONTENT_TYPE_JSON, "EntryListWrite")])
+        .response(201, &[(CONTENT_TYPE_JSON, "EntryList")]),
+    Operation::new("get", "/lists", "List your lists, in sort order")
+        .response(200, &[(CONTENT_TYPE_JSON, "EntryList[]")]),
+    Operation::new("get", "/lists/{id}", "Get a list")
+        .response(200, &[(CONTENT_TYPE_JSON, "EntryList")]),
+    Operation::new("put", "/lists/{id}", "Replace a list")
+        .request(&[(CONTENT_TYPE_JSON, "EntryListWrite")])
+        .response(200, &[(CONTENT_TYPE_JSON, "EntryList")]),
+    Operation::new("delete", "/lists/{id}", "Delete a list, moving its entries to the inbox or the trash")
+        .query(&["ListDeleteParams"])
+        .response(204, &[]),
+
+    Operation::new("post", "/users", "Create a user")
+        .request(&[(CONTENT_TYPE_JSON, "User")])
+        .response(201, &[(CONTENT_TYPE_JSON, "User")])
+        .public(),
+    Operation::new("get", "/users", "List users (admin only)")
+        .response(200, &[(CONTENT_TYPE_JSON, "User[]")]),
+    Operation::new("get", "/users/{id}", "Read a user (admin only)")
+        .response(200, &[(CONTENT_TYPE_JSON, "User")]),
+    Operation::new("patch", "/users/{id}", "Update a user (admin only)")
+        .request(USER_PATCH)
+        .response(200, &[(CONTENT_TYPE_JSON, "User")]),
+    Operation::new("delete", "/users/{id}", "Delete a user (admin only)"),
+
+    Operation::new("get", "/channels", "List notification channels"),
+    Operation::new("put", "/channels/{channel}", "Configure a notification channel")
+        .request(&[(CONTENT_TYPE_JSON, "UserChannelWrite")]),
+    Operation::new("delete", "/channels/{channel}", "Reset a notification channel"),
+
+    Operation::new("post", "/webhooks", "Subscribe a webhook")
+        .request(&[(CONTENT_TYPE_JSON, "WebhookWrite")])
+        .response(201, &[(CONTENT_TYPE_JSON, "")]),
+    Operation::new("get", "/webhooks", "List webhooks"),
+    Operation::new("get", "/webhooks/{id}", "Read a webhook"),
+    Operation::new("delete", "/webhooks/{id}", "Delete a webhook"),
+    Operation::new("get", "/webhooks/{id}/deliveries", "List a webhook's deliveries"),
+
+    Operation::new("get", "/feeds", "Read the calendar feed"),
+    Operation::new("post", "/feeds", "Create or regenerate the calendar feed"),
+    Operation::new("delete", "/feeds", "Delete the calendar feed"),
+    Operation::new("get", "/feeds/{token}.ics", "Read the calendar feed as iCalendar")
+        .query(&["FeedParams"])
+        .response(200, &[(CONTENT_TYPE_CALENDAR, "")])
+        .public(),
+
+    Operation::new("get", "/admin/outbox", "List outbox messages (admin only)")
+        .query(&["OutboxParams"]),
+    Operation::new("post", "/admin/outbox/{id}/retry", "Retry an outbox message (admin only)"),
+    Operation::new("get", "/admin/audit", "Query the audit log (admin only)")
+        .query(&["AuditParams"])
+        .response(200, &[(CONTENT_TYPE_JSON, "AuditEvent[]")]),
+    Operation::new("get", "/admin/audit/export", "Export the audit log as CSV, JSON Lines or Markdown (admin only)")
+        .query(&["ExportParams", "AuditParams"])
+        .response(200, &[(CONTENT_TYPE_CSV, ""), ("application/jsonl", ""), ("text/markdown", "")]),
+
+    Operation::new("post", "/v2/entries", "Create an entry")
+        .request(&[(CONTENT_TYPE_JSON, "EntryV2Write")])
+        .response(201, &[(CONTENT_TYPE_JSON, "EntryV2Read")]),
+    Operation::new("get", "/v2/entries", "List entries")
+        .query(&["EntryV2Params"])
+        .response(200, &[(CONTENT_TYPE_JSON, "EntryV2Read[]")]),
+    Operation::new("get", "/v2/entries/{id}", "Read an entry")
+        .response(200, &[(CONTENT_TYPE_JSON, "EntryV2Read")]),
+    Operation::new("put", "/v2/entries/{id}", "Replace an entry")
+        .request(&[(CONTENT_TYPE_JSON, "EntryV2Write")])
+        .response(200, &[(CONTENT_TYPE_JSON, "EntryV2Read")]),
+    Operation::new("patch", "/v2/entries/{id}", "Update an entry")
+        .request(ENTRY_V2_PATCH)
+        .response(200, &[(CONTENT_TYPE_JSON, "EntryV2Read")]),
+    Operation::new("delete", "/v2/entries/{id}", "Delete an entry")
+        .response(204, &[]),
+];
+
+/* the OpenAPI 3.1 document for the REST API (CalDAV is described by its own standard) */
+pub fn openapi() -> Value {
+    let mut schemas = model_schemas();
+    let mut paths = Map::new();
+
+    for operation in OPERATIONS {
+        let path = paths.entry(operation.path).or_insert_with(|| json!({}));
+        path[operation.method] = operation_object(operation, &schemas);
+    }
+
+    schemas.insert(String::from("Problem"), problem_schema());
+    schemas.insert(String::from("JsonPatch"), json_patch_schema());
+
+    json!({
+        "openapi": "3.1.0",
+        "info": {
+            "title": "task_server",
+            "version": env!("CARGO_PKG_VERSION"),
+        },
+        "servers": [{ "url": "/api" }],
+        "security": [{ "basic": [] }],
+        "paths": paths,
+        "components": {
+            "schemas": schemas,
+            "securitySchemes": {
+                "basic": { "type": "http", "scheme": "basic" },
+            },
+        },
+    })
+}
+
+/* the serde models, as JSON Schema 2020-12 (the dialect of OpenAPI 3.1) */
+fn model_schemas() -> Map<String, Value> {
+    let mut generator = SchemaSettings::draft2020_12()
+        .with(|s| {
+            s.definitions_path = "/components/schemas".into();
+            s.meta_schema = None;
+        })
+        .into_generator();
+
+    model_schema::<EntryRead>(&mut generator);
+    model_schema::<EntryWrite>(&mut generator);
+    model_schema::<EntryPatch>(&mut generator);
+    model_schema::<EntryParams>(&mut generator);
+    model_schema::<User>(&mut generator);
+    model_schema::<UserPatch>(&mut generator);
+    model_schema::<UserChannelWrite>(&mut generator);
+    model_schema::<WebhookWrite>(&mut generator);
+    model_schema::<BatchParams>(&mut generator);
+    model_schema::<BatchOperation>(&mut generator);
+    model_schema::<ImportParams>(&mut generator);
+    model_schema::<ExportParams>(&mut generator);
+    model_schema::<FeedParams>(&mut generator);
+    model_schema::<OutboxParams>(&mut generator);
+    model_schema::<SyncParams>(&mut generator);
+    model_schema::<SyncResponse>(&mut generator);
+    model_schema::<RevisionRead>(&mut generator);
+    model_schema::<TagRead>(&mut generator);
+    model_schema::<EntryList>(&mut generator);
+    model_schema::<EntryListWrite>(&mut generator);
+    model_schema::<ListDeleteParams>(&mut generator);
+    model_schema::<AuditEvent>(&mut generator);
+    model_schema::<AuditParams>(&mut generator);
+    model_schema::<EntryV2Read>(&mut generator);
+    model_schema::<EntryV2Write>(&mut generator);
+    model_schema::<EntryV2Patch>(&mut generator);
+    model_schema::<EntryV2Params>(&mut generator);
+
+    generator.take_definitions(true)
+}
+
+fn model_schema<T: JsonSchema>(generator: &mut SchemaGenerator) {
+    generator.subschema_for::<T>();
+}
+
+fn operation_object(operation: &Operation, schemas: &Map<String, Value>) -> Value {
+    let mut parameters: Vec<Value> = path_parameters(operation.path)
+        .into_iter()
+        .map(|name| json!({
+            "name": name,
+            "in": "path",
+            "required": true,
+            "schema": { "type": "string" },
+        }))
+        .collect();
+
+    /* query parameters are the fields of the query model, with the field's own schema */
+    for model in operation.query {
+        if let Some(properties) = schemas.get(*model).and_then(|s| s["properties"].as_object()) {
+            let required = &schemas[*model]["required"];
+
+            for (name, schema) in properties {
+                parameters.push(json!({
+                    "name": name,
+                    "in": "query",
+                    "required": required.as_array().is_some_and(|r| r.contains(&json!(name))),
+                    "schema": query_schema(schema),
+                }));
+            }
+        }
+    }
+
+    let mut responses = Map::new();
+    let mut response = json!({ "description": operation.summary });
+
+    if !operation.response.is_empty() {
+        response["content"] = content(operation.response);
+    }
+
+    responses.insert(operation.status.to_string(), response);
+    responses.insert(String::from("default"), json!({
+        "description": "Error",
+        "content": { CONTENT_TYPE_PROBLEM: { "schema": schema_ref("Problem") } },
+    }));
+
+    let mut object = json!({
+        "summary": operation.summary,
+        "operationId": format!["{}{}", operation.method, operation.path.replace(['/', '{', '}', '.'], "_")],
+        "parameters": parameters,
+        "responses": responses,
+    });
+
+    if !operation.request.is_empty() {
+        object["requestBody"] = json!({
+            "required": true,
+            "content": content(operation.request),
+        });
+    }
+
+    if operation.public {
+        object["security"] = json!([]);
+    }
+
+    object
+}
+
+/* an optional field's schema allows null, which a query string cannot send */
+fn query_schema(schema: &Value) -> Value {
+    let mut schema = schema.clone();
+
+    if let Some(types) = schema["type"].as_array() {
+        let types: Vec<Value> = types.iter().filter(|t| *t != "null").cloned().collect();
+        schema["type"] = match types.len() {
+            1 => types[0].clone(),
+            _ => Value::Array(types),
+        };
+    }
+
+    if let Some(any_of) = schema["anyOf"].as_array() {
+        let any_of: Vec<Value> = any_of.iter().filter(|s| s["type"] != "null").cloned().collect();
+        if any_of.len() == 1 {
+            return any_of[0].clone();
+        }
+        schema["anyOf"] = Value::Array(any_of);
+    }
+
+    schema
+}
+
+fn content(media: &[(&str, &str)]) -> Value {
+    let mut content = Map::new();
+
+    for (content_type, schema) in media {
+        content.insert(String::from(*content_type), json!({ "schema": schema_ref(schema) }));
+    }
+
+    Value::Object(content)
+}
+
+fn schema_ref(schema: &str) -> Value {
+    match schema {
+        "" => json!({}),
+        _ => match schema.strip_suffix("[]") {
+            Some(item) => json!({ "type": "array", "items": schema_ref(item) }),
+            None => json!({ "$ref": format!["#/components/schemas/{}", schema] }),
+        },
+    }
+}
+
+/* "{id}" in "/entries/{id}" */
+fn path_parameters(path: &str) -> Vec<&str> {
+    path.split('{')
+        .skip(1)
+        .filter_map(|s| s.split('}').next())
+        .collect()
+}
+
+/* as rendered by api::error::ApiError */
+fn problem_schema() -> Value {
+    json!({
+        "type": "object",
+        "properties": {
+            "type": { "type": "string" },
+            "title": { "type": "string" },
+            "status": { "type": "integer" },
+            "code": { "type": "string" },
+            "detail": { "type": "string" },
+            "errors": {
+                "type": "array",
+                "items": {
+                    "type": "object",
+                    "properties": {
+                        "pointer": { "type": "string" },
+                        "code": { "type": "string" },
+                        "detail": { "type": "string" },
+                    },
+                    "required": ["pointer", "code", "detail"],
+                },
+            },
+        },
+        "required": ["type", "title", "status", "code", "detail"],
+    })
+}
+
+/* RFC 6902, limited to the operations patch::json_patch applies */
+fn json_patch_schema() -> Value {
+    json!({
+        "type": "array",
+        "items": {
+            "type": "object",
+            "properties": {
+                "op": { "enum": ["test", "add", "replace", "remove"] },
+                "path": { "type": "string" },
+                "value": {},
+            },
+            "required": ["op", "path"],
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /* the documented operations are the routes registered by api::api::config and api::v2::config, read from their source */
+    #[test]
+    fn operations_match_routes() {
+        let mut routes = config_routes(include_str!("../api/api.rs"), "");
+        routes.extend(config_routes(include_str!("../api/v2.rs"), "/v2"));
+
+        let mut operations: Vec<String> = OPERATIONS.iter().map(|o| format!["{} {}", o.method, o.path]).collect();
+
+        routes.sort();
+        operations.sort();
+
+        assert!(routes.len() > 40);
+        assert_eq!(routes, operations);
+    }
+
+    /* ".route("/entries/{id}", web::get()" gives "get /entries/{id}" */
+    fn config_routes(source: &str, prefix: &str) -> Vec<String> {
+        source
+            .split(".route(\"")
+            .skip(1)
+            .filter_map(|route| {
+                let (path, rest) = route.split_once('"')?;
+                let method = rest.strip_prefix(", web::")?.split('(').next()?;
+                Some(format!["{} {}{}", method, prefix, path])
+            })
+            .collect()
+    }
+
+    /* every documented operation reaches its handler (which, with no database configured, fails as a server error) */
+    #[actix_web::test]
+    async fn operations_routed() {
+        let app = actix_web::test::init_service(
+            actix_web::App::new()
+                .configure(crate::api::v2::config)
+                .configure(crate::api::api::config)
+        ).await;
+
+        for operation in OPERATIONS {
+            let uri = format!["/api{}", operation.path.replace("{token}", "token").replace(['{', '}'], "").replace("revision", "1")];
+            let request = actix_web::test::TestRequest::default()
+                .method(actix_web::http::Method::from_bytes(operation.method.to_uppercase().as_bytes()).unwrap())
+                .uri(&uri)
+                .to_request();
+            let status = actix_web::test::call_service(&app, request).await.status();
+
+            assert!(status != 404 && status != 405, "{} {} answered {}", operation.method, uri, status);
+        }
+    }
+
+    #[test]
+    fn references_resolve() {
+        let document = openapi();
+        let text = document.to_string();
+
+        for reference in text.split("\"$ref\":\"").skip(1) {
+            let reference = reference.split('"').next().unwrap();
+            assert!(document.pointer(reference.trim_start_matches('#')).is_some(), "unresolved {}", reference);
+        }
+    }
+
+    #[test]
+    fn models_and_parameters() {
+        let document = openapi();
+        let create = &document["paths"]["/entries"]["post"];
+        let list = &document["paths"]["/entries"]["get"];
+        let entry = &document["paths"]["/entries/{id}"]["patch"];
+
+        assert!(
+            document["openapi"] == "3.1.0" &&
+            document["components"]["schemas"]["EntryWrite"]["properties"]["task"]["type"] == "string" &&
+            create["requestBody"]["content"]["application/json"]["schema"]["$ref"] == "#/components/schemas/EntryWrite" &&
+            create["responses"]["201"].is_object() &&
+            list["parameters"].as_array().unwrap().iter().any(|p| p["name"] == "completed" && p["in"] == "query") &&
+            entry["parameters"][0]["name"] == "id" &&
+            entry["requestBody"]["content"]["application/merge-patch+json"].is_object() &&
+            document["paths"]["/users"]["post"]["security"] == json!([])
+        );
+    }
+
+    #[test]
+    fn query_parameter_types() {
+        let document = openapi();
+        let parameter = |path: &str, name: &str| document["paths"][path]["get"]["parameters"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|p| p["name"] == name)
+            .map(|p| p["schema"].clone());
+
+        assert_eq!(parameter("/v2/entries", "completed"), Some(json!({ "type": "boolean" })));
+        assert_eq!(parameter("/v2/entries", "due"), Some(json!({ "type": "string" })));
+        assert_eq!(parameter("/entries", "completed"), Some(json!({ "type": "string" })));
+    }
+
+    #[test]
+    fn path_parameter_names() {
+        assert_eq!(path_parameters("/entries"), Vec::<&str>::new());
+        assert_eq!(path_parameters("/admin/outbox/{id}/retry"), vec!["id"]);
+        assert_eq!(path_parameters("/feeds/{token}.ics"), vec!["token"]);
+    }
+}
\ No newline at end of file
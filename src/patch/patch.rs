@@ -0,0 +1,234 @@
+/*************************************************************************
+    "task_server"
+    patch/patch.rs
+    10/18/26
+    © Matthew Bentivegna
+*************************************************************************/
+use serde_json::Value;
+
+pub const CONTENT_TYPE_JSON: &str = "application/json";
+pub const CONTENT_TYPE_MERGE_PATCH: &str = "application/merge-patch+json";
+pub const CONTENT_TYPE_JSON_PATCH: &str = "application/json-patch+json";
+
+/* sent with 415 responses, listing what PATCH understands */
+pub const ACCEPT_PATCH: &str = "application/json, application/merge-patch+json, application/json-patch+json";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PatchFormat {
+    /* the fields present are set; null is the same as absent */
+    Json,
+    /* RFC 7396: null removes (clears) a field */
+    Merge,
+    /* RFC 6902: a list of test/add/replace/remove operations */
+    JsonPatch,
+}
+
+impl PatchFormat {
+    /* no Content-Type is read as plain JSON */
+    pub fn from_content_type(content_type: Option<&str>) -> Option<Self> {
+        let content_type = match content_type {
+            Some(c) => c.split(';').next().unwrap_or("").trim().to_lowercase(),
+            None => { return Some(PatchFormat::Json); },
+        };
+
+        match content_type.as_str() {
+            CONTENT_TYPE_JSON => Some(PatchFormat::Json),
+            CONTENT_TYPE_MERGE_PATCH => Some(PatchFormat::Merge),
+            CONTENT_TYPE_JSON_PATCH => Some(PatchFormat::JsonPatch),
+            _ => None,
+        }
+    }
+}
+
+/* applies a merge-patch or json-patch body to a document */
+pub fn patch_document(format: PatchFormat, document: &mut Value, body: &str) -> Result<(), String> {
+    let patch: Value = serde_json::from_str(body).map_err(|e| e.to_string())?;
+
+    match format {
+        PatchFormat::Merge => {
+            merge_patch(document, &patch);
+            Ok(())
+        },
+        PatchFormat::JsonPatch => json_patch(document, &patch),
+        PatchFormat::Json => Err(String::from("Not a patch document")),
+    }
+}
+
+/* RFC 7396, section 2 */
+pub fn merge_patch(target: &mut Value, patch: &Value) {
+    let Value::Object(members) = patch else {
+        *target = patch.clone();
+        return;
+    };
+
+    if !target.is_object() {
+        *target = Value::Object(serde_json::Map::new());
+    }
+
+    if let Value::Object(target) = target {
+        for (name, value) in members {
+            if value.is_null() {
+                target.remove(name);
+            } else {
+                merge_patch(target.entry(name.clone()).or_insert(Value::Null), value);
+            }
+        }
+    }
+}
+
+/* RFC 6902, limited to "test", "add", "replace" and "remove"; the operations apply all or none */
+pub fn json_patch(target: &mut Value, operations: &Value) -> Result<(), String> {
+    let operations = operations.as_array().ok_or("Patch must be a list of operations")?;
+    let mut document = target.clone();
+
+    for operation in operations {
+        let op = operation.get("op").and_then(|o| o.as_str()).ok_or("No op present")?;
+        let path = operation.get("path").and_then(|p| p.as_str()).ok_or("No path present")?;
+        let value = || operation.get("value").cloned().ok_or(format!["No value present for {}", path]);
+
+        match op {
+            "test" => {
+                if document.pointer(path) != Some(&value()?) {
+                    return Err(format!["Test failed: {}", path]);
+                }
+            },
+            "add" => add(&mut document, path, value()?)?,
+            "replace" => {
+                *document.pointer_mut(path).ok_or(format!["Path not found: {}", path])? = value()?;
+            },
+            "remove" => remove(&mut document, path)?,
+            _ => { return Err(format!["Invalid op: {}", op]); },
+        }
+    }
+
+    *target = document;
+    Ok(())
+}
+
+/* "/a/b~1c" -> ("/a", "b/c") */
+fn split_pointer(path: &str) -> Result<(&str, String), String> {
+    let (parent, last) = path.rsplit_once('/').ok_or(format!["Invalid path: {}", path])?;
+
+    Ok((parent, last.replace("~1", "/").replace("~0", "~")))
+}
+
+fn add(document: &mut Value, path: &str, value: Value) -> Result<(), String> {
+    if path.is_empty() {
+        *document = value;
+        return Ok(());
+    }
+
+    let (parent, name) = split_pointer(path)?;
+
+    match document.pointer_mut(parent) {
+        Some(Value::Object(members)) => {
+            members.insert(name, value);
+        },
+        Some(Value::Array(items)) => {
+            let index = if name == "-" { items.len() } else { name.parse::<usize>().map_err(|_| format!["Invalid path: {}", path])? };
+
+            if index > items.len() {
+                return Err(format!["Path not found: {}", path]);
+            }
+            items.insert(index, value);
+        },
+        _ => { return Err(format!["Path not found: {}", path]); },
+    }
+
+    Ok(())
+}
+
+fn remove(document: &mut Value, path: &str) -> Result<(), String> {
+    let (parent, name) = split_pointer(path)?;
+    let not_found = || format!["Path not found: {}", path];
+
+    match document.pointer_mut(parent) {
+        Some(Value::Object(members)) => {
+            members.remove(&name).ok_or_else(not_found)?;
+        },
+        Some(Value::Array(items)) => {
+            let index = name.parse::<usize>().map_err(|_| not_found())?;
+
+            if index >= items.len() {
+                return Err(not_found());
+            }
+            items.remove(index);
+        },
+        _ => { return Err(not_found()); },
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn content_types() {
+        assert!(
+            PatchFormat::from_content_type(None) == Some(PatchFormat::Json) &&
+            PatchFormat::from_content_type(Some("application/json; charset=utf-8")) == Some(PatchFormat::Json) &&
+            PatchFormat::from_content_type(Some("application/merge-patch+json")) == Some(PatchFormat::Merge) &&
+            PatchFormat::from_content_type(Some("application/json-patch+json")) == Some(PatchFormat::JsonPatch) &&
+            PatchFormat::from_content_type(Some("text/plain")).is_none()
+        );
+    }
+
+    /* the examples of RFC 7396, appendix A */
+    #[test]
+    fn merge_rfc_examples() {
+        let examples = [
+            (json!({"a": "b"}), json!({"a": "c"}), json!({"a": "c"})),
+            (json!({"a": "b"}), json!({"b": "c"}), json!({"a": "b", "b": "c"})),
+            (json!({"a": "b"}), json!({"a": null}), json!({})),
+            (json!({"a": "b", "b": "c"}), json!({"a": null}), json!({"b": "c"})),
+            (json!({"a": ["b"]}), json!({"a": "c"}), json!({"a": "c"})),
+            (json!({"a": {"b": "c"}}), json!({"a": {"b": "d", "c": null}}), json!({"a": {"b": "d"}})),
+            (json!({"a": [{"b": "c"}]}), json!({"a": [1]}), json!({"a": [1]})),
+            (json!({"e": null}), json!({"a": 1}), json!({"e": null, "a": 1})),
+            (json!([1, 2]), json!({"a": "b", "c": null}), json!({"a": "b"})),
+            (json!({}), json!({"a": {"bb": {"ccc": null}}}), json!({"a": {"bb": {}}})),
+        ];
+
+        for (mut target, patch, result) in examples {
+            merge_patch(&mut target, &patch);
+            assert_eq!(target, result);
+        }
+    }
+
+    #[test]
+    fn json_patch_operations() {
+        let mut document = json!({"task": "Pay rent", "reminder": "7", "reminders": [{"offset": "7"}]});
+        let patch = json!([
+            {"op": "test", "path": "/task", "value": "Pay rent"},
+            {"op": "replace", "path": "/task", "value": "Pay rent early"},
+            {"op": "remove", "path": "/reminder"},
+            {"op": "add", "path": "/reminders/-", "value": {"offset": "1"}},
+            {"op": "remove", "path": "/reminders/0"},
+        ]);
+
+        json_patch(&mut document, &patch).unwrap();
+
+        assert_eq!(document, json!({"task": "Pay rent early", "reminders": [{"offset": "1"}]}));
+    }
+
+    #[test]
+    fn json_patch_all_or_none() {
+        let mut document = json!({"task": "Pay rent"});
+        let failed_test = json!([
+            {"op": "replace", "path": "/task", "value": "Changed"},
+            {"op": "test", "path": "/task", "value": "Pay rent"},
+        ]);
+
+        assert_eq!(json_patch(&mut document, &failed_test), Err(String::from("Test failed: /task")));
+        assert_eq!(document, json!({"task": "Pay rent"}));
+        assert!(
+            json_patch(&mut document, &json!([{"op": "remove", "path": "/reminder"}])).is_err() &&
+            json_patch(&mut document, &json!([{"op": "replace", "path": "/due", "value": "x"}])).is_err() &&
+            json_patch(&mut document, &json!([{"op": "copy", "from": "/task", "path": "/name"}])).is_err() &&
+            json_patch(&mut document, &json!({"op": "remove", "path": "/task"})).is_err()
+        );
+    }
+}
\ No newline at end of file
@@ -7,8 +7,10 @@
 use std::fmt::Error;
 use chrono::prelude::*;
 use dotenv::dotenv;
+use tokio::sync::broadcast;
 use diesel::{
     prelude::*,
+    upsert::excluded,
     r2d2::{
         self,
         ConnectionManager
@@ -19,21 +21,109 @@ use crate::models::{
     entry::{
         Entry,
         EntryParams,
+        EntryRead,
+        date_part_normalize,
+        entry_date_normalize,
+    },
+    sync::{
+        EntryChange,
+        EntryChangeWrite,
+    },
+    entry_event::{
+        EntryEvent,
+        EntryEventNotice,
+        ENTRY_EVENT_BROADCAST_CAPACITY,
+        EntryEventWrite,
+    },
+    revision::{
+        EntryRevision,
+        entry_revision,
+        ACTION_CREATED,
+        ACTION_UPDATED,
+        ACTION_DELETED,
+        ACTION_RESTORED,
+    },
+    audit::{
+        AuditEvent,
+        AuditEventWrite,
+        AuditFilter,
+    },
+    tag::{
+        Tag,
+        EntryTag,
+        TagRead,
+        tags_from_param,
+    },
+    list::{
+        EntryList,
+        LIST_INBOX,
+    },
+    batch::BatchChange,
+    reminder::{
+        Reminder,
+        ReminderWrite,
+    },
+    notification::{
+        Notification,
+        STATUS_PLANNED,
+        STATUS_SENDING,
+        STATUS_QUEUED,
+    },
+    channel::UserChannel,
+    feed::Feed,
+    idempotency::IdempotencyKey,
+    outbox::{
+        OutboxMessage,
+        OutboxParams,
+        OUTBOX_PENDING,
+        OUTBOX_SENDING,
+    },
+    webhook::{
+        WebhookSubscription,
+        WebhookDelivery,
+        DELIVERY_PENDING,
+        DELIVERY_SENDING,
     },
     schema::{
+        audit_log,
         entries::{
             self,
             dsl::*
         },
-        users::dsl::*,
+        entry_changes,
+        entry_events,
+        entry_reminders,
+        entry_revisions,
+        entry_tags,
+        feeds,
+        idempotency_keys,
+        lists,
+        notifications,
+        outbox,
+        tags,
+        user_channels,
+        users::{
+            self,
+            dsl::*
+        },
+        webhooks,
+        webhook_deliveries,
     }
 };
 
 pub type DBPool = r2d2::Pool<ConnectionManager<PgConnection>>;
 
+/* advisory lock keys: numbers are taken from a sequence when a row is written, and only made visible at commit, so a user's writers
+   take the user's lock first and commit in number order (a reader of one user's rows that has seen one number has seen every lower
+   one); they also hold the key shared, which a reader of every user's rows takes alone to wait for the writes in progress */
+const ENTRY_CHANGES_LOCK: i32 = 1;
+const ENTRY_EVENTS_LOCK: i32 = 2;
+
+/* every clone shares the pool, and the broadcast of entry events to the streams and sockets open in this process */
 #[derive(Clone)]
 pub struct Database {
     pool: DBPool,
+    event_sender: broadcast::Sender<EntryEventNotice>,
 }
 
 impl Database {
@@ -44,48 +134,93 @@ impl Database {
         let pool: DBPool = r2d2::Pool::builder()
             .build(manager)
             .expect("Failed to create pool.");
-        Database { pool }
+        let (event_sender, _) = broadcast::channel(ENTRY_EVENT_BROADCAST_CAPACITY);
+        Database { pool, event_sender }
     }
     
     /**************************************************************************/
     /* ENTRY actions*/
     /**************************************************************************/
-    pub fn create_entry(&self, entry: Entry, user_id_str: &str) -> Result<Entry, Error> {
-        let entry = Entry {
-            id: uuid::Uuid::new_v4().to_string(),
+    pub fn create_entry(&self, entry: Entry, reminders: &[ReminderWrite], names: &[String], user_id_str: &str, actor: &str) -> Result<EntryRead, diesel::result::Error> {
+        self.create_entry_with_id(entry, reminders, names, user_id_str, &uuid::Uuid::new_v4().to_string(), actor)
+    }
+
+    /* creates an entry under an id chosen by the client (CalDAV resource names); an id that is taken is a unique violation */
+    pub fn create_entry_with_id(&self, entry: Entry, reminders: &[ReminderWrite], names: &[String], user_id_str: &str, entry_id: &str, actor: &str) -> Result<EntryRead, diesel::result::Error> {
+        let mut entry = Entry {
+            id: String::from(entry_id),
             expired: "false".to_string(),
             created: Utc::now().naive_utc(),
             updated: Utc::now().naive_utc(),
             user_id: String::from(user_id_str),
             ..entry
         };
-        diesel::insert_into(entries)
-            .values(&entry)
-            .execute(&mut self.pool.get().unwrap())
-            .expect("Error creating new entry");
-        Ok(entry)
+        entry_date_normalize(&mut entry);
+        /* the stored row is returned, so timestamps carry the database's precision; a CalDAV client may reuse the id of one of
+           its own entries in the trash, while someone else's (trashed or not) keeps it, so the insert fails as a conflict */
+        self.entry_transaction(&[user_id_str], |conn| {
+            diesel::delete(
+                    entries
+                        .find(&entry.id)
+                        .filter(entries::user_id.eq(user_id_str))
+                        .filter(entries::deleted_at.is_not_null())
+                )
+                .execute(conn)?;
+            let entry = diesel::insert_into(entries).values(&entry).get_result::<Entry>(conn)?;
+            let reminders = Self::reminders_replace(conn, &entry.id, reminders)?;
+            let names = Self::tags_replace(conn, &entry.id, &entry.user_id, names)?;
+            let read = EntryRead::new(entry, reminders, names);
+            Self::entry_change_record(conn, &read.entry.id, &read.entry.user_id, false)?;
+            Self::entry_revision_record(conn, None, &read, actor, ACTION_CREATED)?;
+            Ok(read)
+        })
     }
 
     pub fn get_entries(&self) -> Vec<Entry> {
         entries
+            .filter(entries::deleted_at.is_null())
             .load::<Entry>(&mut self.pool.get().unwrap())
             .expect("Error loading all entries")
     }
     
     pub fn get_entries_by_filter(&self, params: &EntryParams) -> Vec<Entry> {
-        let mut query = entries::table.into_boxed();
+        let list = Self::entries_filtered(params).load::<Entry>(&mut self.pool.get().unwrap());
+        
+        match list {
+            Ok(e) => e,
+            Err(_) => vec![],
+        }
+    }
+    
+    /* one page of a filtered listing in id order, starting after "after_id" (for streaming large results) */
+    pub fn get_entries_by_filter_page(&self, params: &EntryParams, after_id: Option<&str>, limit: i64) -> Vec<Entry> {
+        let mut query = Self::entries_filtered(params);
+        
+        if let Some(n) = after_id {
+            query = query.filter(entries::id.gt(n.to_string()));
+        }
+        
+        query
+            .order(entries::id.asc())
+            .limit(limit)
+            .load::<Entry>(&mut self.pool.get().unwrap())
+            .unwrap_or_default()
+    }
+    
+    fn entries_filtered(params: &EntryParams) -> entries::BoxedQuery<'_, diesel::pg::Pg> {
+        let mut query = entries::table.filter(entries::deleted_at.is_null()).into_boxed();
 
         if let Some(n) = &params.username {
             query = query.filter(entries::username.eq(n));
         }
         if let Some(n) = &params.year {
-            query = query.filter(entries::year.eq(n));
+            query = query.filter(entries::year.eq(date_part_normalize(n)));
         }
         if let Some(n) = &params.month {
-            query = query.filter(entries::month.eq(n));
+            query = query.filter(entries::month.eq(date_part_normalize(n)));
         }
         if let Some(n) = &params.day {
-            query = query.filter(entries::day.eq(n));
+            query = query.filter(entries::day.eq(date_part_normalize(n)));
         }
         if let Some(n) = &params.reminder {
             query = query.filter(entries::reminder.eq(n));
@@ -93,18 +228,45 @@ impl Database {
         if let Some(n) = &params.expired {
             query = query.filter(entries::expired.eq(n));
         }
-    
-        let list = query.load::<Entry>(&mut self.pool.get().unwrap());
+        if let Some(n) = &params.completed {
+            query = query.filter(entries::completed.eq(n));
+        }
+        if let Some(n) = &params.list_id {
+            query = match n.as_str() {
+                LIST_INBOX => query.filter(entries::list_id.is_null()),
+                _ => query.filter(entries::list_id.eq(n)),
+            };
+        }
         
-        match list {
-            Ok(e) => e,
-            Err(_) => vec![],
+        /* every tag listed must be on the entry, so each is its own condition */
+        if let Some(n) = &params.tag {
+            for name in tags_from_param(n) {
+                query = query.filter(entries::id.eq_any(entry_tags::table
+                    .inner_join(tags::table)
+                    .filter(tags::name.eq(name))
+                    .select(entry_tags::entry_id)));
+            }
         }
+        if let Some(n) = &params.tag_any {
+            query = query.filter(entries::id.eq_any(entry_tags::table
+                .inner_join(tags::table)
+                .filter(tags::name.eq_any(tags_from_param(n)))
+                .select(entry_tags::entry_id)));
+        }
+        if let Some(n) = &params.tag_none {
+            query = query.filter(diesel::dsl::not(entries::id.eq_any(entry_tags::table
+                .inner_join(tags::table)
+                .filter(tags::name.eq_any(tags_from_param(n)))
+                .select(entry_tags::entry_id))));
+        }
+        
+        query
     }
     
     pub fn get_entry_by_id(&self, entry_id: &str) -> Option<Entry> {
         let entry = entries
             .find(entry_id)
+            .filter(entries::deleted_at.is_null())
             .get_result::<Entry>(&mut self.pool.get().unwrap());
 
         match entry {
@@ -113,19 +275,1070 @@ impl Database {
         }
     }
     
-    pub fn update_entry_by_id(&self, entry_id: &str, mut entry: Entry) -> Option<Entry> {
+    /* the write only happens if the row is still the version the entry was made from ("updated" unchanged), and takes the
+       reminders and tags with it (None keeps them); None when someone else changed or deleted the entry in the meantime */
+    pub fn update_entry_by_id(&self, entry_id: &str, mut entry: Entry, reminders: Option<&[ReminderWrite]>, names: Option<&[String]>, actor: &str) -> Option<EntryRead> {
+        let expected = entry.updated;
+        let owner = entry.user_id.clone();
         entry.updated = Utc::now().naive_utc();
-        let entry = diesel::update(entries.find(entry_id))
-            .set(&entry)
+        entry_date_normalize(&mut entry);
+        self.entry_transaction(&[&owner], |conn| {
+            let before = entries.find(entry_id).for_update().get_result::<Entry>(conn).optional()?;
+            let entry = diesel::update(
+                    entries
+                        .find(entry_id)
+                        .filter(entries::updated.eq(expected))
+                        .filter(entries::deleted_at.is_null())
+                )
+                .set(&entry)
+                .get_result::<Entry>(conn)
+                .optional()?;
+            let (before, entry) = match (before, entry) {
+                (Some(before), Some(entry)) => (Self::entry_read_load(conn, before)?, entry),
+                _ => { return Ok(None); },
+            };
+            let reminders = match reminders {
+                Some(reminders) => Self::reminders_replace(conn, entry_id, reminders)?,
+                None => before.reminders.clone(),
+            };
+            let names = match names {
+                Some(names) => Self::tags_replace(conn, entry_id, &entry.user_id, names)?,
+                None => before.tags.clone(),
+            };
+            let read = EntryRead::new(entry, reminders, names);
+            Self::entry_change_record(conn, &read.entry.id, &read.entry.user_id, false)?;
+            Self::entry_revision_record(conn, Some(&before), &read, actor, ACTION_UPDATED)?;
+            Ok(Some(read))
+        })
+        .expect("Error updating entry by id")
+    }
+    
+    /* moves the entry to the trash; its change record becomes a tombstone, so syncing clients learn of the delete */
+    pub fn delete_entry_by_id(&self, entry: &Entry, actor: &str) -> Option<usize> {
+        let count = self.entry_transaction(&[&entry.user_id], |conn| {
+            Self::entry_delete(conn, &entry.id, None, actor)
+        })
+        .expect("Error deleting entry by id");
+        Some(count)
+    }
+    
+    /* delete_entry_by_id, only if the entry is still the version the client read; 0 otherwise */
+    pub fn delete_entry_unchanged(&self, entry: &Entry, actor: &str) -> usize {
+        self.entry_transaction(&[&entry.user_id], |conn| {
+            Self::entry_delete(conn, &entry.id, Some(entry.updated), actor)
+        })
+        .expect("Error deleting entry by id")
+    }
+    
+    fn entry_delete(conn: &mut PgConnection, entry_id: &str, expected: Option<NaiveDateTime>, actor: &str) -> QueryResult<usize> {
+        let mut query = diesel::update(entries.find(entry_id).filter(entries::deleted_at.is_null())).into_boxed();
+        
+        if let Some(expected) = expected {
+            query = query.filter(entries::updated.eq(expected));
+        }
+        
+        let deleted = query
+            .set(entries::deleted_at.eq(Utc::now().naive_utc()))
+            .get_result::<Entry>(conn)
+            .optional()?;
+        
+        match deleted {
+            Some(entry) => {
+                /* reminders not yet sent are planned again if the entry is restored */
+                diesel::delete(notifications::table
+                    .filter(notifications::entry_id.eq(&entry.id))
+                    .filter(notifications::status.eq(STATUS_PLANNED)))
+                    .execute(conn)?;
+                Self::entry_change_record(conn, &entry.id, &entry.user_id, true)?;
+                let before = Entry { deleted_at: None, ..entry.clone() };
+                Self::entry_revision_record_unlisted(conn, before, entry, actor, ACTION_DELETED)?;
+                Ok(1)
+            },
+            None => Ok(0),
+        }
+    }
+    
+    /* every write that records an entry change runs in one of these, for the users owning the entries it writes; the locks are taken
+       before any row is locked, so all of them lock in the same order */
+    fn entry_transaction<T>(&self, user_ids: &[&str], f: impl FnOnce(&mut PgConnection) -> QueryResult<T>) -> QueryResult<T> {
+        self.pool.get().unwrap().transaction(|conn| {
+            Self::sequence_lock(conn, ENTRY_CHANGES_LOCK, user_ids)?;
+            f(conn)
+        })
+    }
+    
+    /* held until the transaction ends; the users are locked in order, so that two writers never wait on each other */
+    fn sequence_lock(conn: &mut PgConnection, key: i32, user_ids: &[&str]) -> QueryResult<()> {
+        let mut user_ids = user_ids.to_vec();
+        user_ids.sort_unstable();
+        user_ids.dedup();
+        
+        diesel::sql_query("SELECT pg_advisory_xact_lock_shared($1)")
+            .bind::<diesel::sql_types::BigInt, _>(key as i64)
+            .execute(conn)?;
+        
+        for user_id_str in user_ids {
+            diesel::sql_query("SELECT pg_advisory_xact_lock($1, hashtext($2))")
+                .bind::<diesel::sql_types::Integer, _>(key)
+                .bind::<diesel::sql_types::Text, _>(user_id_str)
+                .execute(conn)?;
+        }
+        Ok(())
+    }
+    
+    /* for a read of every user's rows: waits until the writes numbered so far are committed (writes started later number after them) */
+    fn sequence_read_lock(conn: &mut PgConnection, key: i32) -> QueryResult<()> {
+        diesel::sql_query("SELECT pg_advisory_xact_lock($1)")
+            .bind::<diesel::sql_types::BigInt, _>(key as i64)
+            .execute(conn)?;
+        Ok(())
+    }
+    
+    /* moves the entry to the end of the change sequence (inside entry_transaction) */
+    fn entry_change_record(conn: &mut PgConnection, entry_id_str: &str, user_id_str: &str, deleted: bool) -> QueryResult<()> {
+        let change = EntryChangeWrite::new(entry_id_str, user_id_str, deleted, Utc::now().naive_utc());
+        
+        diesel::insert_into(entry_changes::table)
+            .values(&change)
+            .on_conflict(entry_changes::entry_id)
+            .do_update()
+            .set((
+                entry_changes::user_id.eq(excluded(entry_changes::user_id)),
+                entry_changes::seq.eq(excluded(entry_changes::seq)),
+                entry_changes::deleted.eq(excluded(entry_changes::deleted)),
+                entry_changes::changed.eq(excluded(entry_changes::changed)),
+            ))
+            .execute(conn)?;
+        Ok(())
+    }
+    
+    /* entry_revision_record, for a change that leaves the reminders and tags alone */
+    fn entry_revision_record_unlisted(conn: &mut PgConnection, before: Entry, after: Entry, actor: &str, action: &str) -> QueryResult<()> {
+        let after = Self::entry_read_load(conn, after)?;
+        let before = EntryRead::new(before, after.reminders.clone(), after.tags.clone());
+        
+        Self::entry_revision_record(conn, Some(&before), &after, actor, action)
+    }
+    
+    /* records the fields that changed, and who changed them; "before" is None for a new entry */
+    fn entry_revision_record(conn: &mut PgConnection, before: Option<&EntryRead>, after: &EntryRead, actor: &str, action: &str) -> QueryResult<()> {
+        if let Some(revision) = entry_revision(before, after, actor, action, Utc::now().naive_utc()) {
+            diesel::insert_into(entry_revisions::table)
+                .values(&revision)
+                .execute(conn)?;
+        }
+        Ok(())
+    }
+    
+    /* changes after "since" in sequence order; "user_id_str" of None gives every user's */
+    pub fn get_entry_changes_after(&self, since: i64, user_id_str: Option<&str>, tombstones: bool, limit: i64) -> Vec<EntryChange> {
+        let mut query = entry_changes::table
+            .filter(entry_changes::seq.gt(since))
+            .into_boxed();
+        
+        if let Some(n) = user_id_str {
+            query = query.filter(entry_changes::user_id.eq(n.to_string()));
+        }
+        if !tombstones {
+            query = query.filter(entry_changes::deleted.eq(false));
+        }
+        
+        self.pool.get().unwrap().transaction(|conn| {
+            if user_id_str.is_none() {
+                Self::sequence_read_lock(conn, ENTRY_CHANGES_LOCK)?;
+            }
+            query
+                .order(entry_changes::seq.asc())
+                .limit(limit)
+                .load::<EntryChange>(conn)
+        })
+        .unwrap_or_default()
+    }
+    
+    pub fn get_entries_by_ids(&self, entry_ids: &[String]) -> Vec<Entry> {
+        entries
+            .filter(entries::id.eq_any(entry_ids))
+            .filter(entries::deleted_at.is_null())
+            .load::<Entry>(&mut self.pool.get().unwrap())
+            .unwrap_or_default()
+    }
+
+    /* writes a batch of checked changes in one transaction, each under its own savepoint; returns whether it was committed, and the result of every change attempted */
+    pub fn apply_entry_batch(&self, changes: &[BatchChange], atomic: bool, actor: &str) -> (bool, Vec<Result<Option<EntryRead>, String>>) {
+        let mut results = vec![];
+        let user_ids: Vec<&str> = changes
+            .iter()
+            .map(|change| match change {
+                BatchChange::Create(entry, _, _) | BatchChange::Update(entry, _, _) | BatchChange::Delete(entry) => entry.user_id.as_str(),
+            })
+            .collect();
+        
+        let committed = self.entry_transaction(&user_ids, |conn| {
+            for change in changes {
+                let result = match conn.transaction(|conn| Self::apply_entry_change(conn, change, actor)) {
+                    Ok(result) => result,
+                    Err(e) => Err(e.to_string()),
+                };
+                let failed = result.is_err();
+                
+                results.push(result);
+                
+                if failed && atomic {
+                    return Err(diesel::result::Error::RollbackTransaction);
+                }
+            }
+            Ok(())
+        })
+        .is_ok();
+        
+        (committed, results)
+    }
+    
+    /* the inner error is an operation that cannot be applied (nothing has been written for it yet) */
+    fn apply_entry_change(conn: &mut PgConnection, change: &BatchChange, actor: &str) -> QueryResult<Result<Option<EntryRead>, String>> {
+        match change {
+            BatchChange::Create(entry, reminders, names) => {
+                let mut entry = Entry {
+                    id: uuid::Uuid::new_v4().to_string(),
+                    expired: "false".to_string(),
+                    created: Utc::now().naive_utc(),
+                    updated: Utc::now().naive_utc(),
+                    ..entry.clone()
+                };
+                entry_date_normalize(&mut entry);
+                let entry = diesel::insert_into(entries).values(&entry).get_result::<Entry>(conn)?;
+                let reminders = Self::reminders_replace(conn, &entry.id, reminders)?;
+                let names = Self::tags_replace(conn, &entry.id, &entry.user_id, names.as_deref().unwrap_or_default())?;
+                let read = EntryRead::new(entry, reminders, names);
+                Self::entry_change_record(conn, &read.entry.id, &read.entry.user_id, false)?;
+                Self::entry_revision_record(conn, None, &read, actor, ACTION_CREATED)?;
+                
+                Ok(Ok(Some(read)))
+            },
+            BatchChange::Update(entry, reminders, names) => {
+                /* the row is locked and read again: a change made since the operation was checked fails it, rather than being overwritten */
+                let before = match entries.find(&entry.id).filter(entries::deleted_at.is_null()).for_update().get_result::<Entry>(conn).optional()? {
+                    Some(before) if before.updated == entry.updated => Self::entry_read_load(conn, before)?,
+                    Some(_) => { return Ok(Err(String::from("Entry changed since it was checked"))); },
+                    None => { return Ok(Err(String::from("Entry not found"))); },
+                };
+                let mut entry = Entry {
+                    updated: Utc::now().naive_utc(),
+                    ..entry.clone()
+                };
+                entry_date_normalize(&mut entry);
+                let entry = diesel::update(entries.find(&entry.id)).set(&entry).get_result::<Entry>(conn)?;
+                let reminders = Self::reminders_replace(conn, &entry.id, reminders)?;
+                let names = match names {
+                    Some(names) => Self::tags_replace(conn, &entry.id, &entry.user_id, names)?,
+                    None => before.tags.clone(),
+                };
+                let read = EntryRead::new(entry, reminders, names);
+                Self::entry_change_record(conn, &read.entry.id, &read.entry.user_id, false)?;
+                Self::entry_revision_record(conn, Some(&before), &read, actor, ACTION_UPDATED)?;
+                
+                Ok(Ok(Some(read)))
+            },
+            BatchChange::Delete(entry) => {
+                /* as for an update, a change made since the operation was checked fails it */
+                match entries.find(&entry.id).filter(entries::deleted_at.is_null()).for_update().get_result::<Entry>(conn).optional()? {
+                    Some(before) if before.updated == entry.updated => {},
+                    Some(_) => { return Ok(Err(String::from("Entry changed since it was checked"))); },
+                    None => { return Ok(Err(String::from("Entry not found"))); },
+                };
+                match Self::entry_delete(conn, &entry.id, Some(entry.updated), actor)? {
+                    0 => Ok(Err(String::from("Entry not found"))),
+                    _ => Ok(Ok(None)),
+                }
+            },
+        }
+    }
+
+    /**************************************************************************/
+    /* TRASH actions*/
+    /**************************************************************************/
+    /* most recently deleted first; "username_str" of None gives every user's */
+    pub fn get_trashed_entries(&self, username_str: Option<&str>) -> Vec<Entry> {
+        let mut query = entries::table
+            .filter(entries::deleted_at.is_not_null())
+            .into_boxed();
+        
+        if let Some(n) = username_str {
+            query = query.filter(entries::username.eq(n.to_string()));
+        }
+        
+        query
+            .order(entries::deleted_at.desc())
+            .load::<Entry>(&mut self.pool.get().unwrap())
+            .unwrap_or_default()
+    }
+    
+    pub fn get_trashed_entry_by_id(&self, entry_id: &str) -> Option<Entry> {
+        entries
+            .find(entry_id)
+            .filter(entries::deleted_at.is_not_null())
             .get_result::<Entry>(&mut self.pool.get().unwrap())
-            .expect("Error updating entry by id");
-        Some(entry)
+            .optional()
+            .unwrap_or_default()
+    }
+    
+    pub fn restore_entry_by_id(&self, entry: &Entry, actor: &str) -> Option<Entry> {
+        let entry_id = entry.id.as_str();
+        self.entry_transaction(&[&entry.user_id], |conn| {
+            let before = match entries.find(entry_id).filter(entries::deleted_at.is_not_null()).get_result::<Entry>(conn).optional()? {
+                Some(before) => before,
+                None => { return Ok(None); },
+            };
+            let entry = diesel::update(entries.find(entry_id))
+                .set((entries::deleted_at.eq(None::<NaiveDateTime>), entries::updated.eq(Utc::now().naive_utc())))
+                .get_result::<Entry>(conn)?;
+            
+            Self::entry_change_record(conn, &entry.id, &entry.user_id, false)?;
+            Self::entry_revision_record_unlisted(conn, before, entry.clone(), actor, ACTION_RESTORED)?;
+            Ok(Some(entry))
+        })
+        .expect("Error restoring entry by id")
+    }
+    
+    /* permanently deletes entries that have been in the trash since before "time" (their reminders and notifications go with them) */
+    pub fn purge_trash_before(&self, time: NaiveDateTime) -> usize {
+        diesel::delete(entries::table.filter(entries::deleted_at.lt(time)))
+            .execute(&mut self.pool.get().unwrap())
+            .expect("Error purging trash")
+    }
+
+    /**************************************************************************/
+    /* REVISION actions*/
+    /**************************************************************************/
+    /* oldest first */
+    pub fn get_entry_revisions(&self, entry_id_str: &str) -> Vec<EntryRevision> {
+        entry_revisions::table
+            .filter(entry_revisions::entry_id.eq(entry_id_str))
+            .order(entry_revisions::id.asc())
+            .load::<EntryRevision>(&mut self.pool.get().unwrap())
+            .unwrap_or_default()
+    }
+
+    /**************************************************************************/
+    /* LIST actions*/
+    /**************************************************************************/
+    pub fn create_list(&self, list: EntryList) -> EntryList {
+        let list = EntryList {
+            id: uuid::Uuid::new_v4().to_string(),
+            created: Utc::now().naive_utc(),
+            updated: Utc::now().naive_utc(),
+            ..list
+        };
+        
+        diesel::insert_into(lists::table)
+            .values(&list)
+            .get_result::<EntryList>(&mut self.pool.get().unwrap())
+            .expect("Error creating list")
+    }
+    
+    /* in sort order, then by name */
+    pub fn get_lists(&self) -> Vec<EntryList> {
+        lists::table
+            .order((lists::sort_order.asc(), lists::name.asc()))
+            .load::<EntryList>(&mut self.pool.get().unwrap())
+            .unwrap_or_default()
+    }
+    
+    pub fn get_lists_by_user_id(&self, user_id_str: &str) -> Vec<EntryList> {
+        lists::table
+            .filter(lists::user_id.eq(user_id_str))
+            .order((lists::sort_order.asc(), lists::name.asc()))
+            .load::<EntryList>(&mut self.pool.get().unwrap())
+            .unwrap_or_default()
+    }
+    
+    pub fn get_list_by_id(&self, list_id_str: &str) -> Option<EntryList> {
+        lists::table
+            .find(list_id_str)
+            .get_result::<EntryList>(&mut self.pool.get().unwrap())
+            .optional()
+            .unwrap_or_default()
+    }
+    
+    pub fn update_list_by_id(&self, list_id_str: &str, mut list: EntryList) -> Option<EntryList> {
+        list.updated = Utc::now().naive_utc();
+        
+        diesel::update(lists::table.find(list_id_str))
+            .set(&list)
+            .get_result::<EntryList>(&mut self.pool.get().unwrap())
+            .optional()
+            .expect("Error updating list by id")
+    }
+    
+    /* the entries still in the list (outside the trash) move to the inbox, or to the trash when "cascade"; returns each as it was, and as it is now (None in the trash) */
+    pub fn delete_list_by_id(&self, list: &EntryList, cascade: bool, actor: &str) -> Vec<(Entry, Option<Entry>)> {
+        let list_id_str = list.id.as_str();
+        self.entry_transaction(&[&list.user_id], |conn| {
+            /* entries in the trash are moved to the inbox either way (rather than losing the list to the foreign key unrecorded), and stay
+               in the trash */
+            let listed = entries
+                .filter(entries::list_id.eq(list_id_str))
+                .load::<Entry>(conn)?;
+            let mut changed = vec![];
+            
+            for entry in listed {
+                if cascade && entry.deleted_at.is_none() {
+                    Self::entry_delete(conn, &entry.id, None, actor)?;
+                    changed.push((entry, None));
+                } else {
+                    let moved = diesel::update(entries.find(&entry.id))
+                        .set((entries::list_id.eq(None::<String>), entries::updated.eq(Utc::now().naive_utc())))
+                        .get_result::<Entry>(conn)?;
+                    Self::entry_change_record(conn, &moved.id, &moved.user_id, moved.deleted_at.is_some())?;
+                    Self::entry_revision_record_unlisted(conn, entry.clone(), moved.clone(), actor, ACTION_UPDATED)?;
+                    changed.push((entry, Some(moved)));
+                }
+            }
+            
+            diesel::delete(lists::table.find(list_id_str)).execute(conn)?;
+            Ok(changed)
+        })
+        .expect("Error deleting list by id")
+    }
+
+    /**************************************************************************/
+    /* TAG actions*/
+    /**************************************************************************/
+    /* the names are already normalized; the user's tags that no entry carries any more are removed */
+    fn tags_replace(conn: &mut PgConnection, entry_id_str: &str, user_id_str: &str, names: &[String]) -> QueryResult<Vec<String>> {
+        let list: Vec<Tag> = names
+            .iter()
+            .map(|n| Tag {
+                id: uuid::Uuid::new_v4().to_string(),
+                user_id: String::from(user_id_str),
+                name: n.clone(),
+                created: Utc::now().naive_utc(),
+            })
+            .collect();
+        
+        diesel::insert_into(tags::table)
+            .values(&list)
+            .on_conflict((tags::user_id, tags::name))
+            .do_nothing()
+            .execute(conn)?;
+        
+        let links: Vec<EntryTag> = tags::table
+            .filter(tags::user_id.eq(user_id_str))
+            .filter(tags::name.eq_any(names))
+            .select(tags::id)
+            .load::<String>(conn)?
+            .into_iter()
+            .map(|tag_id| EntryTag {
+                entry_id: String::from(entry_id_str),
+                tag_id,
+            })
+            .collect();
+        
+        diesel::delete(entry_tags::table.filter(entry_tags::entry_id.eq(entry_id_str)))
+            .execute(conn)?;
+        diesel::insert_into(entry_tags::table)
+            .values(&links)
+            .execute(conn)?;
+        diesel::delete(tags::table
+            .filter(tags::user_id.eq(user_id_str))
+            .filter(diesel::dsl::not(diesel::dsl::exists(entry_tags::table.filter(entry_tags::tag_id.eq(tags::id))))))
+            .execute(conn)?;
+        
+        Self::entry_tags_load(conn, entry_id_str)
+    }
+    
+    /* by name */
+    fn entry_tags_load(conn: &mut PgConnection, entry_id_str: &str) -> QueryResult<Vec<String>> {
+        entry_tags::table
+            .inner_join(tags::table)
+            .filter(entry_tags::entry_id.eq(entry_id_str))
+            .select(tags::name)
+            .order(tags::name.asc())
+            .load::<String>(conn)
+    }
+    
+    pub fn get_tags_by_entry_id(&self, entry_id_str: &str) -> Vec<String> {
+        Self::entry_tags_load(&mut self.pool.get().unwrap(), entry_id_str)
+            .expect("Error loading tags for entry")
+    }
+    
+    /* (entry id, tag name) pairs, by name */
+    pub fn get_tags_by_entry_ids(&self, entry_ids: &[String]) -> Vec<(String, String)> {
+        entry_tags::table
+            .inner_join(tags::table)
+            .filter(entry_tags::entry_id.eq_any(entry_ids))
+            .select((entry_tags::entry_id, tags::name))
+            .order(tags::name.asc())
+            .load::<(String, String)>(&mut self.pool.get().unwrap())
+            .expect("Error loading tags for entries")
+    }
+    
+    /* the user's tags by name, counting only entries outside the trash */
+    pub fn get_tag_counts(&self, user_id_str: &str) -> Vec<TagRead> {
+        tags::table
+            .inner_join(entry_tags::table.inner_join(entries::table))
+            .filter(tags::user_id.eq(user_id_str))
+            .filter(entries::deleted_at.is_null())
+            .group_by(tags::name)
+            .select((tags::name, diesel::dsl::count(entry_tags::entry_id)))
+            .order(tags::name.asc())
+            .load::<(String, i64)>(&mut self.pool.get().unwrap())
+            .unwrap_or_default()
+            .into_iter()
+            .map(|(name, count)| TagRead { name, count })
+            .collect()
+    }
+
+    /**************************************************************************/
+    /* REMINDER actions*/
+    /**************************************************************************/
+    fn reminders_replace(conn: &mut PgConnection, entry_id_str: &str, reminders: &[ReminderWrite]) -> QueryResult<Vec<Reminder>> {
+        let list: Vec<Reminder> = reminders
+            .iter()
+            .map(|r| Reminder {
+                id: uuid::Uuid::new_v4().to_string(),
+                entry_id: String::from(entry_id_str),
+                offset: r.offset.clone(),
+                channel: String::from(r.channel()),
+            })
+            .collect();
+        
+        diesel::delete(entry_reminders::table.filter(entry_reminders::entry_id.eq(entry_id_str)))
+            .execute(conn)?;
+        diesel::insert_into(entry_reminders::table)
+            .values(&list)
+            .execute(conn)?;
+        
+        Ok(list)
+    }
+    
+    /* the entry with its reminders and tags, read on the connection of a write in progress */
+    fn entry_read_load(conn: &mut PgConnection, entry: Entry) -> QueryResult<EntryRead> {
+        let reminders = Self::entry_reminders_load(conn, &entry.id)?;
+        let names = Self::entry_tags_load(conn, &entry.id)?;
+        
+        Ok(EntryRead::new(entry, reminders, names))
+    }
+    
+    fn entry_reminders_load(conn: &mut PgConnection, entry_id_str: &str) -> QueryResult<Vec<Reminder>> {
+        entry_reminders::table
+            .filter(entry_reminders::entry_id.eq(entry_id_str))
+            .load::<Reminder>(conn)
+    }
+    
+    pub fn get_reminders_by_entry_id(&self, entry_id_str: &str) -> Vec<Reminder> {
+        Self::entry_reminders_load(&mut self.pool.get().unwrap(), entry_id_str)
+            .expect("Error loading reminders for entry")
+    }
+    
+    pub fn get_reminders_by_entry_ids(&self, entry_ids: &[String]) -> Vec<Reminder> {
+        entry_reminders::table
+            .filter(entry_reminders::entry_id.eq_any(entry_ids))
+            .load::<Reminder>(&mut self.pool.get().unwrap())
+            .expect("Error loading reminders for entries")
+    }
+    
+    /**************************************************************************/
+    /* NOTIFICATION actions*/
+    /**************************************************************************/
+    pub fn create_notification(&self, notification: Notification) -> Option<Notification> {
+        /* the dedupe key makes planning the same notification twice a no-op */
+        let count = diesel::insert_into(notifications::table)
+            .values(&notification)
+            .on_conflict(notifications::dedupe_key)
+            .do_nothing()
+            .execute(&mut self.pool.get().unwrap())
+            .expect("Error creating new notification");
+        
+        if count == 1 {
+            Some(notification)
+        } else {
+            None
+        }
+    }
+    
+    pub fn get_notifications_by_entry_id(&self, entry_id_str: &str) -> Vec<Notification> {
+        notifications::table
+            .filter(notifications::entry_id.eq(entry_id_str))
+            .load::<Notification>(&mut self.pool.get().unwrap())
+            .expect("Error loading notifications for entry")
+    }
+    
+    pub fn get_notifications_due(&self, now: NaiveDateTime) -> Vec<Notification> {
+        notifications::table
+            .filter(notifications::status.eq(STATUS_PLANNED))
+            .filter(notifications::scheduled.le(now))
+            .order(notifications::scheduled.asc())
+            .load::<Notification>(&mut self.pool.get().unwrap())
+            .expect("Error loading due notifications")
+    }
+    
+    pub fn update_notification_scheduled_by_id(&self, notification_id: &str, scheduled: NaiveDateTime) -> Option<Notification> {
+        diesel::update(notifications::table.find(notification_id))
+            .set(notifications::scheduled.eq(scheduled))
+            .get_result::<Notification>(&mut self.pool.get().unwrap())
+            .ok()
+    }
+    
+    /* moves a planned notification to "sending"; false if another run already took it */
+    pub fn claim_notification_by_id(&self, notification_id: &str) -> bool {
+        let count = diesel::update(
+                notifications::table
+                    .find(notification_id)
+                    .filter(notifications::status.eq(STATUS_PLANNED))
+            )
+            .set(notifications::status.eq(STATUS_SENDING))
+            .execute(&mut self.pool.get().unwrap())
+            .expect("Error claiming notification");
+        
+        count == 1
+    }
+    
+    pub fn update_notification_status_by_id(&self, notification_id: &str, status: &str) -> Option<Notification> {
+        diesel::update(notifications::table.find(notification_id))
+            .set((
+                notifications::status.eq(status),
+                notifications::processed.eq(Some(Utc::now().naive_utc())),
+            ))
+            .get_result::<Notification>(&mut self.pool.get().unwrap())
+            .ok()
+    }
+    
+    /* hands a claimed notification over to the outbox in one step */
+    pub fn queue_notification_by_id(&self, notification_id: &str, message: OutboxMessage) -> Option<OutboxMessage> {
+        self.pool.get().unwrap().transaction::<_, diesel::result::Error, _>(|conn| {
+            diesel::insert_into(outbox::table)
+                .values(&message)
+                .execute(conn)?;
+            diesel::update(notifications::table.find(notification_id))
+                .set((
+                    notifications::status.eq(STATUS_QUEUED),
+                    notifications::processed.eq(Some(Utc::now().naive_utc())),
+                ))
+                .execute(conn)?;
+            Ok(message)
+        })
+        .ok()
     }
     
-    pub fn delete_entry_by_id(&self, entry_id: &str) -> Option<usize> {
-        let count = diesel::delete(entries.find(entry_id))
+    /* anything left "sending" by a previous run never made it to the outbox */
+    pub fn reset_notifications_sending(&self) -> usize {
+        diesel::update(notifications::table.filter(notifications::status.eq(STATUS_SENDING)))
+            .set(notifications::status.eq(STATUS_PLANNED))
             .execute(&mut self.pool.get().unwrap())
-            .expect("Error deleting entry by id");
+            .expect("Error resetting notifications")
+    }
+    
+    pub fn delete_notification_by_id(&self, notification_id: &str) -> Option<usize> {
+        let count = diesel::delete(notifications::table.find(notification_id))
+            .execute(&mut self.pool.get().unwrap())
+            .expect("Error deleting notification by id");
+        Some(count)
+    }
+
+    /**************************************************************************/
+    /* OUTBOX actions*/
+    /**************************************************************************/
+    pub fn get_outbox_by_filter(&self, params: &OutboxParams) -> Vec<OutboxMessage> {
+        let mut query = outbox::table.into_boxed();
+        
+        if let Some(n) = &params.status {
+            query = query.filter(outbox::status.eq(n));
+        }
+        
+        query
+            .order(outbox::created.desc())
+            .load::<OutboxMessage>(&mut self.pool.get().unwrap())
+            .unwrap_or_default()
+    }
+    
+    pub fn get_outbox_by_id(&self, message_id: &str) -> Option<OutboxMessage> {
+        outbox::table
+            .find(message_id)
+            .get_result::<OutboxMessage>(&mut self.pool.get().unwrap())
+            .ok()
+    }
+    
+    pub fn get_outbox_due(&self, now: NaiveDateTime) -> Vec<OutboxMessage> {
+        outbox::table
+            .filter(outbox::status.eq(OUTBOX_PENDING))
+            .filter(outbox::next_attempt.le(now))
+            .order(outbox::next_attempt.asc())
+            .load::<OutboxMessage>(&mut self.pool.get().unwrap())
+            .expect("Error loading due outbox messages")
+    }
+    
+    /* moves a pending message to "sending"; false if another run already took it */
+    pub fn claim_outbox_by_id(&self, message_id: &str) -> bool {
+        let count = diesel::update(
+                outbox::table
+                    .find(message_id)
+                    .filter(outbox::status.eq(OUTBOX_PENDING))
+            )
+            .set(outbox::status.eq(OUTBOX_SENDING))
+            .execute(&mut self.pool.get().unwrap())
+            .expect("Error claiming outbox message");
+        
+        count == 1
+    }
+    
+    pub fn update_outbox_by_id(&self, message_id: &str, mut message: OutboxMessage) -> Option<OutboxMessage> {
+        message.updated = Utc::now().naive_utc();
+        diesel::update(outbox::table.find(message_id))
+            .set(&message)
+            .get_result::<OutboxMessage>(&mut self.pool.get().unwrap())
+            .ok()
+    }
+    
+    /* resets a message for another round of attempts; None if the worker changed it since it was read */
+    pub fn retry_outbox_by_id(&self, message: &OutboxMessage) -> Option<OutboxMessage> {
+        let now = Utc::now().naive_utc();
+        
+        diesel::update(
+                outbox::table
+                    .find(&message.id)
+                    .filter(outbox::status.eq(&message.status))
+                    .filter(outbox::attempts.eq(message.attempts))
+            )
+            .set((
+                outbox::status.eq(OUTBOX_PENDING),
+                outbox::attempts.eq(0),
+                outbox::next_attempt.eq(now),
+                outbox::updated.eq(now),
+            ))
+            .get_result::<OutboxMessage>(&mut self.pool.get().unwrap())
+            .ok()
+    }
+    
+    /* a message that was mid-send when the server stopped is tried again */
+    pub fn reset_outbox_sending(&self) -> usize {
+        diesel::update(outbox::table.filter(outbox::status.eq(OUTBOX_SENDING)))
+            .set(outbox::status.eq(OUTBOX_PENDING))
+            .execute(&mut self.pool.get().unwrap())
+            .expect("Error resetting outbox")
+    }
+
+    /**************************************************************************/
+    /* USER CHANNEL actions*/
+    /**************************************************************************/
+    pub fn get_user_channels_by_user_id(&self, user_id_str: &str) -> Vec<UserChannel> {
+        user_channels::table
+            .filter(user_channels::user_id.eq(user_id_str))
+            .order(user_channels::channel.asc())
+            .load::<UserChannel>(&mut self.pool.get().unwrap())
+            .expect("Error loading channels for user")
+    }
+    
+    pub fn get_user_channel_by_user_id(&self, user_id_str: &str, channel_str: &str) -> Option<UserChannel> {
+        user_channels::table
+            .filter(user_channels::user_id.eq(user_id_str))
+            .filter(user_channels::channel.eq(channel_str))
+            .first::<UserChannel>(&mut self.pool.get().unwrap())
+            .ok()
+    }
+    
+    /* creates the user's settings for a channel, or replaces them if they already exist */
+    pub fn set_user_channel(&self, channel: UserChannel) -> UserChannel {
+        let channel = match self.get_user_channel_by_user_id(&channel.user_id, &channel.channel) {
+            Some(existing) => UserChannel {
+                id: existing.id,
+                ..channel
+            },
+            None => UserChannel {
+                id: uuid::Uuid::new_v4().to_string(),
+                ..channel
+            },
+        };
+        
+        diesel::insert_into(user_channels::table)
+            .values(&channel)
+            .on_conflict(user_channels::id)
+            .do_update()
+            .set(&channel)
+            .get_result::<UserChannel>(&mut self.pool.get().unwrap())
+            .expect("Error setting channel for user")
+    }
+    
+    pub fn delete_user_channel_by_user_id(&self, user_id_str: &str, channel_str: &str) -> Option<usize> {
+        let count = diesel::delete(
+                user_channels::table
+                    .filter(user_channels::user_id.eq(user_id_str))
+                    .filter(user_channels::channel.eq(channel_str))
+            )
+            .execute(&mut self.pool.get().unwrap())
+            .expect("Error deleting channel for user");
+        Some(count)
+    }
+
+    /**************************************************************************/
+    /* WEBHOOK actions*/
+    /**************************************************************************/
+    pub fn create_webhook(&self, webhook: WebhookSubscription) -> WebhookSubscription {
+        let webhook = WebhookSubscription {
+            id: uuid::Uuid::new_v4().to_string(),
+            created: Utc::now().naive_utc(),
+            ..webhook
+        };
+        diesel::insert_into(webhooks::table)
+            .values(&webhook)
+            .execute(&mut self.pool.get().unwrap())
+            .expect("Error creating new webhook");
+        webhook
+    }
+    
+    pub fn get_webhooks(&self) -> Vec<WebhookSubscription> {
+        webhooks::table
+            .order(webhooks::created.asc())
+            .load::<WebhookSubscription>(&mut self.pool.get().unwrap())
+            .expect("Error loading all webhooks")
+    }
+    
+    pub fn get_webhooks_by_user_id(&self, user_id_str: &str) -> Vec<WebhookSubscription> {
+        webhooks::table
+            .filter(webhooks::user_id.eq(user_id_str))
+            .order(webhooks::created.asc())
+            .load::<WebhookSubscription>(&mut self.pool.get().unwrap())
+            .expect("Error loading webhooks for user")
+    }
+    
+    /* the webhooks that hear about a user's entries: their own, plus the admin's */
+    pub fn get_webhooks_for_user_id(&self, user_id_str: &str) -> Vec<WebhookSubscription> {
+        let admin_ids = users::table
+            .filter(users::username.eq("admin"))
+            .select(users::id);
+        
+        webhooks::table
+            .filter(webhooks::user_id.eq(user_id_str).or(webhooks::user_id.eq_any(admin_ids)))
+            .load::<WebhookSubscription>(&mut self.pool.get().unwrap())
+            .expect("Error loading webhooks for user")
+    }
+    
+    pub fn get_webhook_by_id(&self, webhook_id_str: &str) -> Option<WebhookSubscription> {
+        webhooks::table
+            .find(webhook_id_str)
+            .get_result::<WebhookSubscription>(&mut self.pool.get().unwrap())
+            .ok()
+    }
+    
+    pub fn delete_webhook_by_id(&self, webhook_id_str: &str) -> Option<usize> {
+        let count = diesel::delete(webhooks::table.find(webhook_id_str))
+            .execute(&mut self.pool.get().unwrap())
+            .expect("Error deleting webhook by id");
+        Some(count)
+    }
+    
+    pub fn create_webhook_delivery(&self, delivery: WebhookDelivery) -> WebhookDelivery {
+        diesel::insert_into(webhook_deliveries::table)
+            .values(&delivery)
+            .execute(&mut self.pool.get().unwrap())
+            .expect("Error creating new webhook delivery");
+        delivery
+    }
+    
+    pub fn get_webhook_deliveries_by_webhook_id(&self, webhook_id_str: &str) -> Vec<WebhookDelivery> {
+        webhook_deliveries::table
+            .filter(webhook_deliveries::webhook_id.eq(webhook_id_str))
+            .order(webhook_deliveries::created.desc())
+            .load::<WebhookDelivery>(&mut self.pool.get().unwrap())
+            .expect("Error loading deliveries for webhook")
+    }
+    
+    pub fn get_webhook_deliveries_due(&self, now: NaiveDateTime) -> Vec<WebhookDelivery> {
+        webhook_deliveries::table
+            .filter(webhook_deliveries::status.eq(DELIVERY_PENDING))
+            .filter(webhook_deliveries::next_attempt.le(now))
+            .order(webhook_deliveries::next_attempt.asc())
+            .load::<WebhookDelivery>(&mut self.pool.get().unwrap())
+            .expect("Error loading due webhook deliveries")
+    }
+    
+    /* moves a pending delivery to "sending"; false if another run already took it */
+    pub fn claim_webhook_delivery_by_id(&self, delivery_id: &str) -> bool {
+        let count = diesel::update(
+                webhook_deliveries::table
+                    .find(delivery_id)
+                    .filter(webhook_deliveries::status.eq(DELIVERY_PENDING))
+            )
+            .set(webhook_deliveries::status.eq(DELIVERY_SENDING))
+            .execute(&mut self.pool.get().unwrap())
+            .expect("Error claiming webhook delivery");
+        
+        count == 1
+    }
+    
+    pub fn update_webhook_delivery_by_id(&self, delivery_id: &str, mut delivery: WebhookDelivery) -> Option<WebhookDelivery> {
+        delivery.updated = Utc::now().naive_utc();
+        diesel::update(webhook_deliveries::table.find(delivery_id))
+            .set(&delivery)
+            .get_result::<WebhookDelivery>(&mut self.pool.get().unwrap())
+            .ok()
+    }
+    
+    /* a delivery that was mid-send when the server stopped is tried again */
+    pub fn reset_webhook_deliveries_sending(&self) -> usize {
+        diesel::update(webhook_deliveries::table.filter(webhook_deliveries::status.eq(DELIVERY_SENDING)))
+            .set(webhook_deliveries::status.eq(DELIVERY_PENDING))
+            .execute(&mut self.pool.get().unwrap())
+            .expect("Error resetting webhook deliveries")
+    }
+
+    /**************************************************************************/
+    /* ENTRY EVENT actions*/
+    /**************************************************************************/
+    pub fn create_entry_event(&self, event: EntryEventWrite) -> Option<EntryEvent> {
+        self.pool.get().unwrap().transaction(|conn| {
+            Self::sequence_lock(conn, ENTRY_EVENTS_LOCK, &[&event.user_id])?;
+            diesel::insert_into(entry_events::table)
+                .values(&event)
+                .get_result::<EntryEvent>(conn)
+        })
+        .ok()
+    }
+    
+    /* nobody listening is not an error */
+    pub fn publish_entry_event(&self, notice: EntryEventNotice) {
+        let _ = self.event_sender.send(notice);
+    }
+    
+    pub fn subscribe_entry_events(&self) -> broadcast::Receiver<EntryEventNotice> {
+        self.event_sender.subscribe()
+    }
+    
+    /* oldest first; "user_id_str" of None gives every user's events */
+    pub fn get_entry_events_after(&self, after_id: i64, user_id_str: Option<&str>, limit: i64) -> Vec<EntryEvent> {
+        let mut query = entry_events::table
+            .filter(entry_events::id.gt(after_id))
+            .into_boxed();
+        
+        if let Some(n) = user_id_str {
+            query = query.filter(entry_events::user_id.eq(n.to_string()));
+        }
+        
+        self.pool.get().unwrap().transaction(|conn| {
+            if user_id_str.is_none() {
+                Self::sequence_read_lock(conn, ENTRY_EVENTS_LOCK)?;
+            }
+            query
+                .order(entry_events::id.asc())
+                .limit(limit)
+                .load::<EntryEvent>(conn)
+        })
+        .unwrap_or_default()
+    }
+    
+    /* 0 when there are none, so that every event is newer */
+    pub fn get_entry_event_id_last(&self) -> i64 {
+        entry_events::table
+            .select(diesel::dsl::max(entry_events::id))
+            .first::<Option<i64>>(&mut self.pool.get().unwrap())
+            .ok()
+            .flatten()
+            .unwrap_or(0)
+    }
+    
+    pub fn get_entry_event_id_first(&self) -> Option<i64> {
+        entry_events::table
+            .select(diesel::dsl::min(entry_events::id))
+            .first::<Option<i64>>(&mut self.pool.get().unwrap())
+            .ok()
+            .flatten()
+    }
+    
+    pub fn delete_entry_events_before(&self, time: NaiveDateTime) -> usize {
+        diesel::delete(entry_events::table.filter(entry_events::created.lt(time)))
+            .execute(&mut self.pool.get().unwrap())
+            .expect("Error deleting old entry events")
+    }
+
+    /**************************************************************************/
+    /* FEED actions*/
+    /**************************************************************************/
+    pub fn get_feed_by_user_id(&self, user_id_str: &str) -> Option<Feed> {
+        feeds::table
+            .filter(feeds::user_id.eq(user_id_str))
+            .first::<Feed>(&mut self.pool.get().unwrap())
+            .ok()
+    }
+    
+    pub fn get_feed_by_token(&self, token_str: &str) -> Option<Feed> {
+        feeds::table
+            .filter(feeds::token.eq(token_str))
+            .first::<Feed>(&mut self.pool.get().unwrap())
+            .ok()
+    }
+    
+    /* gives the user a feed token, replacing (and so revoking) any previous one */
+    pub fn set_feed(&self, user_id_str: &str, token_str: &str) -> Feed {
+        let feed = Feed {
+            id: uuid::Uuid::new_v4().to_string(),
+            user_id: String::from(user_id_str),
+            token: String::from(token_str),
+            created: Utc::now().naive_utc(),
+        };
+        
+        self.pool.get().unwrap().transaction::<_, diesel::result::Error, _>(|conn| {
+            diesel::delete(feeds::table.filter(feeds::user_id.eq(user_id_str)))
+                .execute(conn)?;
+            diesel::insert_into(feeds::table)
+                .values(&feed)
+                .execute(conn)?;
+            Ok(())
+        })
+        .expect("Error setting feed for user");
+        
+        feed
+    }
+    
+    pub fn delete_feed_by_user_id(&self, user_id_str: &str) -> Option<usize> {
+        let count = diesel::delete(feeds::table.filter(feeds::user_id.eq(user_id_str)))
+            .execute(&mut self.pool.get().unwrap())
+            .expect("Error deleting feed for user");
+        Some(count)
+    }
+
+    /**************************************************************************/
+    /* IDEMPOTENCY actions*/
+    /**************************************************************************/
+    /* claims the key for this request; when it is already taken, the stored request is returned instead */
+    pub fn claim_idempotency_key(&self, key: &IdempotencyKey, expired_before: NaiveDateTime, abandoned_before: NaiveDateTime) -> Option<IdempotencyKey> {
+        self.pool.get().unwrap().transaction::<_, diesel::result::Error, _>(|conn| {
+            diesel::delete(idempotency_keys::table
+                .filter(idempotency_keys::created.lt(expired_before)
+                    .or(idempotency_keys::response_status.is_null().and(idempotency_keys::created.lt(abandoned_before)))))
+                .execute(conn)?;
+            
+            let claimed = diesel::insert_into(idempotency_keys::table)
+                .values(key)
+                .on_conflict((idempotency_keys::scope, idempotency_keys::idempotency_key))
+                .do_nothing()
+                .execute(conn)?;
+            
+            if claimed == 1 {
+                return Ok(None);
+            }
+            
+            idempotency_keys::table
+                .filter(idempotency_keys::scope.eq(&key.scope))
+                .filter(idempotency_keys::idempotency_key.eq(&key.idempotency_key))
+                .first::<IdempotencyKey>(conn)
+                .optional()
+        })
+        .expect("Error claiming idempotency key")
+    }
+    
+    pub fn update_idempotency_key_by_id(&self, key_id: &str, key: IdempotencyKey) -> Option<IdempotencyKey> {
+        diesel::update(idempotency_keys::table.find(key_id))
+            .set(&key)
+            .get_result::<IdempotencyKey>(&mut self.pool.get().unwrap())
+            .optional()
+            .expect("Error updating idempotency key by id")
+    }
+    
+    /* lets a request that could not be completed be retried under the same key */
+    pub fn delete_idempotency_key_by_id(&self, key_id: &str) -> Option<usize> {
+        let count = diesel::delete(idempotency_keys::table.find(key_id))
+            .execute(&mut self.pool.get().unwrap())
+            .expect("Error deleting idempotency key by id");
         Some(count)
     }
 
@@ -150,6 +1363,15 @@ impl Database {
             .expect("Error loading all users")
     }
     
+    /* what every authenticated request looks up, so only the one row is read */
+    pub fn get_user_by_username(&self, username_str: &str) -> Option<User> {
+        users
+            .filter(users::username.eq(username_str))
+            .first::<User>(&mut self.pool.get().unwrap())
+            .optional()
+            .unwrap_or_default()
+    }
+    
     pub fn get_user_by_id(&self, user_id_str: &str) -> Option<User> {
         let user = users
             .find(user_id_str)
@@ -175,4 +1397,175 @@ impl Database {
             .expect("Error deleting user by id");
         Some(count)
     }
+
+    /**************************************************************************/
+    /* AUDIT actions*/
+    /**************************************************************************/
+    pub fn create_audit_event(&self, event: AuditEventWrite) -> Option<AuditEvent> {
+        diesel::insert_into(audit_log::table)
+            .values(&event)
+            .get_result::<AuditEvent>(&mut self.pool.get().unwrap())
+            .ok()
+    }
+    
+    /* newest first */
+    pub fn get_audit_events_by_filter(&self, filter: &AuditFilter, limit: i64) -> Vec<AuditEvent> {
+        Self::audit_filtered(filter)
+            .order(audit_log::id.desc())
+            .limit(limit)
+            .load::<AuditEvent>(&mut self.pool.get().unwrap())
+            .unwrap_or_default()
+    }
+    
+    /* oldest first, after the event with id "after_id"; for streaming an export */
+    pub fn get_audit_events_page(&self, filter: &AuditFilter, after_id: i64, limit: i64) -> Vec<AuditEvent> {
+        Self::audit_filtered(filter)
+            .filter(audit_log::id.gt(after_id))
+            .order(audit_log::id.asc())
+            .limit(limit)
+            .load::<AuditEvent>(&mut self.pool.get().unwrap())
+            .unwrap_or_default()
+    }
+    
+    fn audit_filtered(filter: &AuditFilter) -> audit_log::BoxedQuery<'_, diesel::pg::Pg> {
+        let mut query = audit_log::table.into_boxed();
+        
+        if let Some(n) = &filter.action {
+            query = query.filter(audit_log::action.eq(n));
+        }
+        if let Some(n) = &filter.actor {
+            query = query.filter(audit_log::actor.eq(n));
+        }
+        if let Some(n) = &filter.ip {
+            query = query.filter(audit_log::ip.eq(n));
+        }
+        if let Some(n) = filter.since {
+            query = query.filter(audit_log::created.ge(n));
+        }
+        if let Some(n) = filter.until {
+            query = query.filter(audit_log::created.lt(n));
+        }
+        
+        query
+    }
+}
+
+/* these run against the database in DATABASE_URL and are skipped when it is not set */
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::entry::entry_fixture;
+
+    /* these tests write to the database in DATABASE_URL, so they only run when asked for: cargo test -- --ignored */
+    fn database() -> Database {
+        Database::new()
+    }
+
+    fn entry(db: &Database) -> Entry {
+        let entry = Entry {
+            username: String::from("test"),
+            created: Utc::now().naive_utc(),
+            updated: Utc::now().naive_utc(),
+            ..entry_fixture()
+        };
+
+        db.create_entry(entry, &[], &[], &uuid::Uuid::new_v4().to_string(), "test").unwrap().entry
+    }
+
+    /* a change numbered while another writer's transaction is open is committed after it, so a sync never passes a number still to appear */
+    #[test]
+    #[ignore = "needs the database in DATABASE_URL"]
+    fn change_sequence_without_gaps() {
+        let db = database();
+        let user = uuid::Uuid::new_v4().to_string();
+        let first = uuid::Uuid::new_v4().to_string();
+        let second = uuid::Uuid::new_v4().to_string();
+        let (recorded, waiting) = std::sync::mpsc::channel();
+
+        let writer = {
+            let (db, user, first) = (db.clone(), user.clone(), first.clone());
+
+            std::thread::spawn(move || {
+                db.entry_transaction(&[&user], |conn| {
+                    Database::entry_change_record(conn, &first, &user, false)?;
+                    recorded.send(()).unwrap();
+                    std::thread::sleep(std::time::Duration::from_millis(300));
+                    Ok(())
+                })
+                .unwrap();
+            })
+        };
+
+        waiting.recv().unwrap();
+        db.entry_transaction(&[&user], |conn| Database::entry_change_record(conn, &second, &user, false)).unwrap();
+
+        /* once the second change can be read, so can the first, numbered before it */
+        let changes = db.get_entry_changes_after(0, Some(&user), true, 10);
+        writer.join().unwrap();
+
+        assert!(changes.len() == 2 && changes[0].entry_id == first && changes[1].entry_id == second && changes[0].seq < changes[1].seq);
+    }
+
+    /* another user's write does not wait for the open transaction, but a read of every user's changes does */
+    #[test]
+    #[ignore = "needs the database in DATABASE_URL"]
+    fn change_sequence_across_users() {
+        let db = database();
+        let (user, other) = (uuid::Uuid::new_v4().to_string(), uuid::Uuid::new_v4().to_string());
+        let (first, second) = (uuid::Uuid::new_v4().to_string(), uuid::Uuid::new_v4().to_string());
+        let since = db.get_entry_changes_after(0, None, true, i64::MAX).last().map(|c| c.seq).unwrap_or_default();
+        let (recorded, waiting) = std::sync::mpsc::channel();
+
+        let writer = {
+            let (db, user, first) = (db.clone(), user.clone(), first.clone());
+
+            std::thread::spawn(move || {
+                db.entry_transaction(&[&user], |conn| {
+                    Database::entry_change_record(conn, &first, &user, false)?;
+                    recorded.send(()).unwrap();
+                    std::thread::sleep(std::time::Duration::from_millis(300));
+                    Ok(())
+                })
+                .unwrap();
+            })
+        };
+
+        waiting.recv().unwrap();
+        let started = std::time::Instant::now();
+        db.entry_transaction(&[&other], |conn| Database::entry_change_record(conn, &second, &other, false)).unwrap();
+        let unblocked = started.elapsed() < std::time::Duration::from_millis(200);
+
+        let changes: Vec<String> = db.get_entry_changes_after(since, None, true, i64::MAX)
+            .into_iter()
+            .map(|c| c.entry_id)
+            .filter(|e| *e == first || *e == second)
+            .collect();
+        writer.join().unwrap();
+
+        assert!(unblocked && changes == vec![first, second]);
+    }
+
+    #[test]
+    #[ignore = "needs the database in DATABASE_URL"]
+    fn update_refused_after_change() {
+        let db = database();
+        let read = entry(&db);
+
+        let first = db.update_entry_by_id(&read.id, Entry { task: String::from("Pay rent early"), ..read.clone() }, None, None, "test");
+        let second = db.update_entry_by_id(&read.id, Entry { completed: String::from("true"), ..read.clone() }, None, None, "test");
+        let current = db.get_entry_by_id(&read.id).unwrap();
+
+        assert!(first.is_some() && second.is_none() && current.task == "Pay rent early" && current.completed == "false");
+    }
+
+    #[test]
+    #[ignore = "needs the database in DATABASE_URL"]
+    fn delete_refused_after_change() {
+        let db = database();
+        let read = entry(&db);
+
+        db.update_entry_by_id(&read.id, Entry { task: String::from("Pay rent early"), ..read.clone() }, None, None, "test");
+
+        assert!(db.delete_entry_unchanged(&read, "test") == 0 && db.get_entry_by_id(&read.id).is_some());
+    }
 }
\ No newline at end of file
@@ -0,0 +1,139 @@
+/*************************************************************************
+    "task_server"
+    timer/deliveries.rs
+    10/18/26
+    © Matthew Bentivegna
+*************************************************************************/
+use std::{
+    thread,
+    time::Duration,
+    sync::Arc,
+};
+use actix_web::web::Data;
+use crate::events::events::signature;
+use crate::file::options::Options;
+use crate::timer::target::TargetResolver;
+use crate::models::{
+    outbox::outbox_backoff,
+    webhook::{
+        WebhookDelivery,
+        WebhookSubscription,
+        DELIVERY_PENDING,
+        DELIVERY_DELIVERED,
+        DELIVERY_DEAD,
+    },
+};
+use crate::repository::database::Database;
+
+const POLL_SECONDS: u64 = 10;
+const TIMEOUT_SECONDS: u64 = 10;
+
+/* sends queued entry lifecycle events to webhook subscribers, with the same retry policy as the outbox */
+pub fn run(db: Data<Database>, options: Arc<Options>) {
+    let agent = ureq::AgentBuilder::new()
+        .timeout(Duration::from_secs(TIMEOUT_SECONDS))
+        .resolver(TargetResolver::new(options.webhook_allowed_hosts()))
+        .build();
+
+    db.reset_webhook_deliveries_sending();
+
+    thread::spawn(move || {
+        loop {
+            deliver(&db, &options, &agent);
+
+            thread::sleep(Duration::from_secs(POLL_SECONDS));
+        }
+    });
+}
+
+fn deliver(db: &Database, options: &Options, agent: &ureq::Agent) {
+    let now = chrono::Utc::now().naive_utc();
+
+    for delivery in db.get_webhook_deliveries_due(now) {
+        if !db.claim_webhook_delivery_by_id(&delivery.id) {
+            continue;
+        }
+
+        let result = match db.get_webhook_by_id(&delivery.webhook_id) {
+            Some(webhook) => post(agent, &webhook, &delivery),
+            None => (None, Err(String::from("webhook no longer exists"))),
+        };
+        let delivery = attempt_result(delivery, result, options, chrono::Utc::now().naive_utc());
+
+        db.update_webhook_delivery_by_id(&delivery.id.clone(), delivery);
+    }
+}
+
+fn post(agent: &ureq::Agent, webhook: &WebhookSubscription, delivery: &WebhookDelivery) -> (Option<i32>, Result<(), String>) {
+    let response = agent
+        .post(&webhook.url)
+        .set("Content-Type", "application/json")
+        .set("X-Event", &delivery.event)
+        .set("X-Delivery", &delivery.id)
+        .set("X-Signature", &signature(&webhook.secret, &delivery.payload))
+        .send_string(&delivery.payload);
+
+    match response {
+        Ok(r) => (Some(r.status() as i32), Ok(())),
+        Err(ureq::Error::Status(code, _)) => (Some(code as i32), Err(format!["status {code}"])),
+        Err(e) => (None, Err(e.to_string())),
+    }
+}
+
+/* applies the outcome of one delivery attempt */
+fn attempt_result(mut delivery: WebhookDelivery, result: (Option<i32>, Result<(), String>), options: &Options, now: chrono::NaiveDateTime) -> WebhookDelivery {
+    let (response_status, result) = result;
+
+    delivery.attempts += 1;
+    delivery.response_status = response_status;
+
+    match result {
+        Ok(()) => {
+            delivery.status = String::from(DELIVERY_DELIVERED);
+            delivery.last_error = None;
+        },
+        Err(e) => {
+            delivery.last_error = Some(e);
+
+            if delivery.attempts as u32 >= options.outbox_max_attempts() {
+                delivery.status = String::from(DELIVERY_DEAD);
+            } else {
+                delivery.status = String::from(DELIVERY_PENDING);
+                delivery.next_attempt = now + outbox_backoff(delivery.attempts, options.outbox_retry_seconds());
+            }
+        },
+    }
+
+    delivery
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDateTime;
+
+    fn delivery() -> WebhookDelivery {
+        WebhookDelivery::new("webhook-1", "entry.created", "{}", NaiveDateTime::MIN)
+    }
+
+    #[test]
+    fn attempt_delivered() {
+        let delivery = attempt_result(delivery(), (Some(204), Ok(())), &Options::default(), NaiveDateTime::MIN);
+
+        assert!(delivery.status == DELIVERY_DELIVERED && delivery.response_status == Some(204));
+    }
+
+    #[test]
+    fn attempt_retry_then_dead() {
+        let options = Options::default();
+        let mut delivery = attempt_result(delivery(), (Some(500), Err(String::from("status 500"))), &options, NaiveDateTime::MIN);
+
+        assert!(delivery.status == DELIVERY_PENDING && delivery.attempts == 1);
+
+        for _ in 1..options.outbox_max_attempts() {
+            delivery = attempt_result(delivery, (None, Err(String::from("refused"))), &options, NaiveDateTime::MIN);
+        }
+
+        assert!(delivery.status == DELIVERY_DEAD && delivery.response_status.is_none());
+    }
+}
\ No newline at end of file
@@ -11,7 +11,14 @@ use lettre::{
     message::header::ContentType,
     transport::smtp::authentication::Credentials,
 };
-use crate::date::date::Date;
+use crate::models::{
+    channel::{
+        UserChannel,
+        CHANNEL_EMAIL,
+    },
+    outbox::OutboxMessage,
+};
+use crate::timer::notifier::Notifier;
 
 pub struct Email {
     cred_user: String,
@@ -28,40 +35,40 @@ impl Email {
         }
     }
     
-    pub fn send(&self, username: &str, email: &str, task: &str, due_date: &Date, expired: bool) -> Result<(), ()> {
+    pub fn send(&self, username: &str, email: &str, subject: &str, body: &str) -> Result<(), String> {
         let from_field = format!["Task-Server <{}>", self.cred_user];
         let to_field = format!["{username} <{email}>"];
-        let body_field = if expired {
-            format!["The following task has expired ({})\n\n{}", due_date.to_string(), task]
-        } else {
-            format!["The following task is due on: {}\n\n{}", due_date.to_string(), task]
-        };
-        let subject_field = if expired {
-            String::from("Task Due Today!")
-        } else {
-            String::from("Task Reminder!")
-        };
         
         let email = Message::builder()
-            .from(from_field.parse().unwrap())
-            .to(to_field.parse().unwrap())
-            .subject(subject_field)
+            .from(from_field.parse().map_err(|e| format!["invalid sender address: {e}"])?)
+            .to(to_field.parse().map_err(|e| format!["invalid recipient address: {e}"])?)
+            .subject(subject)
             .header(ContentType::TEXT_PLAIN)
-            .body(body_field)
-            .unwrap();
+            .body(String::from(body))
+            .map_err(|e| e.to_string())?;
 
         let cred_user = self.cred_user.clone();
         let cred_pass = self.cred_pass.clone();
         let creds = Credentials::new(cred_user.to_owned(), cred_pass.to_owned());
 
         let mailer = SmtpTransport::relay(&self.smtp)
-            .unwrap()
+            .map_err(|e| e.to_string())?
             .credentials(creds)
             .build();
 
         match mailer.send(&email) {
             Ok(_) => Ok(()),
-            Err(_) => Err(()),
+            Err(e) => Err(e.to_string()),
         }
     }
+}
+
+impl Notifier for Email {
+    fn channel(&self) -> &'static str {
+        CHANNEL_EMAIL
+    }
+    
+    fn notify(&self, message: &OutboxMessage, _settings: Option<&UserChannel>) -> Result<(), String> {
+        self.send(&message.recipient_name, &message.recipient_email, &message.subject, &message.body)
+    }
 }
\ No newline at end of file
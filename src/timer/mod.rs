@@ -5,4 +5,9 @@
 	© Matthew Bentivegna
 *************************************************************************/
 pub mod timer;
-pub mod email;
\ No newline at end of file
+pub mod email;
+pub mod outbox;
+pub mod notifier;
+pub mod webhook;
+pub mod target;
+pub mod deliveries;
\ No newline at end of file
@@ -0,0 +1,76 @@
+/*************************************************************************
+    "task_server"
+    timer/notifier.rs
+    10/18/26
+    © Matthew Bentivegna
+*************************************************************************/
+use crate::date::date::Date;
+use crate::models::{
+    channel::UserChannel,
+    outbox::OutboxMessage,
+};
+
+/* a way of delivering an outbox message to a user */
+pub trait Notifier: Send + Sync {
+    fn channel(&self) -> &'static str;
+
+    /* "settings" is the user's configuration for this channel, if they have one */
+    fn notify(&self, message: &OutboxMessage, settings: Option<&UserChannel>) -> Result<(), String>;
+}
+
+pub fn notifier_for<'a>(notifiers: &'a [Box<dyn Notifier>], channel: &str) -> Option<&'a dyn Notifier> {
+    notifiers
+        .iter()
+        .find(|n| n.channel() == channel)
+        .map(|n| n.as_ref())
+}
+
+/* builds the subject and body for a reminder/expired notification */
+pub fn notification_text(task: &str, due_date: &Date, expired: bool) -> (String, String) {
+    let body_field = if expired {
+        format!["The following task has expired ({})\n\n{}", due_date, task]
+    } else {
+        format!["The following task is due on: {}\n\n{}", due_date, task]
+    };
+    let subject_field = if expired {
+        String::from("Task Due Today!")
+    } else {
+        String::from("Task Reminder!")
+    };
+
+    (subject_field, body_field)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Stub(&'static str);
+
+    impl Notifier for Stub {
+        fn channel(&self) -> &'static str {
+            self.0
+        }
+
+        fn notify(&self, _message: &OutboxMessage, _settings: Option<&UserChannel>) -> Result<(), String> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn notifier_lookup() {
+        let notifiers: Vec<Box<dyn Notifier>> = vec![Box::new(Stub("email")), Box::new(Stub("webhook"))];
+
+        assert!(
+            notifier_for(&notifiers, "webhook").map(|n| n.channel()) == Some("webhook") &&
+            notifier_for(&notifiers, "pigeon").is_none()
+        );
+    }
+
+    #[test]
+    fn text_reminder() {
+        let (subject, body) = notification_text("Pay rent", &Date::new(2030, 6, 15).unwrap(), false);
+
+        assert!(subject == "Task Reminder!" && body == "The following task is due on: 06/15/2030\n\nPay rent");
+    }
+}
\ No newline at end of file
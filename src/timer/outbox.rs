@@ -0,0 +1,161 @@
+/*************************************************************************
+    "task_server"
+    timer/outbox.rs
+    10/18/26
+    © Matthew Bentivegna
+*************************************************************************/
+use std::{
+    thread,
+    time::Duration,
+    sync::Arc,
+};
+use actix_web::web::Data;
+use crate::timer::{
+    email::Email,
+    webhook::Webhook,
+    notifier::{
+        Notifier,
+        notifier_for,
+    },
+};
+use crate::file::options::Options;
+use crate::models::outbox::{
+    OutboxMessage,
+    outbox_backoff,
+    OUTBOX_PENDING,
+    OUTBOX_SENT,
+    OUTBOX_DEAD,
+};
+use crate::repository::database::Database;
+
+const POLL_SECONDS: u64 = 10;
+
+pub fn run(db: Data<Database>, options: Arc<Options>) {
+    let notifiers: Vec<Box<dyn Notifier>> = vec![
+        Box::new(
+            Email::new(
+                options.sender_email_address(),
+                options.sender_email_password(),
+                options.sender_email_smtp(),
+            )
+        ),
+        Box::new(Webhook::new(options.webhook_allowed_hosts())),
+    ];
+
+    db.reset_outbox_sending();
+
+    thread::spawn(move || {
+        loop {
+            deliver(&db, &options, &notifiers);
+
+            thread::sleep(Duration::from_secs(POLL_SECONDS));
+        }
+    });
+}
+
+fn deliver(db: &Database, options: &Options, notifiers: &[Box<dyn Notifier>]) {
+    let now = chrono::Utc::now().naive_utc();
+
+    for message in db.get_outbox_due(now) {
+        if !db.claim_outbox_by_id(&message.id) {
+            continue;
+        }
+
+        let result = send(db, notifiers, &message);
+        let message = attempt_result(message, result, options, chrono::Utc::now().naive_utc());
+
+        if message.status == OUTBOX_DEAD {
+            eprintln!("Error: Giving up on outbox message after {} attempts (id: {})", message.attempts, message.id);
+        }
+
+        db.update_outbox_by_id(&message.id.clone(), message);
+    }
+}
+
+fn send(db: &Database, notifiers: &[Box<dyn Notifier>], message: &OutboxMessage) -> Result<(), String> {
+    let notifier = match notifier_for(notifiers, &message.channel) {
+        Some(notifier) => notifier,
+        None => { return Err(format!["no notifier for channel \"{}\"", message.channel]); },
+    };
+
+    let settings = match &message.user_id {
+        Some(user_id) => db.get_user_channel_by_user_id(user_id, &message.channel),
+        None => None,
+    };
+
+    notifier.notify(message, settings.as_ref())
+}
+
+/* applies the outcome of one delivery attempt to a message */
+fn attempt_result(mut message: OutboxMessage, result: Result<(), String>, options: &Options, now: chrono::NaiveDateTime) -> OutboxMessage {
+    message.attempts += 1;
+
+    match result {
+        Ok(()) => {
+            message.status = String::from(OUTBOX_SENT);
+            message.last_error = None;
+        },
+        Err(e) => {
+            message.last_error = Some(e);
+
+            if message.attempts as u32 >= options.outbox_max_attempts() {
+                message.status = String::from(OUTBOX_DEAD);
+            } else {
+                message.status = String::from(OUTBOX_PENDING);
+                message.next_attempt = now + outbox_backoff(message.attempts, options.outbox_retry_seconds());
+            }
+        },
+    }
+
+    message
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDateTime;
+    use crate::models::user::User;
+
+    fn message() -> OutboxMessage {
+        let user = User {
+            id: String::from("user-1"),
+            username: String::from("user"),
+            password: String::from("password"),
+            email: String::from("user@domain.com"),
+            utc_offset: None,
+        };
+
+        OutboxMessage::new(None, &user, "email", "subject", "body", NaiveDateTime::MIN)
+    }
+
+    #[test]
+    fn attempt_sent() {
+        let message = attempt_result(message(), Ok(()), &Options::default(), NaiveDateTime::MIN);
+
+        assert!(message.status == OUTBOX_SENT && message.attempts == 1);
+    }
+
+    #[test]
+    fn attempt_retry() {
+        let now = NaiveDateTime::MIN + chrono::Duration::days(1);
+        let message = attempt_result(message(), Err(String::from("timeout")), &Options::default(), now);
+
+        assert!(
+            message.status == OUTBOX_PENDING &&
+            message.next_attempt == now + chrono::Duration::seconds(60) &&
+            message.last_error == Some(String::from("timeout"))
+        );
+    }
+
+    #[test]
+    fn attempt_dead_letter() {
+        let options = Options::default();
+        let mut message = message();
+
+        for _ in 0..options.outbox_max_attempts() {
+            message = attempt_result(message, Err(String::from("refused")), &options, NaiveDateTime::MIN);
+        }
+
+        assert!(message.status == OUTBOX_DEAD && message.attempts == 5);
+    }
+}
\ No newline at end of file
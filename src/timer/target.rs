@@ -0,0 +1,179 @@
+/*************************************************************************
+    "task_server"
+    timer/target.rs
+    10/18/26
+    © Matthew Bentivegna
+*************************************************************************/
+use std::{
+    io,
+    net::{
+        IpAddr,
+        Ipv4Addr,
+        SocketAddr,
+        ToSocketAddrs,
+    },
+};
+
+/* webhooks are posted by the server itself, so a url chosen by a user must not reach into the server's own network
+   (loopback, link-local, private ranges) unless the admin allows its host in the configuration file */
+pub struct TargetResolver {
+    allowed_hosts: Vec<String>,
+}
+
+impl TargetResolver {
+    pub fn new(allowed_hosts: &[String]) -> Self {
+        Self {
+            allowed_hosts: allowed_hosts.iter().map(|h| h.to_lowercase()).collect(),
+        }
+    }
+
+    /* checked when a url is stored; delivery checks again on every connection (the answer can change) */
+    pub fn check_url(&self, url: &str) -> Result<(), String> {
+        let netloc = url_netloc(url).ok_or("invalid url")?;
+
+        self.resolve_checked(&netloc).map(|_| ())
+    }
+
+    fn resolve_checked(&self, netloc: &str) -> Result<Vec<SocketAddr>, String> {
+        let host = netloc_host(netloc).to_lowercase();
+        let addresses: Vec<SocketAddr> = match netloc.to_socket_addrs() {
+            Ok(addresses) => addresses.collect(),
+            Err(e) => { return Err(format!["cannot resolve {}: {}", host, e]); },
+        };
+
+        if self.allowed_hosts.contains(&host) {
+            return Ok(addresses);
+        }
+
+        if host == "localhost" || host.ends_with(".localhost") || addresses.is_empty() || !addresses.iter().all(|a| address_public(a.ip())) {
+            return Err(format!["{} is not a public address", host]);
+        }
+
+        Ok(addresses)
+    }
+}
+
+impl ureq::Resolver for TargetResolver {
+    fn resolve(&self, netloc: &str) -> io::Result<Vec<SocketAddr>> {
+        self.resolve_checked(netloc).map_err(|e| io::Error::new(io::ErrorKind::PermissionDenied, e))
+    }
+}
+
+/* "host:port" of an http(s) url, with the scheme's default port filled in */
+fn url_netloc(url: &str) -> Option<String> {
+    let (rest, port) = if let Some(rest) = url.strip_prefix("http://") {
+        (rest, 80)
+    } else if let Some(rest) = url.strip_prefix("https://") {
+        (rest, 443)
+    } else {
+        return None;
+    };
+
+    let authority = rest.split(['/', '?', '#']).next().unwrap_or_default();
+    let authority = match authority.rsplit_once('@') {
+        Some((_, authority)) => authority,
+        None => authority,
+    };
+
+    if authority.is_empty() {
+        return None;
+    }
+
+    /* a port is only present after the closing bracket of an IPv6 address */
+    match authority.rfind(':') {
+        Some(i) if !authority[i..].contains(']') => Some(String::from(authority)),
+        _ => Some(format!["{}:{}", authority, port]),
+    }
+}
+
+fn netloc_host(netloc: &str) -> &str {
+    let host = match netloc.rsplit_once(':') {
+        Some((host, _)) => host,
+        None => netloc,
+    };
+
+    host.trim_start_matches('[').trim_end_matches(']')
+}
+
+fn address_public(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => address_public_v4(ip),
+        IpAddr::V6(ip) => {
+            if let Some(ip) = ip.to_ipv4_mapped() {
+                return address_public_v4(ip);
+            }
+
+            !(ip.is_loopback() || ip.is_unspecified() || ip.is_multicast() || ip.is_unique_local() || ip.is_unicast_link_local())
+        },
+    }
+}
+
+fn address_public_v4(ip: Ipv4Addr) -> bool {
+    let octets = ip.octets();
+
+    /* 0.0.0.0/8 ("this network") and 100.64.0.0/10 (carrier-grade NAT) */
+    let reserved = octets[0] == 0 || (octets[0] == 100 && (octets[1] & 0xc0) == 64);
+
+    !(reserved || ip.is_loopback() || ip.is_private() || ip.is_link_local() || ip.is_broadcast() || ip.is_multicast())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn netloc_from_url() {
+        assert_eq!(url_netloc("https://hooks.example.com/a?b"), Some(String::from("hooks.example.com:443")));
+        assert_eq!(url_netloc("http://user:pw@hooks.example.com:8080"), Some(String::from("hooks.example.com:8080")));
+        assert_eq!(url_netloc("http://[::1]/hook"), Some(String::from("[::1]:80")));
+        assert_eq!(url_netloc("http://[::1]:9000/hook"), Some(String::from("[::1]:9000")));
+        assert_eq!(url_netloc("ftp://hooks.example.com"), None);
+        assert_eq!(url_netloc("http:///hook"), None);
+    }
+
+    #[test]
+    fn addresses_public() {
+        let public = |ip: &str| address_public(ip.parse::<IpAddr>().unwrap());
+
+        assert!(
+            public("93.184.216.34") &&
+            public("2606:2800:220:1::1") &&
+            !public("127.0.0.1") &&
+            !public("10.1.2.3") &&
+            !public("172.16.0.1") &&
+            !public("192.168.1.1") &&
+            !public("169.254.169.254") &&
+            !public("100.64.0.1") &&
+            !public("0.0.0.0") &&
+            !public("::1") &&
+            !public("fe80::1") &&
+            !public("fd00::1") &&
+            !public("::ffff:127.0.0.1")
+        );
+    }
+
+    #[test]
+    fn check_rejects_internal() {
+        let resolver = TargetResolver::new(&[]);
+
+        assert!(
+            resolver.check_url("http://93.184.216.34/hook").is_ok() &&
+            resolver.check_url("http://127.0.0.1:8085/api").is_err() &&
+            resolver.check_url("http://localhost/hook").is_err() &&
+            resolver.check_url("http://169.254.169.254/latest/meta-data").is_err() &&
+            resolver.check_url("http://[::1]/hook").is_err() &&
+            resolver.check_url("hooks.example.com").is_err()
+        );
+    }
+
+    #[test]
+    fn check_allowed_host() {
+        let resolver = TargetResolver::new(&[String::from("127.0.0.1"), String::from("LOCALHOST")]);
+
+        assert!(
+            resolver.check_url("http://127.0.0.1:8085/api").is_ok() &&
+            resolver.check_url("http://localhost/hook").is_ok() &&
+            resolver.check_url("http://10.0.0.1/hook").is_err()
+        );
+    }
+}
\ No newline at end of file
@@ -8,110 +8,131 @@ use std::{
     thread,
     time::Duration,
     sync::Arc,
-    str::FromStr,
 };
-use chrono::Timelike;
+use chrono::NaiveDateTime;
 use actix_web::web::Data;
 use crate::date::date::Date;
-use crate::timer::email::Email;
+use crate::timer::notifier::notification_text;
 use crate::file::options::Options;
-use crate::models::user::User;
+use crate::events::events;
+use crate::models::{
+    user::User,
+    entry::{
+        Entry,
+        entry_due_date,
+    },
+    channel::user_channels_enabled,
+    entry_event::ENTRY_EVENT_RETENTION_HOURS,
+    revision::ACTOR_TIMER,
+    webhook::EVENT_EXPIRED,
+    notification::{
+        Notification,
+        notifications_plan,
+        reminder_deliverable,
+        KIND_EXPIRED,
+        STATUS_PLANNED,
+        STATUS_FAILED,
+        STATUS_SKIPPED,
+    },
+    outbox::OutboxMessage,
+};
 use crate::repository::database::Database;
 
 pub fn run(db: Data<Database>, options: Arc<Options>) {
-    let email = Arc::new(
-        Email::new(
-            options.sender_email_address(),
-            options.sender_email_password(),
-            options.sender_email_smtp(),
-        )
-    );
+    db.reset_notifications_sending();
     
     thread::spawn(move || {
+        let mut last_hour = None;
+        
         loop {
-            unsafe {
-                let db_arc = Arc::clone(&db);
-                let options_arc = Arc::clone(&options);
-                let email_arc = Arc::clone(&email);
+            let db_arc = Arc::clone(&db);
+            let options_arc = Arc::clone(&options);
+            
+            /* UTC offsets are whole hours, so every reminder and expiry falls on the hour: one pass each hour (and one at
+               start-up) is enough; planning and queueing are both idempotent, so every pass can safely catch up on missed work */
+            let current_hour = get_current_hour();
+            
+            if last_hour != Some(current_hour) {
+                last_hour = Some(current_hour);
                 
-                static mut LAST_HOUR: i32 = 0;
-                let current_hour = get_current_hour();
-                
-                if current_hour != LAST_HOUR {
-                    LAST_HOUR = current_hour;
-                    
-                    match check_entries(&db_arc, &options_arc, &email_arc, current_hour) {
-                        Ok(()) => {},
-                        Err((e, id)) => { eprintln!("Error: {} (id: {})", e, id) },
-                    }
-                }
-
-                thread::sleep(Duration::from_secs(60));
+                check_entries(&db_arc);
+                send_notifications(&db_arc, &options_arc);
+                purge_entry_events(&db_arc);
+                purge_trash(&db_arc, &options_arc);
             }
+
+            thread::sleep(Duration::from_secs(60));
         }
     });
 }
 
-fn get_current_hour() -> i32 {
-    let current_date = chrono::Utc::now();
-    current_date.hour() as i32
-    
-    /* use the following line instead to check entries once every 24 minutes (for testing) */
-    //(current_date.minute() % 24) as i32
+/* hours since the epoch */
+fn get_current_hour() -> i64 {
+    chrono::Utc::now().timestamp() / 3600
 }
 
-fn check_entries(db: &Database, options: &Options, email: &Email, current_hour: i32) -> Result<(), (String, String)> {
-    let current_date = Date::today();
+fn check_entries(db: &Database) {
+    let now = chrono::Utc::now().naive_utc();
     let entries = db.get_entries();
     
-    for mut entry in entries {
-        /* get user for current entry */
-        let user = match db.get_user_by_id(&entry.user_id) {
-            Some(user) => user,
-            None => { return Err((String::from("Cannot find user for entry"), String::from(entry.id))) }
-        };
-        
-        /* get UTC offset for current user */
-        let utc_offset = match user.utc_offset {
-            Some(ref offset) => {
-                match offset.parse::<i32>() {
-                    Ok(offset) => offset,
-                    Err(_) => { return Err((String::from("Invalid UTC offset for user"), String::from(user.id))) }
-                }
-            },
-            None => 0,
-        };
-        
-        let adjusted_hour = current_hour + utc_offset;
+    for entry in entries {
+        if entry.expired == "true" || entry.completed == "true" {
+            continue;
+        }
         
-        if adjusted_hour == 0 {  /* midnight */
-            let due_date = match Date::from_str(&format!["{}/{}/{}", entry.month, entry.day, entry.year]) {
-                Ok(date) => { date },
-                Err(_) => { return Err((String::from("Cannot parse date for user entry"), String::from(entry.id))) }
-            };
-
-            let date_diff = Date::difference(&due_date, &current_date) + 1;
-
-            /* check for reminder */
-            if options.enable_reminder_emails() {
-                match get_reminder(&entry.reminder) {
-                    Some(reminder) => {
-                        if date_diff == reminder {
-                            send_email(&user, &entry.task, &due_date, false, email);
-                        }
-                    },
-                    _ => {},
-                }
-            }
+        match plan_entry(db, &entry, now) {
+            Ok(()) => {},
+            Err((e, id)) => { eprintln!("Error: {} (id: {})", e, id) },
+        }
+    }
+}
 
-            /* check for expired */
-            if entry.expired == "false" && date_diff <= 0 {
-                if options.enable_expired_emails() {
-                    send_email(&user, &entry.task, &due_date, true, email);
+fn plan_entry(db: &Database, entry: &Entry, now: NaiveDateTime) -> Result<(), (String, String)> {
+    /* get user for current entry */
+    let user = match db.get_user_by_id(&entry.user_id) {
+        Some(user) => user,
+        None => { return Err((String::from("Cannot find user for entry"), entry.id.clone())) }
+    };
+    
+    let utc_offset = get_utc_offset(&user)?;
+    let reminders = db.get_reminders_by_entry_id(&entry.id);
+    let channels = user_channels_enabled(&db.get_user_channels_by_user_id(&user.id));
+    
+    let planned = match notifications_plan(entry, &reminders, &channels, utc_offset, now) {
+        Ok(planned) => planned,
+        Err(_) => { return Err((String::from("Cannot parse date for user entry"), entry.id.clone())) }
+    };
+    let existing = db.get_notifications_by_entry_id(&entry.id);
+    
+    /* drop anything still pending that no longer applies (reminder removed or due date moved) */
+    for notification in &existing {
+        if notification.status == STATUS_PLANNED && !planned.iter().any(|p| p.dedupe_key == notification.dedupe_key) {
+            db.delete_notification_by_id(&notification.id);
+        }
+    }
+    
+    for notification in planned {
+        match existing.iter().find(|e| e.dedupe_key == notification.dedupe_key) {
+            None => {
+                db.create_notification(notification);
+            },
+            Some(e) => {
+                /* follow changes to the user's UTC offset */
+                if e.status == STATUS_PLANNED && e.scheduled != notification.scheduled {
+                    db.update_notification_scheduled_by_id(&e.id, notification.scheduled);
                 }
+            },
+        }
+    }
+    
+    /* check for expired (once planned, so the expired notifications are already in place) */
+    if let Ok(due_date) = entry_due_date(entry) {
+        if now >= due_date.end_of_day_utc(utc_offset) {
+            let mut entry = entry.clone();
+            entry.expired = String::from("true");
 
-                entry.expired = String::from("true");
-                db.update_entry_by_id(&entry.id.clone(), entry);
+            if let Some(read) = db.update_entry_by_id(&entry.id.clone(), entry, None, None, ACTOR_TIMER) {
+                events::dispatch(db, EVENT_EXPIRED, &read.entry);
             }
         }
     }
@@ -119,22 +140,95 @@ fn check_entries(db: &Database, options: &Options, email: &Email, current_hour:
     Ok(())
 }
 
-fn send_email(user: &User, task: &str, date: &Date, is_expired: bool, email: &Email) {
-    match email.send(&user.username, &user.email, task, date, is_expired) {
-        Ok(()) => {},
-        Err(()) => { eprintln!("error sending email"); },
-    }
+/* streams can only resume from events still kept */
+fn purge_entry_events(db: &Database) {
+    let before = chrono::Utc::now().naive_utc() - chrono::Duration::hours(ENTRY_EVENT_RETENTION_HOURS);
+    db.delete_entry_events_before(before);
+}
+
+fn purge_trash(db: &Database, options: &Options) {
+    let before = chrono::Utc::now().naive_utc() - chrono::Duration::days(options.trash_retention_days() as i64);
+    db.purge_trash_before(before);
 }
 
-fn get_reminder(reminder: &Option<String>) -> Option<i64> {
-    if let Some(reminder) = reminder {
-        match reminder.parse::<i64>() {
-            Ok(reminder) => {
-                return Some(reminder);
+fn send_notifications(db: &Database, options: &Options) {
+    let now = chrono::Utc::now().naive_utc();
+    
+    for notification in db.get_notifications_due(now) {
+        if !db.claim_notification_by_id(&notification.id) {
+            continue;
+        }
+        
+        match prepare_notification(db, options, &notification, now) {
+            Ok(Some(message)) => {
+                if db.queue_notification_by_id(&notification.id, message).is_none() {
+                    eprintln!("Error: Cannot queue notification (id: {})", notification.id);
+                    db.update_notification_status_by_id(&notification.id, STATUS_FAILED);
+                }
+            },
+            Ok(None) => {
+                db.update_notification_status_by_id(&notification.id, STATUS_SKIPPED);
+            },
+            Err((e, id)) => {
+                eprintln!("Error: {} (id: {})", e, id);
+                db.update_notification_status_by_id(&notification.id, STATUS_FAILED);
             },
-            Err(_) => {},
         }
     }
+}
+
+/* the message a due notification should produce, or None if it is to be skipped */
+fn prepare_notification(db: &Database, options: &Options, notification: &Notification, now: NaiveDateTime) -> Result<Option<OutboxMessage>, (String, String)> {
+    let entry = match db.get_entry_by_id(&notification.entry_id) {
+        Some(entry) => entry,
+        None => { return Err((String::from("Cannot find entry for notification"), notification.id.clone())) }
+    };
+    
+    let user = match db.get_user_by_id(&entry.user_id) {
+        Some(user) => user,
+        None => { return Err((String::from("Cannot find user for entry"), entry.id.clone())) }
+    };
+    
+    let due_date = match entry_due_date(&entry) {
+        Ok(date) => date,
+        Err(_) => { return Err((String::from("Cannot parse date for user entry"), entry.id.clone())) }
+    };
+    
+    if entry.completed == "true" {
+        return Ok(None);
+    }
+    
+    if notification.kind == KIND_EXPIRED {
+        if !options.enable_expired_emails() {
+            return Ok(None);
+        }
+        
+        return Ok(Some(compose_message(notification, &user, &entry.task, &due_date, true, now)));
+    }
+    
+    let expires = due_date.end_of_day_utc(get_utc_offset(&user)?);
+    
+    if !options.enable_reminder_emails() || !reminder_deliverable(notification.scheduled, expires, now) {
+        return Ok(None);
+    }
+    
+    Ok(Some(compose_message(notification, &user, &entry.task, &due_date, false, now)))
+}
+
+fn get_utc_offset(user: &User) -> Result<i32, (String, String)> {
+    match user.utc_offset {
+        Some(ref offset) => {
+            match offset.parse::<i32>() {
+                Ok(offset) => Ok(offset),
+                Err(_) => Err((String::from("Invalid UTC offset for user"), user.id.clone())),
+            }
+        },
+        None => Ok(0),
+    }
+}
+
+fn compose_message(notification: &Notification, user: &User, task: &str, date: &Date, is_expired: bool, now: NaiveDateTime) -> OutboxMessage {
+    let (subject, body) = notification_text(task, date, is_expired);
     
-    None
+    OutboxMessage::new(Some(&notification.id), user, &notification.channel, &subject, &body, now)
 }
\ No newline at end of file
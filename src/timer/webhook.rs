@@ -0,0 +1,184 @@
+/*************************************************************************
+    "task_server"
+    timer/webhook.rs
+    10/18/26
+    © Matthew Bentivegna
+*************************************************************************/
+use std::time::Duration;
+use crate::models::{
+    channel::{
+        UserChannel,
+        CHANNEL_WEBHOOK,
+    },
+    outbox::OutboxMessage,
+};
+use crate::timer::{
+    notifier::Notifier,
+    target::TargetResolver,
+};
+
+const TIMEOUT_SECONDS: u64 = 10;
+
+/* posts notifications as JSON to a URL chosen by the user (chat webhooks, ntfy-style push, ...) */
+pub struct Webhook {
+    agent: ureq::Agent,
+}
+
+impl Webhook {
+    /* every connection (redirects included) is checked against the internal address ranges */
+    pub fn new(allowed_hosts: &[String]) -> Self {
+        Self {
+            agent: ureq::AgentBuilder::new()
+                .timeout(Duration::from_secs(TIMEOUT_SECONDS))
+                .resolver(TargetResolver::new(allowed_hosts))
+                .build(),
+        }
+    }
+}
+
+impl Notifier for Webhook {
+    fn channel(&self) -> &'static str {
+        CHANNEL_WEBHOOK
+    }
+
+    fn notify(&self, message: &OutboxMessage, settings: Option<&UserChannel>) -> Result<(), String> {
+        let settings = settings.ok_or("no webhook configured for user")?;
+        let url = settings.url.as_ref().ok_or("no webhook url configured for user")?;
+
+        let mut request = self.agent.post(url);
+
+        for (name, value) in settings.headers() {
+            request = request.set(&name, &value);
+        }
+
+        let payload = serde_json::json!({
+            "id": message.id,
+            "recipient": message.recipient_name,
+            "subject": message.subject,
+            "body": message.body,
+        });
+
+        match request.set("Content-Type", "application/json").send_string(&payload.to_string()) {
+            Ok(_) => Ok(()),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{
+        io::{
+            Read,
+            Write,
+        },
+        net::TcpListener,
+        thread,
+    };
+    use chrono::NaiveDateTime;
+    use crate::models::user::User;
+
+    /* a one-shot local HTTP server: answers with "status" and hands back the raw request */
+    fn stand_in(status: &'static str) -> (String, thread::JoinHandle<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!["http://{}/hook", listener.local_addr().unwrap()];
+
+        let handle = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = vec![];
+            let mut buffer = [0u8; 1024];
+
+            /* read until the full body (per content-length) has arrived */
+            loop {
+                let n = stream.read(&mut buffer).unwrap();
+                request.extend_from_slice(&buffer[..n]);
+                let text = String::from_utf8_lossy(&request).to_string();
+
+                if let Some(split) = text.find("\r\n\r\n") {
+                    let length = text[..split]
+                        .lines()
+                        .find_map(|l| l.to_lowercase().strip_prefix("content-length:").map(|v| v.trim().parse::<usize>().unwrap()))
+                        .unwrap_or(0);
+
+                    if request.len() >= split + 4 + length {
+                        break;
+                    }
+                }
+
+                if n == 0 {
+                    break;
+                }
+            }
+
+            let response = format!["HTTP/1.1 {status}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"];
+            stream.write_all(response.as_bytes()).unwrap();
+
+            String::from_utf8_lossy(&request).to_string()
+        });
+
+        (url, handle)
+    }
+
+    fn settings(url: &str) -> UserChannel {
+        UserChannel {
+            id: String::new(),
+            user_id: String::from("user-1"),
+            channel: String::from(CHANNEL_WEBHOOK),
+            enabled: String::from("true"),
+            url: Some(String::from(url)),
+            headers: Some(String::from("{\"X-Token\":\"secret\"}")),
+        }
+    }
+
+    fn message() -> OutboxMessage {
+        let user = User {
+            id: String::from("user-1"),
+            username: String::from("user"),
+            password: String::from("password"),
+            email: String::from("user@domain.com"),
+            utc_offset: None,
+        };
+
+        OutboxMessage::new(None, &user, CHANNEL_WEBHOOK, "Task Reminder!", "Pay rent", NaiveDateTime::MIN)
+    }
+
+    /* the stand-in runs on loopback, which only an allowed host can reach */
+    fn local() -> Vec<String> {
+        vec![String::from("127.0.0.1")]
+    }
+
+    #[test]
+    fn notify_posts_json() {
+        let (url, handle) = stand_in("200 OK");
+        let result = Webhook::new(&local()).notify(&message(), Some(&settings(&url)));
+        let request = handle.join().unwrap();
+
+        assert!(result.is_ok());
+        assert!(request.starts_with("POST /hook HTTP/1.1"));
+        assert!(request.to_lowercase().contains("x-token: secret"));
+        assert!(request.to_lowercase().contains("content-type: application/json"));
+        assert!(request.contains("\"subject\":\"Task Reminder!\"") && request.contains("\"body\":\"Pay rent\""));
+    }
+
+    #[test]
+    fn notify_error_status() {
+        let (url, handle) = stand_in("500 Internal Server Error");
+        let result = Webhook::new(&local()).notify(&message(), Some(&settings(&url)));
+        handle.join().unwrap();
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn notify_refuses_internal() {
+        let result = Webhook::new(&[]).notify(&message(), Some(&settings("http://127.0.0.1:9/hook")));
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn notify_without_settings() {
+        assert!(Webhook::new(&local()).notify(&message(), None).is_err());
+    }
+}
\ No newline at end of file